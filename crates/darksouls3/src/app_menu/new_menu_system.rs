@@ -1,12 +1,13 @@
-use std::{borrow::Cow, ptr::NonNull};
+use std::ptr::NonNull;
 
-use shared::{FromStatic, Subclass, Superclass, UnknownStruct};
+use shared::{Subclass, Superclass, UnknownStruct};
 
 use super::{GaitemSelectBaseMenu, GaitemSelectMenu};
-use crate::{CxxVec, dlut::DLFixedVector, rva, sprj::SprjScaleformValue};
+use crate::{CxxVec, dlut::DLFixedVector, sprj::SprjScaleformValue};
 
 #[repr(C)]
 // Source of name: RTTI
+#[shared::singleton(rva = app_menu_new_menu_system_ptr, indirect)]
 pub struct NewMenuSystem {
     _vftable: usize,
     _array_menu_window_job_1: usize,
@@ -66,16 +67,6 @@ impl NewMenuSystem {
     }
 }
 
-impl FromStatic for NewMenuSystem {
-    fn name() -> Cow<'static, str> {
-        "NewMenuSystem".into()
-    }
-
-    fn instance_ptr() -> fromsoftware_shared::InstanceResult<*mut Self> {
-        unsafe { shared::load_static_indirect(rva::get().app_menu_new_menu_system_ptr) }
-    }
-}
-
 #[repr(C)]
 #[derive(Superclass)]
 #[superclass(children(GaitemSelectBaseMenu, GaitemSelectMenu))]
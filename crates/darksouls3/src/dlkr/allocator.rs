@@ -1,8 +1,11 @@
 use std::{
     alloc::{GlobalAlloc, Layout},
+    ffi::c_void,
     ptr::NonNull,
 };
 
+use fromsoftware_shared_stl::Allocator;
+use shared::{HeapSlot, RegisteredHeap};
 use vtable_rs::VPtr;
 
 #[vtable_rs::vtable]
@@ -54,3 +57,30 @@ unsafe impl GlobalAlloc for DLAllocatorRef {
         (allocator.vftable.deallocate)(allocator, ptr);
     }
 }
+
+impl Allocator for DLAllocatorRef {
+    unsafe fn allocate_raw(&mut self, size: usize, align: usize) -> *mut c_void {
+        let allocator = unsafe { self.0.as_ref() };
+        (allocator.vftable.allocate_aligned)(allocator, size, align) as *mut c_void
+    }
+
+    unsafe fn deallocate_raw(&mut self, ptr: *mut c_void) {
+        let allocator = unsafe { self.0.as_ref() };
+        (allocator.vftable.deallocate)(allocator, ptr as *mut u8);
+    }
+}
+
+impl RegisteredHeap for DLAllocatorRef {
+    fn slot() -> &'static HeapSlot<Self> {
+        static SLOT: HeapSlot<DLAllocatorRef> = HeapSlot::new();
+        &SLOT
+    }
+}
+
+/// A [GameAllocator](shared::GameAllocator) that allocates from and frees to
+/// a [DLAllocatorRef] registered at runtime.
+///
+/// The game's global heap allocators haven't been mapped for this game yet, so
+/// one has to be taken from a game structure that holds a [DLAllocatorRef] and
+/// passed to [RegisteredHeapAllocator::register] before this can allocate.
+pub type RegisteredHeapAllocator = shared::RegisteredHeapAllocator<DLAllocatorRef>;
@@ -1,10 +1,13 @@
-use std::{ptr::NonNull, slice};
+use std::{fmt::Display, ptr::NonNull, slice, str::FromStr};
 
 use thiserror::Error;
 
 use super::FieldArea;
 use crate::cxx_stl::CxxVec;
-use shared::{FromStatic, OwnedPtr, UnknownStruct};
+use shared::{
+    EventFlagLayout, EventFlagSnapshot, EventFlagStorage, FromStatic, OwnedPtr,
+    ParseEventFlagError, UnknownStruct,
+};
 
 #[derive(Debug, Error, PartialEq, Eq)]
 pub enum EventFlagError {
@@ -18,7 +21,7 @@ pub enum EventFlagError {
 }
 
 /// A handle pointing to a one-bit event flag in the game's event storage.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct EventFlag(u32);
 
 /// A valid event flag
@@ -64,18 +67,24 @@ impl EventFlag {
     /// Gets the block index to use when there's no global FieldArea currently
     /// available.
     fn global_block_index(&self) -> Option<u8> {
-        Some(match (self.area(), self.group()) {
-            (12, 1) => 0,
-            (20, 1) => 1,
-            (21, 0) => 3,
-            (29, 0) => 4,
-            (29, 1) => 5,
-            (29, 2) => 6,
-            _ => return None,
-        })
+        GLOBAL_BLOCKS
+            .iter()
+            .find(|(area, group, _)| (*area, *group) == (self.area(), self.group()))
+            .map(|(_, _, index)| *index)
     }
 }
 
+/// The area, group, and block index of each [EventBlock] that's available when
+/// there's no global FieldArea.
+const GLOBAL_BLOCKS: [(u8, u8, u8); 6] = [
+    (12, 1, 0),
+    (20, 1, 1),
+    (21, 0, 3),
+    (29, 0, 4),
+    (29, 1, 5),
+    (29, 2, 6),
+];
+
 impl TryFrom<u32> for EventFlag {
     type Error = EventFlagError;
 
@@ -99,6 +108,33 @@ impl From<EventFlag> for u32 {
     }
 }
 
+impl EventFlagLayout for EventFlag {
+    const CHUNK_SIZE: u32 = 1000;
+    type Error = EventFlagError;
+
+    fn from_id(id: u32) -> Result<Self, Self::Error> {
+        id.try_into()
+    }
+
+    fn id(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Display for EventFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for EventFlag {
+    type Err = ParseEventFlagError<EventFlagError>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
 #[repr(C)]
 // Source of name: FD4Singleton error handling
 /// The singleton that manages the game's event flags.
@@ -139,6 +175,68 @@ impl SprjEventFlagMan {
             .unwrap_or_default()
     }
 
+    /// Copies the state of every flag in the [EventZone]s that can currently
+    /// be accessed.
+    pub fn snapshot(&self) -> EventFlagSnapshot<EventFlag> {
+        let mut snapshot = EventFlagSnapshot::new();
+        for (area, group) in self.event_block_ids() {
+            for region in 0..10 {
+                for zone in 0..10 {
+                    let id = region * 10000000 + area as u32 * 100000 + group as u32 * 10000;
+                    let Ok(flag) = EventFlag::try_from(id + zone * 1000) else {
+                        continue;
+                    };
+                    if let Some(zone) = self.get_event_zone(flag) {
+                        let bits = zone.words.iter().flat_map(|word| word.to_be_bytes());
+                        snapshot.insert_chunk(flag.chunk(), bits.collect::<Vec<_>>());
+                    }
+                }
+            }
+        }
+        snapshot
+    }
+
+    /// Overwrites every [EventZone] in `snapshot` with its recorded state.
+    /// Zones that can't currently be accessed are skipped.
+    pub fn restore(&mut self, snapshot: &EventFlagSnapshot<EventFlag>) {
+        for (chunk, bits) in snapshot.chunks() {
+            let Some(flag) = chunk
+                .checked_mul(EventFlag::CHUNK_SIZE)
+                .and_then(|id| EventFlag::try_from(id).ok())
+            else {
+                continue;
+            };
+            if let Some(zone) = self.get_event_zone_mut(flag) {
+                let words = bits
+                    .chunks_exact(4)
+                    .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+                for (word, value) in zone.words.iter_mut().zip(words) {
+                    *word = value;
+                }
+            }
+        }
+    }
+
+    /// Returns the area and group of every [EventBlock] that can currently be
+    /// accessed.
+    fn event_block_ids(&self) -> Vec<(u8, u8)> {
+        let mut ids = vec![(0, 0)];
+        // Safety: If the event man is being accessed safely, the field area
+        // should be accessible as well.
+        if let Ok(field_area) = unsafe { FieldArea::instance() } {
+            ids.extend(
+                field_area
+                    .world_info_owner
+                    .area_and_block_info()
+                    .flat_map(|(_, block_infos)| block_infos.iter())
+                    .map(|bi| (bi.block_id.area(), bi.block_id.group())),
+            );
+        } else {
+            ids.extend(GLOBAL_BLOCKS.iter().map(|(area, group, _)| (*area, *group)));
+        }
+        ids
+    }
+
     /// Returns the [EventZone] that contains the data for the given
     /// [EventFlag].
     pub fn get_event_zone(&self, flag: EventFlag) -> Option<&EventZone> {
@@ -187,6 +285,20 @@ impl SprjEventFlagMan {
     }
 }
 
+impl EventFlagStorage for SprjEventFlagMan {
+    type Flag = EventFlag;
+    type Bits = [u32];
+
+    fn flag_bits(&self, flag: EventFlag) -> Option<&[u32]> {
+        self.get_event_zone(flag).map(|zone| &zone.words[..])
+    }
+
+    fn flag_bits_mut(&mut self, flag: EventFlag) -> Option<&mut [u32]> {
+        self.get_event_zone_mut(flag)
+            .map(|zone| &mut zone.words[..])
+    }
+}
+
 #[repr(C)]
 // Source of name: Elden Ring RTTI
 /// The container for the actual event data.
@@ -313,4 +425,25 @@ mod test {
         assert_eq!(0x230, size_of::<FD4VirtualMemoryFlag>());
         assert_eq!(0x278, size_of::<SprjEventFlagMan>());
     }
+
+    #[test]
+    fn snapshot_bit_order() {
+        // Each zone is snapshotted as its words in big-endian order.
+        let mut words = [0u32; 32];
+        for id in [13000000, 13000031, 13000033] {
+            let flag = EventFlag::try_from(id).unwrap();
+            words[flag.word() as usize] |= 1 << flag.bit();
+        }
+
+        let mut snapshot = EventFlagSnapshot::new();
+        let bits = words.iter().flat_map(|word| word.to_be_bytes());
+        snapshot.insert_chunk(13000, bits.collect::<Vec<_>>());
+        for (id, state) in [(13000000, true), (13000001, false), (13000031, true)] {
+            assert_eq!(Some(state), snapshot.get(EventFlag::try_from(id).unwrap()));
+        }
+        assert_eq!(
+            Some(true),
+            snapshot.get(EventFlag::try_from(13000033).unwrap())
+        );
+    }
 }
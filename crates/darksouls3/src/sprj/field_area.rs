@@ -1,10 +1,10 @@
-use std::{borrow::Cow, ptr::NonNull};
+use std::ptr::NonNull;
 
 use super::WorldInfoOwner;
-use crate::rva;
 use shared::*;
 
 #[repr(C)]
+#[shared::singleton(rva = field_area_ptr, indirect)]
 pub struct FieldArea {
     _vftable: usize,
 
@@ -24,13 +24,3 @@ pub struct FieldArea {
     _unke0: usize,
     _unke8: [u8; 8],
 }
-
-impl FromStatic for FieldArea {
-    fn name() -> Cow<'static, str> {
-        "FieldArea".into()
-    }
-
-    fn instance_ptr() -> InstanceResult<*mut Self> {
-        unsafe { shared::load_static_indirect(rva::get().field_area_ptr) }
-    }
-}
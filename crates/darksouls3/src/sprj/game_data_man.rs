@@ -1,13 +1,12 @@
-use std::borrow::Cow;
-
 use pelite::pe64::Pe;
-use shared::{FromStatic, InstanceResult, OwnedPtr, Program};
+use shared::{OwnedPtr, Program};
 
 use super::{ItemId, PlayerGameData};
 use crate::rva;
 
 #[repr(C)]
 /// Source of name: RTTI
+#[shared::singleton(rva = game_data_man_ptr, indirect)]
 pub struct GameDataMan {
     _vftable: usize,
     _trophy_equip_data: usize,
@@ -61,17 +60,6 @@ impl GameDataMan {
     }
 }
 
-impl FromStatic for GameDataMan {
-    fn name() -> Cow<'static, str> {
-        "GameDataMan".into()
-    }
-
-    /// Returns the singleton instance of `GameDataMan`.
-    fn instance_ptr() -> InstanceResult<*mut Self> {
-        unsafe { shared::load_static_indirect(rva::get().game_data_man_ptr) }
-    }
-}
-
 #[repr(C)]
 pub struct GameDataManBloodstain {
     /// The coordinates of the bloodstain.
@@ -1,29 +1,19 @@
 use std::alloc::{Layout, LayoutError, alloc_zeroed};
-use std::{borrow::Cow, fmt, ops, ptr, sync::LazyLock};
+use std::{fmt, ops, ptr, sync::LazyLock};
 
 use pelite::pe64::Pe;
 
-use shared::{FromStatic, InstanceResult, Program, util::IncompleteArrayField};
+use shared::{Program, util::IncompleteArrayField};
 
 use super::ItemId;
 use crate::rva;
 
 #[repr(C)]
+#[shared::singleton(rva = map_item_man_ptr, indirect)]
 pub struct MapItemMan {
     // TODO: actual data
 }
 
-impl FromStatic for MapItemMan {
-    fn name() -> Cow<'static, str> {
-        "MapItemMan".into()
-    }
-
-    /// Returns the singleton instance of `MapItemMan`.
-    fn instance_ptr() -> InstanceResult<*mut Self> {
-        unsafe { shared::load_static_indirect(rva::get().map_item_man_ptr) }
-    }
-}
-
 /// The address for the function call that grants an item to the player with a
 /// visible popup. Callers can use this to hook the function for their needs.
 ///
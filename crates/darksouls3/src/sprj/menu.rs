@@ -1,13 +1,14 @@
-use std::{borrow::Cow, ptr::NonNull};
+use std::ptr::NonNull;
 
 use pelite::pe64::Pe;
-use shared::{FromStatic, InstanceResult, OwnedPtr, Program};
+use shared::{OwnedPtr, Program};
 
 use super::{ItemCategoryHigh, ItemId};
 use crate::rva;
 
 // Source of name: RTTI
 #[repr(C)]
+#[shared::singleton(rva = item_get_menu_man_ptr, indirect, name = "ItemGetMan")]
 pub struct ItemGetMenuMan {
     _vftable: usize,
     pub used_display: Option<NonNull<ItemGetMenuManDisplay>>,
@@ -39,16 +40,6 @@ impl ItemGetMenuMan {
     }
 }
 
-impl FromStatic for ItemGetMenuMan {
-    fn name() -> Cow<'static, str> {
-        "ItemGetMan".into()
-    }
-
-    fn instance_ptr() -> InstanceResult<*mut Self> {
-        unsafe { shared::load_static_indirect(rva::get().item_get_menu_man_ptr) }
-    }
-}
-
 #[repr(C)]
 pub struct ItemGetMenuManDisplay {
     pub next: Option<NonNull<ItemGetMenuManDisplay>>,
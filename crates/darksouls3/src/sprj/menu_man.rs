@@ -1,13 +1,14 @@
-use std::{borrow::Cow, mem, ptr::NonNull};
+use std::{mem, ptr::NonNull};
 
-use shared::empty::{IsEmpty, MaybeEmpty};
-use shared::{FromStatic, UnknownStruct};
+use shared::empty::MaybeEmpty;
+use shared::{IsEmpty, UnknownStruct};
 
 use super::{ItemCategoryHigh, ItemId};
-use crate::{fd4::FD4Time, rva};
+use crate::fd4::FD4Time;
 
 // Source of name: RTTI
 #[repr(C)]
+#[shared::singleton(rva = sprj_menu_man_ptr, indirect)]
 pub struct MenuMan {
     _vftable: usize,
     _unk08: u64,
@@ -175,17 +176,9 @@ impl MenuMan {
     }
 }
 
-impl FromStatic for MenuMan {
-    fn name() -> Cow<'static, str> {
-        "MenuMan".into()
-    }
-
-    fn instance_ptr() -> fromsoftware_shared::InstanceResult<*mut Self> {
-        unsafe { shared::load_static_indirect(rva::get().sprj_menu_man_ptr) }
-    }
-}
-
 #[repr(C)]
+#[derive(IsEmpty)]
+#[empty(field = category, as = i32, value = -1)]
 pub struct GrantItemCommand {
     // We require that these collectively produce a valid ItemId.
     category: ItemCategoryHigh,
@@ -225,12 +218,6 @@ impl From<ItemId> for GrantItemCommand {
     }
 }
 
-unsafe impl IsEmpty for GrantItemCommand {
-    fn is_empty(value: &MaybeEmpty<Self>) -> bool {
-        *unsafe { value.as_non_null().cast::<i32>().as_ref() } == -1
-    }
-}
-
 #[cfg(test)]
 mod test {
     use crate::sprj::MenuMan;
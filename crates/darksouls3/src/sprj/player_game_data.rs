@@ -2,7 +2,7 @@ use std::ops::{Index, IndexMut};
 use std::{borrow::Cow, iter, num::NonZero, ptr::NonNull, slice};
 
 use bitfield::bitfield;
-use shared::{FromStatic, InstanceResult, OwnedPtr, empty::*};
+use shared::{FromStatic, InstanceResult, IsEmpty, OwnedPtr, empty::*};
 
 use crate::CxxVec;
 use crate::sprj::{ItemGetMenuMan, ItemId, OptionalItemId, PlayerIns};
@@ -436,6 +436,8 @@ pub type ItemsIteratorMut<'a> = NonEmptyIterMut<
 
 /// An entry in [InventoryItemsData].
 #[repr(C)]
+#[derive(IsEmpty)]
+#[empty(field = item_id, as = OptionalItemId, with = |id: &OptionalItemId| !id.is_valid())]
 pub struct EquipInventoryDataListEntry {
     /// Handle to the gaitem instance which describes additional properties of
     /// the inventory item, like durability.
@@ -451,13 +453,6 @@ pub struct EquipInventoryDataListEntry {
     _unk0c: [u8; 4],
 }
 
-unsafe impl IsEmpty for EquipInventoryDataListEntry {
-    fn is_empty(value: &MaybeEmpty<EquipInventoryDataListEntry>) -> bool {
-        !OptionalItemId::from(unsafe { *value.as_non_null().cast::<u32>().offset(1).as_ref() })
-            .is_valid()
-    }
-}
-
 #[repr(C)]
 pub struct ItemIdMapping {
     /// The ID of the item whose mapping this represents. This is invalid if
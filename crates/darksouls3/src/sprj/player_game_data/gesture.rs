@@ -1,9 +1,6 @@
-use std::borrow::Cow;
-
 use bitfield::bitfield;
 
-use crate::rva;
-use shared::{FromStatic, InstanceResult};
+use shared::FromStatic;
 
 bitfield! {
     /// The handle providing information about a single gesture in the player's
@@ -69,22 +66,13 @@ impl GestureGameData {
 
 /// A static, global store of information about each gesture in the game.
 #[repr(C)]
+#[shared::singleton(rva = gesture_data_store)]
 pub struct GestureDataStore {
     /// The contents of the store. The first entry contains meaningless data.
     /// Note that these are *not* in ID order.
     pub entries: [GestureDataStoreEntry; 41],
 }
 
-impl FromStatic for GestureDataStore {
-    fn name() -> Cow<'static, str> {
-        "GestureDataStore".into()
-    }
-
-    fn instance_ptr() -> InstanceResult<*mut Self> {
-        shared::load_static_direct(rva::get().gesture_data_store)
-    }
-}
-
 /// An entry describing global properties of a gesture.
 #[repr(C)]
 pub struct GestureDataStoreEntry {
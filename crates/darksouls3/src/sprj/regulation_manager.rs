@@ -222,9 +222,7 @@ impl CSRegulationManager {
         match id.category() {
             Weapon => self
                 .get_param::<EQUIP_PARAM_WEAPON_ST>()
-                // Round to the nearest 100 in case the ID is for an upgraded
-                // weapon.
-                .get((u64::from(id.param_id()) / 100) * 100)
+                .get(id.equip_param_id().into())
                 .map(|p| p.as_enum()),
             Protector => self
                 .get_param::<EQUIP_PARAM_PROTECTOR_ST>()
@@ -248,9 +246,7 @@ impl CSRegulationManager {
         match id.category() {
             Weapon => self
                 .get_mut_param::<EQUIP_PARAM_WEAPON_ST>()
-                // Round to the nearest 100 in case the ID is for an upgraded
-                // weapon.
-                .get_mut((u64::from(id.param_id()) / 100) * 100)
+                .get_mut(id.equip_param_id().into())
                 .map(|p| p.as_enum_mut()),
             Protector => self
                 .get_mut_param::<EQUIP_PARAM_PROTECTOR_ST>()
@@ -1,5 +1,5 @@
+use std::sync::LazyLock;
 use std::time::{Duration, Instant};
-use std::{sync::LazyLock, thread};
 
 use pelite::pe64::Pe;
 use shared::{FromStatic, InstanceError, OwnedPtr, RecurringTask, SharedTaskImp, program::Program};
@@ -26,6 +26,10 @@ impl SprjTaskImp {
     /// thread is responsible for initializing this singleton in the first
     /// place.
     ///
+    /// Unlike [FromStatic::wait_for_instance], which this shadows, this waits
+    /// for system initialization first, so it can be called as soon as the
+    /// mod is loaded.
+    ///
     /// This returns [`SystemInitError::InvalidRva`] if either the global
     /// HINSTANCE RVA or the `SprjTaskImp` RVA aren't within the executable.
     ///
@@ -33,19 +37,13 @@ impl SprjTaskImp {
     pub fn wait_for_instance(timeout: Duration) -> Result<&'static Self, SystemInitError> {
         let start = Instant::now();
         wait_for_system_init(&Program::current(), timeout)?;
-
-        loop {
-            match unsafe { Self::instance() } {
-                Err(InstanceError::NotFound(_)) => return Err(SystemInitError::InvalidRva),
-                Err(InstanceError::Null(_)) => {
-                    if start.elapsed() > timeout {
-                        return Err(SystemInitError::Timeout);
-                    }
-                    thread::yield_now();
-                }
-                Ok(instance) => return Ok(instance),
-            }
+        if let Err(InstanceError::NotFound(_)) = Self::instance_ptr() {
+            return Err(SystemInitError::InvalidRva);
         }
+
+        let remaining = timeout.saturating_sub(start.elapsed());
+        unsafe { <Self as FromStatic>::wait_for_instance(remaining) }
+            .map_err(|_| SystemInitError::Timeout)
     }
 }
 
@@ -86,7 +84,7 @@ pub struct SprjTask {}
 
 #[repr(u32)]
 #[allow(non_camel_case_types)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SprjTaskGroupIndex {
     FrameBegin,
     FD4TaskMng,
@@ -1,12 +1,15 @@
 use std::{ptr::NonNull, slice};
 
-use shared::{OwnedPtr, empty::*};
+use shared::{IsEmpty, OwnedPtr};
 
 use crate::sprj::ChrSet;
 
 use super::{ChrIns, FieldInsSelector, WorldBlockInfo};
 
 #[repr(C)]
+#[derive(IsEmpty)]
+// We can't check the vtable because it can be set even for empty values.
+#[empty(field = mappings, as = usize, value = 0)]
 /// Source of name: RTTI
 pub struct WorldBlockChr {
     _vftable: usize,
@@ -36,20 +39,6 @@ pub struct WorldBlockChr {
     _unk134: u32,
 }
 
-unsafe impl IsEmpty for WorldBlockChr {
-    fn is_empty(value: &MaybeEmpty<WorldBlockChr>) -> bool {
-        *unsafe {
-            value
-                .as_non_null()
-                .cast::<usize>()
-                // Offset for mappings. We can't check the vtable because it can
-                // be set even for empty values.
-                .offset(0x16)
-                .as_ref()
-        } == 0
-    }
-}
-
 impl WorldBlockChr {
     /// Returns this information about this block.
     pub fn info(&self) -> &WorldBlockInfo {
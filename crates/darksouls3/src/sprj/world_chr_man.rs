@@ -1,6 +1,6 @@
 use std::{mem::MaybeUninit, ptr::NonNull, slice};
 
-use shared::{OwnedPtr, Subclass, UnknownStruct, empty::*};
+use shared::{IsEmpty, OwnedPtr, Subclass, UnknownStruct, empty::*};
 
 use super::{ChrIns, PlayerIns, ReplayGhostIns, WorldAreaInfo, WorldBlockChr, WorldInfoOwner};
 use crate::CxxVec;
@@ -187,6 +187,8 @@ where
 }
 
 #[repr(C)]
+#[derive(IsEmpty)]
+#[empty(field = chr, as = usize, value = 0)]
 /// Source of name: Copied from ER RTTI
 pub struct ChrSetEntry<T>
 where
@@ -203,15 +205,6 @@ where
     _unk30: usize,
 }
 
-unsafe impl<T> IsEmpty for ChrSetEntry<T>
-where
-    T: Subclass<ChrIns>,
-{
-    fn is_empty(value: &MaybeEmpty<ChrSetEntry<T>>) -> bool {
-        *unsafe { value.as_non_null().cast::<usize>().as_ref() } == 0
-    }
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
@@ -1,4 +1,10 @@
-use std::{mem::MaybeUninit, ptr::NonNull, slice};
+use std::{
+    fmt::{self, Display},
+    mem::MaybeUninit,
+    ptr::NonNull,
+    slice,
+    str::FromStr,
+};
 
 use bitfield::bitfield;
 
@@ -169,6 +175,59 @@ bitfield! {
     pub u8, area, _: 31, 24;
 }
 
+impl BlockId {
+    /// Constructs a [BlockId] for the map `mAA_GG_00_00`.
+    pub const fn from_parts(area: u8, group: u8) -> Self {
+        Self(((area as u32) << 24) | ((group as u32) << 16))
+    }
+}
+
+impl TryFrom<BlockId> for MapId {
+    type Error = BlockIdToMapIdError;
+
+    /// Converts a block ID to the map ID `mAA_GG_00_00`. Block IDs with any of
+    /// their low 16 bits set don't correspond to a map, so they're an error.
+    fn try_from(value: BlockId) -> Result<Self, Self::Error> {
+        if value.0 & 0xffff != 0 {
+            return Err(BlockIdToMapIdError(value.0));
+        }
+        Ok(MapId::new(value.area(), value.group(), 0, 0))
+    }
+}
+
+impl TryFrom<MapId> for BlockId {
+    type Error = ParseMapIdError;
+
+    /// Converts a map ID to a block ID. This game's maps always end in
+    /// `_00_00`, so any other map ID is an error.
+    fn try_from(value: MapId) -> Result<Self, Self::Error> {
+        if value.region != 0 || value.index != 0 {
+            return Err(ParseMapIdError::Unsupported(value));
+        }
+        Ok(Self::from_parts(value.area, value.block))
+    }
+}
+
+impl Display for BlockId {
+    /// Formats the block ID as a map ID, or in hex if it doesn't correspond to
+    /// one.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match MapId::try_from(*self) {
+            Ok(map_id) => map_id.fmt(f),
+            Err(_) => write!(f, "{:#010x}", self.0),
+        }
+    }
+}
+
+impl FromStr for BlockId {
+    type Err = ParseMapIdError;
+
+    /// Parses a block ID in the `mAA_GG_00_00` form.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<MapId>()?.try_into()
+    }
+}
+
 #[repr(C)]
 /// Source of name: RTTI
 pub struct WorldBlockInfo {
@@ -312,4 +371,27 @@ mod test {
         assert_eq!(0x1298, size_of::<WorldInfo>());
         assert_eq!(0xae90, size_of::<WorldRes>());
     }
+
+    #[test]
+    fn block_id_parse_and_display() {
+        let block_id: BlockId = "m30_01_00_00".parse().unwrap();
+        assert_eq!(30, block_id.area());
+        assert_eq!(1, block_id.group());
+        assert_eq!(BlockId::from_parts(30, 1), block_id);
+        assert_eq!("m30_01_00_00", block_id.to_string());
+        assert_eq!(
+            Err(ParseMapIdError::Unsupported(MapId::new(30, 1, 2, 0))),
+            "m30_01_02_00".parse::<BlockId>()
+        );
+    }
+
+    #[test]
+    fn block_id_low_bits_dont_convert() {
+        let block_id = BlockId(0x1e01_0005);
+        assert_eq!(
+            Err(BlockIdToMapIdError(0x1e01_0005)),
+            MapId::try_from(block_id)
+        );
+        assert_eq!("0x1e010005", block_id.to_string());
+    }
 }
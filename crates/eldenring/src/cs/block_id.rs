@@ -1,6 +1,7 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
 use bitfield::bitfield;
+use shared::{MapId, ParseMapIdError};
 
 bitfield! {
     #[repr(C)]
@@ -47,6 +48,162 @@ impl BlockId {
         let area = self.area();
         (50..89).contains(&area)
     }
+
+    /// Returns the kind of map this block is part of.
+    pub fn map_area(&self) -> MapArea {
+        MapArea::from_area(self.area())
+    }
+
+    /// Returns the scale of this overworld tile, or `None` if it isn't one of
+    /// the tiles of the base game or DLC overworld grid.
+    pub fn tile_scale(&self) -> Option<TileScale> {
+        if !self.map_area().is_overworld() {
+            return None;
+        }
+        TileScale::from_index(self.index())
+    }
+
+    /// Returns the overworld tile `dx` tiles along the X axis and `dz` tiles
+    /// along the Z axis from this one, at the same scale. Returns `None` if
+    /// this isn't an overworld tile or the neighbor is outside the grid.
+    pub fn neighbor(&self, dx: i8, dz: i8) -> Option<Self> {
+        self.tile_scale()?;
+        Some(Self::from_parts(
+            self.area(),
+            self.block().checked_add_signed(dx)?,
+            self.region().checked_add_signed(dz)?,
+            self.index(),
+        ))
+    }
+
+    /// Returns the up to eight overworld tiles that surround this one at the
+    /// same scale. This is empty if this isn't an overworld tile.
+    pub fn neighbors(&self) -> impl Iterator<Item = Self> {
+        let this = *self;
+        (-1..=1)
+            .flat_map(|dz| (-1..=1).map(move |dx| (dx, dz)))
+            .filter(|&offset| offset != (0, 0))
+            .filter_map(move |(dx, dz)| this.neighbor(dx, dz))
+    }
+
+    /// Returns the overworld tile at `scale` that contains this one, which may
+    /// be this tile itself. Returns `None` if this isn't an overworld tile or
+    /// `scale` is smaller than its own.
+    pub fn containing_tile(&self, scale: TileScale) -> Option<Self> {
+        let shift = (scale as u8).checked_sub(self.tile_scale()? as u8)?;
+        Some(Self::from_parts(
+            self.area(),
+            self.block() >> shift,
+            self.region() >> shift,
+            scale as u8,
+        ))
+    }
+}
+
+/// The kind of map an area number refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MapArea {
+    /// A legacy dungeon, such as Stormveil Castle (m10) or Shadow Keep (m21).
+    ///
+    /// This covers every area from m10 to m29 other than the underground, so
+    /// it includes hand-made maps that aren't dungeons, like the Stranded
+    /// Graveyard (m18) and the Elden Beast's arena (m19).
+    LegacyDungeon,
+    /// The underground regions below the Lands Between, such as Siofra River
+    /// and Nokron (m12).
+    Underground,
+    /// The base game's overworld grid, the Lands Between (m60).
+    Overworld,
+    /// The DLC's overworld grid, the Realm of Shadow (m61).
+    DlcOverworld,
+    /// Catacombs and hero's graves (m30, and m40 in the DLC).
+    Catacomb,
+    /// Caves (m31, and m43 in the DLC).
+    Cave,
+    /// Tunnels (m32).
+    Tunnel,
+    /// Divine towers (m34).
+    DivineTower,
+    /// The Subterranean Shunning-Grounds (m35).
+    SewerDungeon,
+    /// Evergaols (m39).
+    Evergaol,
+    /// The DLC's gaols (m41).
+    Gaol,
+    /// The DLC's ruined forges (m42).
+    RuinedForge,
+    /// The colosseums (m45).
+    Colosseum,
+    /// An area that isn't any of the above.
+    Other(u8),
+}
+
+impl MapArea {
+    /// Classifies the area number of a [BlockId].
+    pub fn from_area(area: u8) -> Self {
+        match area {
+            12 => Self::Underground,
+            10..=29 => Self::LegacyDungeon,
+            30 | 40 => Self::Catacomb,
+            31 | 43 => Self::Cave,
+            32 => Self::Tunnel,
+            34 => Self::DivineTower,
+            35 => Self::SewerDungeon,
+            39 => Self::Evergaol,
+            41 => Self::Gaol,
+            42 => Self::RuinedForge,
+            45 => Self::Colosseum,
+            60 => Self::Overworld,
+            61 => Self::DlcOverworld,
+            other => Self::Other(other),
+        }
+    }
+
+    /// Returns whether this is one of the overworld grids.
+    pub fn is_overworld(&self) -> bool {
+        matches!(self, Self::Overworld | Self::DlcOverworld)
+    }
+}
+
+/// The scale of an overworld tile, stored in its [BlockId::index].
+///
+/// Each scale covers twice as many meters along each axis as the one before
+/// it, and the game loads the larger tiles as lower-detail versions of the
+/// smaller ones they contain.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TileScale {
+    /// 256 meter tiles such as `m60_42_36_00`.
+    Small = 0,
+    /// 512 meter tiles such as `m60_21_18_01`.
+    Medium = 1,
+    /// 1024 meter tiles such as `m60_10_09_02`.
+    Large = 2,
+}
+
+impl TileScale {
+    /// Returns the scale stored in an overworld [BlockId::index], if it's
+    /// valid.
+    pub fn from_index(index: u8) -> Option<Self> {
+        match index {
+            0 => Some(Self::Small),
+            1 => Some(Self::Medium),
+            2 => Some(Self::Large),
+            _ => None,
+        }
+    }
+
+    /// Returns the size of a tile at this scale along each axis, in meters.
+    pub fn size(&self) -> f32 {
+        256.0 * (1u32 << *self as u8) as f32
+    }
+
+    /// Returns the number of small tiles along each axis of a tile at this
+    /// scale.
+    pub fn small_tiles(&self) -> u8 {
+        1 << *self as u8
+    }
 }
 
 impl From<BlockId> for i32 {
@@ -61,22 +218,36 @@ impl From<i32> for BlockId {
     }
 }
 
+impl From<BlockId> for MapId {
+    fn from(val: BlockId) -> Self {
+        MapId::new(val.area(), val.block(), val.region(), val.index())
+    }
+}
+
+impl From<MapId> for BlockId {
+    fn from(value: MapId) -> Self {
+        Self::from_parts(value.area, value.block, value.region, value.index)
+    }
+}
+
 impl Display for BlockId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "m{:0>2}_{:0>2}_{:0>2}_{:0>2}",
-            self.area(),
-            self.block(),
-            self.region(),
-            self.index()
-        )
+        MapId::from(*self).fmt(f)
+    }
+}
+
+impl FromStr for BlockId {
+    type Err = ParseMapIdError;
+
+    /// Parses a block ID in the `mAA_BB_CC_DD` form.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<MapId>().map(Self::from)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::cs::BlockId;
+    use crate::cs::{BlockId, MapArea, TileScale};
 
     #[test]
     fn test_bitfield() {
@@ -93,4 +264,72 @@ mod tests {
 
         assert_eq!(blockid.0, 0x3D392703);
     }
+
+    #[test]
+    fn parse_and_display() {
+        let block_id: BlockId = "m60_42_36_00".parse().unwrap();
+        assert_eq!(BlockId::from_parts(60, 42, 36, 0), block_id);
+        assert_eq!("m60_42_36_00", block_id.to_string());
+        assert!("m60_42_36".parse::<BlockId>().is_err());
+        assert_eq!("m255_255_255_255", BlockId::none().to_string());
+    }
+
+    #[test]
+    fn map_areas() {
+        let area = |s: &str| s.parse::<BlockId>().unwrap().map_area();
+        assert_eq!(MapArea::LegacyDungeon, area("m10_00_00_00"));
+        assert_eq!(MapArea::LegacyDungeon, area("m21_00_00_00"));
+        assert_eq!(MapArea::Overworld, area("m60_42_36_00"));
+        assert_eq!(MapArea::DlcOverworld, area("m61_44_45_00"));
+        assert_eq!(MapArea::Catacomb, area("m30_00_00_00"));
+        assert_eq!(MapArea::Cave, area("m31_00_00_00"));
+        assert_eq!(MapArea::Tunnel, area("m32_00_00_00"));
+        assert_eq!(MapArea::Other(99), area("m99_00_00_00"));
+        assert_eq!(MapArea::Underground, area("m12_01_00_00"));
+        assert_eq!(MapArea::LegacyDungeon, area("m18_00_00_00"));
+        assert!("m61_44_45_00".parse::<BlockId>().unwrap().is_overworld());
+        assert!(!MapArea::from_area(50).is_overworld());
+        assert!("m50_00_00_00".parse::<BlockId>().unwrap().is_overworld());
+    }
+
+    #[test]
+    fn tile_scales() {
+        let tile = |s: &str| s.parse::<BlockId>().unwrap();
+        assert_eq!(Some(TileScale::Small), tile("m60_42_36_00").tile_scale());
+        assert_eq!(Some(TileScale::Large), tile("m60_10_09_02").tile_scale());
+        assert_eq!(None, tile("m60_42_36_03").tile_scale());
+        assert_eq!(None, tile("m10_00_00_00").tile_scale());
+        assert_eq!(None, tile("m50_00_00_00").tile_scale());
+        assert_eq!(1024.0, TileScale::Large.size());
+
+        assert_eq!(
+            Some(tile("m60_21_18_01")),
+            tile("m60_43_37_00").containing_tile(TileScale::Medium)
+        );
+        assert_eq!(
+            Some(tile("m60_10_09_02")),
+            tile("m60_21_18_01").containing_tile(TileScale::Large)
+        );
+        assert_eq!(None, tile("m60_21_18_01").containing_tile(TileScale::Small));
+    }
+
+    #[test]
+    fn neighbors() {
+        let tile = |s: &str| s.parse::<BlockId>().unwrap();
+        assert_eq!(
+            Some(tile("m60_43_35_00")),
+            tile("m60_42_36_00").neighbor(1, -1)
+        );
+        assert_eq!(8, tile("m60_42_36_00").neighbors().count());
+        assert_eq!(
+            vec![
+                tile("m60_01_00_01"),
+                tile("m60_00_01_01"),
+                tile("m60_01_01_01")
+            ],
+            tile("m60_00_00_01").neighbors().collect::<Vec<_>>()
+        );
+        assert_eq!(None, tile("m10_00_00_00").neighbor(1, 0));
+        assert_eq!(0, tile("m10_00_00_00").neighbors().count());
+    }
 }
@@ -1,7 +1,3 @@
-use std::borrow::Cow;
-
-use shared::{FromStatic, load_static_direct};
-
 use crate::cs::ChrType;
 
 #[repr(C)]
@@ -42,17 +38,8 @@ pub struct CharacterTypePropertiesEntry {
 }
 
 #[repr(C)]
+#[shared::singleton(rva = character_type_properties)]
 pub struct CharacterTypePropertiesTable {
     pub entries: [CharacterTypePropertiesEntry; 22],
     pub default: CharacterTypePropertiesEntry,
 }
-
-impl FromStatic for CharacterTypePropertiesTable {
-    fn name() -> Cow<'static, str> {
-        Cow::Borrowed("CharacterTypePropertiesTable")
-    }
-
-    fn instance_ptr() -> shared::InstanceResult<*mut Self> {
-        load_static_direct(crate::rva::get().character_type_properties)
-    }
-}
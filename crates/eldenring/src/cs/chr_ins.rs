@@ -19,8 +19,8 @@ use crate::position::{BlockPosition, HavokPosition};
 use crate::rva;
 use shared::program::Program;
 use shared::{
-    Aabb, F32Matrix4x4, F32ModelMatrix, F32Vector3, F32Vector4, FromStatic, InstanceError,
-    InstanceResult, OwnedPtr, Subclass, Superclass, for_all_subclasses,
+    Aabb, F32Matrix4x4, F32ModelMatrix, F32Vector3, F32Vector4, FromStatic, GameLayout,
+    InstanceError, InstanceResult, OwnedPtr, Subclass, Superclass, for_all_subclasses,
 };
 
 mod module;
@@ -117,8 +117,9 @@ pub enum OmissionMode {
 }
 
 #[repr(C)]
-#[derive(Superclass)]
+#[derive(Superclass, GameLayout)]
 #[superclass(children(PlayerIns, EnemyIns))]
+#[layout(size = 0x580)]
 /// Abstract base class to all characters. NPCs, Enemies, Players, Summons, Ghosts, even gesturing
 /// character on bloodmessages inherit from this.
 ///
@@ -291,6 +292,7 @@ pub struct ChrIns {
     /// Whether this character's position has been synchronized over the network.
     /// Will be set by NetAIManipulator after receiving a position update.
     pub net_position_synchronized: bool,
+    _pad365: [u8; 0x3],
     unk368: [u8; 0x20],
     last_received_packet60: u32,
     unk38c: [u8; 0xc],
@@ -1,6 +1,10 @@
+use std::ptr;
+
 use vtable_rs::VPtr;
 
-use shared::{OwnedPtr, Subclass, Superclass};
+use shared::{Subclass, Superclass};
+
+mod sfmt;
 
 #[vtable_rs::vtable]
 pub trait CSRandVmt {
@@ -176,20 +180,116 @@ pub struct CSRandSFMT {
     pub state: DLRandomGeneratorSFMT,
 }
 
+impl CSRandSFMT {
+    /// Creates a generator seeded with [DLRandomGeneratorSFMT::seed].
+    ///
+    /// Unlike [CSRandXorshift::new], this returns a box, because the
+    /// generator's state pointers point into the generator itself and would
+    /// dangle if it were moved.
+    pub fn new(seed: u32) -> Box<Self> {
+        let vftable = VPtr::<dyn CSRandVmt, CSRandSFMT>::new();
+        let mut rand = Box::new(CSRandSFMT {
+            base: CSRand {
+                // SAFETY: This vftable is only ever called with this
+                // `CSRandSFMT`, which starts with its `CSRand`.
+                vftable: unsafe {
+                    std::mem::transmute::<
+                        VPtr<dyn CSRandVmt, CSRandSFMT>,
+                        VPtr<dyn CSRandVmt, CSRand>,
+                    >(vftable)
+                },
+            },
+            unk8: 0,
+            state: DLRandomGeneratorSFMT {
+                state: [0; 624],
+                index: 0,
+                mt_state_ptr: ptr::null_mut(),
+                mt_state_end_ptr: ptr::null_mut(),
+            },
+        });
+        rand.state.seed(seed);
+        rand
+    }
+}
+
+/// The game's SFMT-19937 generator.
+///
+/// The outputs match the SFMT reference implementation's known answers, but
+/// haven't been compared against values read from a live `CSRandSFMT` yet.
+/// Neither has [Self::seed], which uses the reference implementation's
+/// `init_gen_rand`.
 #[repr(C)]
 pub struct DLRandomGeneratorSFMT {
     pub state: [u32; 624],
     pub index: u32,
-    pub mt_state_ptr: OwnedPtr<u32>,
-    pub mt_state_end_ptr: OwnedPtr<u32>,
+    /// Points to the start of [Self::state], like the reference
+    /// implementation's `psfmt32`. Generating numbers doesn't read or update
+    /// this, so it's only set by [Self::seed].
+    pub mt_state_ptr: *mut u32,
+    /// Points just past the end of [Self::state]. Like [Self::mt_state_ptr],
+    /// this is only set by [Self::seed].
+    pub mt_state_end_ptr: *mut u32,
+}
+
+impl DLRandomGeneratorSFMT {
+    /// Reseeds the generator using SFMT's standard 32-bit seeding, after
+    /// which the next output regenerates the whole state.
+    ///
+    /// This also points [Self::mt_state_ptr] and [Self::mt_state_end_ptr] at
+    /// [Self::state], so a generator owned by Rust mustn't be moved after it's
+    /// seeded.
+    pub fn seed(&mut self, seed: u32) {
+        self.index = sfmt::init_gen_rand(&mut self.state, seed);
+        self.sync_state_ptrs();
+    }
+
+    /// Returns the next 32-bit output of the SFMT-19937 generator.
+    pub fn next_u32(&mut self) -> u32 {
+        sfmt::gen_rand32(&mut self.state, &mut self.index)
+    }
+
+    /// Returns the next 64-bit output of the SFMT-19937 generator, made from
+    /// the next two 32-bit words of the state.
+    ///
+    /// Like the reference implementation, the first word is the low half of
+    /// the result. This is the opposite of [CSRandXorshift::next_long], which
+    /// puts the first word it generates in the high half. Which order the
+    /// game's `CSRandSFMT` uses hasn't been confirmed.
+    pub fn next_u64(&mut self) -> u64 {
+        sfmt::gen_rand64(&mut self.state, &mut self.index)
+    }
+
+    /// Points [Self::mt_state_ptr] and [Self::mt_state_end_ptr] at
+    /// [Self::state].
+    fn sync_state_ptrs(&mut self) {
+        let range = self.state.as_mut_ptr_range();
+        self.mt_state_ptr = range.start;
+        self.mt_state_end_ptr = range.end;
+    }
 }
 
 impl CSRandVmt for CSRandSFMT {
     extern "C" fn destructor(&mut self, _should_free: bool) {}
     extern "C" fn next_uint(&mut self) -> u32 {
-        unimplemented!()
+        self.state.next_u32()
     }
     extern "C" fn next_long(&mut self) -> u64 {
-        unimplemented!()
+        self.state.next_u64()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sfmt_new_seeds_in_place() {
+        let mut rand = CSRandSFMT::new(1234);
+        let range = rand.state.state.as_mut_ptr_range();
+        assert_eq!(range.start, rand.state.mt_state_ptr);
+        assert_eq!(range.end, rand.state.mt_state_end_ptr);
+
+        // The first output of the reference implementation for this seed.
+        assert_eq!(3440181298, rand.base.next_uint());
     }
 }
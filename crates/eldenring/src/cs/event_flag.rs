@@ -1,9 +1,14 @@
-use std::mem::ManuallyDrop;
+use std::{convert::Infallible, fmt::Display, mem::ManuallyDrop, str::FromStr};
 
 use crate::Tree;
-use shared::OwnedPtr;
+use shared::{EventFlagLayout, EventFlagSnapshot, EventFlagStorage, OwnedPtr, ParseEventFlagError};
 
+/// A handle pointing to a one-bit event flag in the game's event storage.
+///
+/// Flags are stored in groups of 1000 consecutive IDs. Any `u32` is a valid
+/// ID, although flags in groups the game doesn't define are never set.
 #[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct EventFlag(u32);
 
 impl From<u32> for EventFlag {
@@ -12,6 +17,12 @@ impl From<u32> for EventFlag {
     }
 }
 
+impl From<EventFlag> for u32 {
+    fn from(value: EventFlag) -> u32 {
+        value.0
+    }
+}
+
 impl EventFlag {
     pub fn group(&self) -> u32 {
         self.0 / 1000
@@ -26,6 +37,33 @@ impl EventFlag {
     }
 }
 
+impl EventFlagLayout for EventFlag {
+    const CHUNK_SIZE: u32 = 1000;
+    type Error = Infallible;
+
+    fn from_id(id: u32) -> Result<Self, Self::Error> {
+        Ok(EventFlag(id))
+    }
+
+    fn id(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Display for EventFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for EventFlag {
+    type Err = ParseEventFlagError<Infallible>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
 #[repr(C)]
 /// Manages the event flags for the game.
 ///
@@ -62,37 +100,49 @@ impl CSFD4VirtualMemoryFlag {
     /// Sets the event flag bit for a given event flag. Does not inherently network set flags.
     pub fn set_flag(&mut self, flag: impl Into<EventFlag>, state: bool) {
         let flag: EventFlag = flag.into();
-        let Some(group) = self
-            .flag_block_descriptors
-            .iter()
-            .find(|d| d.group == flag.group())
-        else {
-            return;
-        };
-
-        let Some(location) = self.flag_block(group) else {
-            return;
-        };
-
-        location.set(flag, state)
+        if let Some(location) = self.group_flag_block(flag.group()) {
+            location.set(flag, state)
+        }
     }
 
     /// Retrieves the event flag current state.
     pub fn get_flag(&self, flag: impl Into<EventFlag>) -> bool {
         let flag: EventFlag = flag.into();
-        let Some(group) = self
-            .flag_block_descriptors
-            .iter()
-            .find(|d| d.group == flag.group())
-        else {
-            return false;
-        };
+        self.group_flag_block(flag.group())
+            .is_some_and(|location| location.get(flag))
+    }
 
-        let Some(location) = self.flag_block(group) else {
-            return false;
-        };
+    /// Copies the state of every flag group the game has allocated.
+    pub fn snapshot(&self) -> EventFlagSnapshot<EventFlag> {
+        let mut snapshot = EventFlagSnapshot::new();
+        for descriptor in self.flag_block_descriptors.iter() {
+            let group = descriptor.group;
+            if let Some(block) = self.flag_block(descriptor) {
+                snapshot.insert_chunk(group, block.bytes);
+            }
+        }
+        snapshot
+    }
+
+    /// Overwrites every flag group in `snapshot` with its recorded state.
+    /// Groups that the game hasn't allocated are skipped. Does not inherently
+    /// network set flags.
+    pub fn restore(&mut self, snapshot: &EventFlagSnapshot<EventFlag>) {
+        for (group, bits) in snapshot.chunks() {
+            if let Some(block) = self.group_flag_block(group) {
+                let len = bits.len().min(block.bytes.len());
+                block.bytes[..len].copy_from_slice(&bits[..len]);
+            }
+        }
+    }
 
-        location.get(flag)
+    /// Locates the flag block for a given event flag group.
+    fn group_flag_block(&self, group: u32) -> Option<&mut FlagBlock> {
+        let descriptor = self
+            .flag_block_descriptors
+            .iter()
+            .find(|d| d.group == group)?;
+        self.flag_block(descriptor)
     }
 
     /// Locates a flag block for a given FlagBlockDescriptor.
@@ -109,6 +159,22 @@ impl CSFD4VirtualMemoryFlag {
     }
 }
 
+/// Setting flags through this does not inherently network them.
+impl EventFlagStorage for CSFD4VirtualMemoryFlag {
+    type Flag = EventFlag;
+    type Bits = [u8];
+
+    fn flag_bits(&self, flag: EventFlag) -> Option<&[u8]> {
+        self.group_flag_block(flag.group())
+            .map(|block| &block.bytes[..])
+    }
+
+    fn flag_bits_mut(&mut self, flag: EventFlag) -> Option<&mut [u8]> {
+        self.group_flag_block(flag.group())
+            .map(|block| &mut block.bytes[..])
+    }
+}
+
 #[repr(C)]
 /// Describes where to find a flag block.
 pub struct FlagBlockDescriptor {
@@ -154,3 +220,43 @@ impl FlagBlock {
         (*byte & mask) != 0
     }
 }
+
+#[cfg(test)]
+mod test {
+    use shared::{EventFlagLayout, EventFlagRange, EventFlagSnapshot};
+
+    use super::{EventFlag, FlagBlock};
+
+    #[test]
+    fn parse_and_display() {
+        let flag: EventFlag = "1040_0123".parse().unwrap();
+        assert_eq!(EventFlag::from(10400123), flag);
+        assert_eq!("10400123", flag.to_string());
+        assert_eq!(10400, flag.group());
+        assert!("flag".parse::<EventFlag>().is_err());
+    }
+
+    #[test]
+    fn flag_block_bits() {
+        let mut block = FlagBlock { bytes: [0; 125] };
+        for flag in EventFlagRange::<EventFlag>::new(10400000, 10400010) {
+            block.set(flag, flag.offset() % 3 == 0);
+        }
+        assert_eq!([0b1001_0010, 0b0100_0000], block.bytes[..2]);
+        assert!(block.get(EventFlag::from(10400009)));
+        assert!(!block.get(EventFlag::from(10400008)));
+    }
+
+    #[test]
+    fn snapshot_matches_flag_block() {
+        let mut block = FlagBlock { bytes: [0; 125] };
+        block.set(EventFlag::from(10400005), true);
+        block.set(EventFlag::from(10400999), true);
+
+        let mut snapshot = EventFlagSnapshot::new();
+        snapshot.insert_chunk(10400, block.bytes);
+        assert_eq!(Some(true), snapshot.get(EventFlag::from(10400005)));
+        assert_eq!(Some(true), snapshot.get(EventFlag::from(10400999)));
+        assert_eq!(Some(false), snapshot.get(EventFlag::from(10400006)));
+    }
+}
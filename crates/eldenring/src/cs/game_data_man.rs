@@ -4,8 +4,8 @@ use crate::{
     fd4::FD4Time,
 };
 use bitfield::bitfield;
-use shared::{FromStatic, OwnedPtr, load_static_indirect};
-use std::{borrow::Cow, ptr::NonNull};
+use shared::OwnedPtr;
+use std::ptr::NonNull;
 
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -39,6 +39,7 @@ pub enum DeathState {
 }
 
 #[repr(C)]
+#[shared::singleton(rva = game_data_man, indirect)]
 pub struct GameDataMan {
     pub trophy_equip_data: OwnedPtr<TrophyEquipData>,
     pub main_player_game_data: OwnedPtr<PlayerGameData>,
@@ -113,16 +114,6 @@ pub struct GameDataMan {
     unk124: [u8; 0x34],
 }
 
-impl FromStatic for GameDataMan {
-    fn name() -> Cow<'static, str> {
-        Cow::Borrowed("GameDataMan")
-    }
-
-    fn instance_ptr() -> shared::InstanceResult<*mut Self> {
-        unsafe { load_static_indirect(crate::rva::get().game_data_man) }
-    }
-}
-
 #[repr(C)]
 pub struct TrophyEquipData {
     vftable: usize,
@@ -1,15 +1,14 @@
-use std::borrow::Cow;
-
 use crate::cs::{
     BlockId, CSEzTask, CSEzUpdateTask, CSRandSFMT, CSRandXorshift, MultiplayRole, PartyMemberInfo,
     SummonParamType,
 };
 use crate::dlut::DLDateTime;
 use crate::position::BlockPosition;
-use fromsoftware_shared::{FromStatic, OwnedPtr, load_static_indirect};
+use fromsoftware_shared::OwnedPtr;
 use shared::{F32Vector3, F32Vector4};
 
 #[repr(C)]
+#[shared::singleton(rva = game_man, indirect)]
 pub struct GameMan {
     vftable: usize,
     unk8: usize,
@@ -196,16 +195,6 @@ pub enum ForceCamRotationMethod {
     Clockwise = 3,
 }
 
-impl FromStatic for GameMan {
-    fn name() -> Cow<'static, str> {
-        Cow::Borrowed("GameMan")
-    }
-
-    fn instance_ptr() -> fromsoftware_shared::InstanceResult<*mut Self> {
-        unsafe { load_static_indirect(crate::rva::get().game_man) }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::GameMan;
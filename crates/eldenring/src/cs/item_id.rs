@@ -1,24 +1,9 @@
-use std::{fmt, mem};
+use std::{fmt, mem, str::FromStr};
 
 use bitfield::bitfield;
-use thiserror::Error;
 
-/// An error indicating that a valid [ItemId] or [ItemCategory] couldn't be
-/// constructed.
-#[derive(Debug, Error, PartialEq, Eq)]
-pub enum ItemIdError {
-    /// The category, or the bits in the ID representing the category, is
-    /// invalid. This is always the error used when interpreting a [u32]
-    /// directly as an [ItemId].
-    #[error("Invalid item category {0}")]
-    InvalidCategory(u8),
-
-    /// The parameter ID isn't valid. Because the only valid parameter IDs are
-    /// those whose 1 bits overlap with the category, this error is only used
-    /// when supplying a parameter ID separately from a category.
-    #[error("Invalid param ID {0}")]
-    InvalidParamId(u32),
-}
+use shared::{ItemCategoryName, ItemIdNotation};
+pub use shared::{ItemIdError, ParseItemIdError};
 
 /// All item categories that correspond to an `EQUIP_PARAM_*` parameter table.
 #[repr(u8)]
@@ -46,6 +31,43 @@ impl TryFrom<u8> for ItemCategory {
     }
 }
 
+impl ItemCategoryName for ItemCategory {
+    const ALL: &'static [ItemCategory] = &[
+        ItemCategory::Weapon,
+        ItemCategory::Protector,
+        ItemCategory::Accessory,
+        ItemCategory::Goods,
+        ItemCategory::Gem,
+    ];
+
+    fn name(&self) -> &'static str {
+        use ItemCategory::*;
+        match self {
+            Weapon => "weapon",
+            Protector => "protector",
+            Accessory => "accessory",
+            Goods => "goods",
+            Gem => "gem",
+        }
+    }
+}
+
+impl fmt::Display for ItemCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ItemCategory {
+    type Err = ParseItemIdError;
+
+    /// Parses a category from its [name](ItemCategoryName::name), ignoring
+    /// case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ItemCategory::from_name(s.trim())
+    }
+}
+
 bitfield! {
     #[repr(transparent)]
     #[derive(Copy, Clone, PartialEq, Eq, Hash)]
@@ -140,6 +162,31 @@ impl fmt::Debug for OptionalItemId {
     }
 }
 
+impl fmt::Display for OptionalItemId {
+    /// Formats valid IDs the same way as [ItemId], and invalid IDs as `none`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.as_valid() {
+            Some(id) => fmt::Display::fmt(&id, f),
+            None => f.write_str("none"),
+        }
+    }
+}
+
+impl FromStr for OptionalItemId {
+    type Err = ParseItemIdError;
+
+    /// Parses any string accepted by [ItemId]'s [FromStr] implementation, as
+    /// well as `none`. Hex values with invalid categories are normalized to
+    /// [OptionalItemId::NONE].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.parse()? {
+            ItemIdNotation::Parts(category, param_id) => ItemId::new(category, param_id)?.into(),
+            ItemIdNotation::Raw(value) => value.into(),
+            ItemIdNotation::None => OptionalItemId::NONE,
+        })
+    }
+}
+
 /// An item ID that includes category information in the higher bits and is
 /// guaranteed to be valid.
 ///
@@ -183,6 +230,18 @@ impl ItemId {
         self.0.param_id_raw()
     }
 
+    /// Returns the ID of the row for this item in its `EQUIP_PARAM_*` table.
+    ///
+    /// This is the same as [param_id](Self::param_id), except that upgraded
+    /// weapons share the row of their base weapon, whose ID is rounded down to
+    /// the nearest 100.
+    pub fn equip_param_id(&self) -> u32 {
+        match self.category() {
+            ItemCategory::Weapon => (self.param_id() / 100) * 100,
+            _ => self.param_id(),
+        }
+    }
+
     /// Returns the underlying numeric value of the item ID.
     pub fn into_inner(self) -> u32 {
         self.0.into_inner()
@@ -222,9 +281,49 @@ impl fmt::Debug for ItemId {
     }
 }
 
+impl fmt::Display for ItemId {
+    /// Formats the ID as `category:param_id`, such as `weapon:1000000` or
+    /// `goods:2919`. Use the `{:#x}` format to get the raw hex value instead.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.category(), self.param_id())
+    }
+}
+
+impl fmt::LowerHex for ItemId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.into_inner(), f)
+    }
+}
+
+impl fmt::UpperHex for ItemId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.into_inner(), f)
+    }
+}
+
+impl FromStr for ItemId {
+    type Err = ParseItemIdError;
+
+    /// Parses either the `category:param_id` format produced by [Display],
+    /// such as `goods:2919`, or a raw hex value starting with `0x`, such as
+    /// `0x40000b67`. Category names are case-insensitive.
+    ///
+    /// [Display]: fmt::Display
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse()? {
+            ItemIdNotation::Parts(category, param_id) => Ok(ItemId::new(category, param_id)?),
+            ItemIdNotation::Raw(value) => Ok(ItemId::try_from(value)?),
+            ItemIdNotation::None => Err(ParseItemIdError::InvalidFormat),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+shared::serde_via_str!(ItemCategory, OptionalItemId, ItemId);
+
 #[cfg(test)]
 mod tests {
-    use crate::cs::{ItemCategory, OptionalItemId};
+    use crate::cs::{ItemCategory, ItemId, ItemIdError, OptionalItemId, ParseItemIdError};
 
     #[test]
     fn test_bitfield() {
@@ -240,4 +339,46 @@ mod tests {
         assert_eq!(item.param_id(), None);
         assert_eq!(item.category(), None);
     }
+
+    #[test]
+    fn parse_and_display() {
+        let id: ItemId = "goods:2919".parse().unwrap();
+        assert_eq!(ItemId::new(ItemCategory::Goods, 2919), Ok(id));
+        assert_eq!("goods:2919", id.to_string());
+        assert_eq!("0x40000b67", format!("{id:#x}"));
+        assert_eq!(Ok(id), "0x40000B67".parse());
+        assert_eq!(Ok(id), " Goods : 2919 ".parse());
+
+        let weapon: ItemId = "weapon:1000005".parse().unwrap();
+        assert_eq!("weapon:1000005", weapon.to_string());
+        assert_eq!(1000000, weapon.equip_param_id());
+
+        assert_eq!(
+            Err(ParseItemIdError::UnknownCategory("sword".to_string())),
+            "sword:1000000".parse::<ItemId>()
+        );
+        assert_eq!(
+            Err(ParseItemIdError::InvalidFormat),
+            "1000000".parse::<ItemId>()
+        );
+        assert_eq!(
+            Err(ParseItemIdError::Invalid(ItemIdError::InvalidCategory(15))),
+            "0xffffffff".parse::<ItemId>()
+        );
+        assert!(matches!(
+            "goods:abc".parse::<ItemId>(),
+            Err(ParseItemIdError::InvalidNumber(_))
+        ));
+    }
+
+    #[test]
+    fn parse_and_display_optional() {
+        assert_eq!(Ok(OptionalItemId::NONE), "none".parse());
+        assert_eq!(Ok(OptionalItemId::NONE), "0xffffffff".parse());
+        assert_eq!("none", OptionalItemId::NONE.to_string());
+
+        let id: OptionalItemId = "protector:10000".parse().unwrap();
+        assert_eq!(Some(ItemCategory::Protector), id.category());
+        assert_eq!("protector:10000", id.to_string());
+    }
 }
@@ -1,3 +1,8 @@
+use std::collections::HashMap;
+
+use crate::cs::CSRandVmt;
+use crate::param::ITEMLOT_PARAM_ST;
+
 #[repr(C)]
 pub struct ItemLotUtil {
     pub item_lot: u32,
@@ -9,3 +14,562 @@ pub struct ItemLotUtil {
     unk14: u32,
     unk18: usize,
 }
+
+/// The number of item slots in an [ITEMLOT_PARAM_ST] row.
+pub const ITEM_LOT_SLOTS: usize = 8;
+
+/// A single item slot of an [ITEMLOT_PARAM_ST] row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ItemLotSlot {
+    /// The index of this slot within the row, from 0 to 7.
+    pub index: usize,
+    pub item_id: i32,
+    pub category: i32,
+    pub count: u8,
+    /// The slot's weight relative to the other slots in the row.
+    pub base_point: u16,
+    /// The slot's weight once the lot's cumulate count reaches its maximum.
+    pub cumulate_point: u16,
+    /// Whether awarding this slot resets the lot's cumulate count.
+    pub cumulate_reset: bool,
+    /// Whether the player's item discovery affects this slot's weight.
+    pub enable_luck: bool,
+    /// The event flag that suppresses this slot once it's set, or 0.
+    pub get_item_flag_id: u32,
+}
+
+impl ItemLotSlot {
+    /// Reads slot `index` of `lot`.
+    ///
+    /// ## Panics
+    ///
+    /// If `index` is [ITEM_LOT_SLOTS] or greater.
+    pub fn from_param(lot: &ITEMLOT_PARAM_ST, index: usize) -> Self {
+        macro_rules! slot {
+            (
+                $id:ident,
+                $category:ident,
+                $base:ident,
+                $cumulate:ident,
+                $reset:ident,
+                $num:ident,
+                $luck:ident,
+                $flag:ident
+            ) => {
+                ItemLotSlot {
+                    index,
+                    item_id: lot.$id(),
+                    category: lot.$category(),
+                    count: lot.$num(),
+                    base_point: lot.$base(),
+                    cumulate_point: lot.$cumulate(),
+                    cumulate_reset: lot.$reset(),
+                    enable_luck: lot.$luck(),
+                    get_item_flag_id: lot.$flag(),
+                }
+            };
+        }
+
+        match index {
+            0 => slot!(
+                lot_item_id01,
+                lot_item_category01,
+                lot_item_base_point01,
+                cumulate_lot_point01,
+                cumulate_reset01,
+                lot_item_num01,
+                enable_luck01,
+                get_item_flag_id01
+            ),
+            1 => slot!(
+                lot_item_id02,
+                lot_item_category02,
+                lot_item_base_point02,
+                cumulate_lot_point02,
+                cumulate_reset02,
+                lot_item_num02,
+                enable_luck02,
+                get_item_flag_id02
+            ),
+            2 => slot!(
+                lot_item_id03,
+                lot_item_category03,
+                lot_item_base_point03,
+                cumulate_lot_point03,
+                cumulate_reset03,
+                lot_item_num03,
+                enable_luck03,
+                get_item_flag_id03
+            ),
+            3 => slot!(
+                lot_item_id04,
+                lot_item_category04,
+                lot_item_base_point04,
+                cumulate_lot_point04,
+                cumulate_reset04,
+                lot_item_num04,
+                enable_luck04,
+                get_item_flag_id04
+            ),
+            4 => slot!(
+                lot_item_id05,
+                lot_item_category05,
+                lot_item_base_point05,
+                cumulate_lot_point05,
+                cumulate_reset05,
+                lot_item_num05,
+                enable_luck05,
+                get_item_flag_id05
+            ),
+            5 => slot!(
+                lot_item_id06,
+                lot_item_category06,
+                lot_item_base_point06,
+                cumulate_lot_point06,
+                cumulate_reset06,
+                lot_item_num06,
+                enable_luck06,
+                get_item_flag_id06
+            ),
+            6 => slot!(
+                lot_item_id07,
+                lot_item_category07,
+                lot_item_base_point07,
+                cumulate_lot_point07,
+                cumulate_reset07,
+                lot_item_num07,
+                enable_luck07,
+                get_item_flag_id07
+            ),
+            7 => slot!(
+                lot_item_id08,
+                lot_item_category08,
+                lot_item_base_point08,
+                cumulate_lot_point08,
+                cumulate_reset08,
+                lot_item_num08,
+                enable_luck08,
+                get_item_flag_id08
+            ),
+            _ => panic!("item lots only have {ITEM_LOT_SLOTS} slots"),
+        }
+    }
+
+    /// Returns whether this slot awards nothing when it's rolled.
+    pub fn is_empty(&self) -> bool {
+        self.item_id == 0 || self.count == 0
+    }
+}
+
+/// The player state that affects how an item lot is evaluated.
+pub struct ItemLotContext<'a> {
+    /// The player's item discovery as a percentage, where 100 is no bonus.
+    pub item_discovery: u32,
+
+    /// Returns whether the given event flag is set.
+    pub is_flag_set: &'a dyn Fn(u32) -> bool,
+
+    /// How many times the lot has been rolled since its cumulate count was
+    /// last reset. The game keeps this in the event flags starting at the
+    /// lot's `cumulate_num_flag_id`, and ignores it for lots where that's 0.
+    pub cumulate_count: u32,
+}
+
+impl Default for ItemLotContext<'_> {
+    fn default() -> Self {
+        Self {
+            item_discovery: 100,
+            is_flag_set: &|_| false,
+            cumulate_count: 0,
+        }
+    }
+}
+
+/// An item awarded by an item lot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ItemLotDrop {
+    /// The index of the slot that was rolled.
+    pub slot: usize,
+    pub item_id: i32,
+    pub category: i32,
+    pub count: u8,
+}
+
+/// The chance of each possible outcome of evaluating an item lot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemLotDistribution {
+    /// The chance that the lot awards nothing, from 0 to 1.
+    pub nothing: f64,
+
+    /// The chance of each item the lot can award, from 0 to 1.
+    pub drops: Vec<(ItemLotDrop, f64)>,
+}
+
+/// Returns the weight of each slot of `lot` for a player in `context`.
+///
+/// This follows the community's understanding of the game's logic:
+///
+/// * If the lot's own get item flag is set, every weight is 0.
+/// * If the lot has a cumulate count and it's reached `cumulate_num_max`,
+///   each slot's cumulate point is used instead of its base point.
+/// * Slots whose get item flag is set have a weight of 0.
+/// * Slots with luck enabled that award an item have their weight scaled by
+///   the player's item discovery. Scaled weights that don't fit in a `u32`
+///   are clamped to [u32::MAX].
+/// * Other slots, including ones that award nothing, keep their base weight.
+pub fn item_lot_weights(lot: &ITEMLOT_PARAM_ST, context: &ItemLotContext) -> [u32; ITEM_LOT_SLOTS] {
+    let lot_flag = lot.get_item_flag_id();
+    if lot_flag != 0 && (context.is_flag_set)(lot_flag) {
+        return [0; ITEM_LOT_SLOTS];
+    }
+    let cumulated =
+        lot.cumulate_num_flag_id() != 0 && context.cumulate_count >= lot.cumulate_num_max() as u32;
+
+    std::array::from_fn(|index| {
+        let slot = ItemLotSlot::from_param(lot, index);
+        let base = if cumulated {
+            slot.cumulate_point
+        } else {
+            slot.base_point
+        } as u32;
+        if slot.get_item_flag_id != 0 && (context.is_flag_set)(slot.get_item_flag_id) {
+            0
+        } else if slot.enable_luck && !slot.is_empty() {
+            let scaled = u64::from(base) * u64::from(context.item_discovery) / 100;
+            u32::try_from(scaled).unwrap_or(u32::MAX)
+        } else {
+            base
+        }
+    })
+}
+
+/// Rolls `lot` once for a player in `context`: a single value from `rand`
+/// modulo the total weight is matched against the slots' running totals in
+/// order.
+///
+/// This is how the community describes the game's roll, but it hasn't been
+/// checked against the game's code or against drops observed with a known
+/// generator state, so it may not reproduce the game's exact outcomes.
+///
+/// Returns `None` if the lot awards nothing.
+pub fn roll_item_lot<R: CSRandVmt>(
+    lot: &ITEMLOT_PARAM_ST,
+    context: &ItemLotContext,
+    rand: &mut R,
+) -> Option<ItemLotDrop> {
+    let weights = item_lot_weights(lot, context);
+    let total = total_weight(&weights);
+    if total == 0 {
+        return None;
+    }
+
+    let mut roll = u64::from(rand.next_uint()) % total;
+    let index = weights
+        .iter()
+        .map(|&weight| u64::from(weight))
+        .position(|weight| {
+            if roll < weight {
+                true
+            } else {
+                roll -= weight;
+                false
+            }
+        })?;
+    drop_for_slot(ItemLotSlot::from_param(lot, index))
+}
+
+/// Returns the exact chance of each outcome of rolling `lot` for a player in
+/// `context`.
+pub fn item_lot_distribution(
+    lot: &ITEMLOT_PARAM_ST,
+    context: &ItemLotContext,
+) -> ItemLotDistribution {
+    let weights = item_lot_weights(lot, context);
+    let total = total_weight(&weights);
+    let mut distribution = ItemLotDistribution {
+        nothing: if total == 0 { 1.0 } else { 0.0 },
+        drops: Vec::new(),
+    };
+    if total == 0 {
+        return distribution;
+    }
+
+    for (index, weight) in weights.into_iter().enumerate() {
+        if weight == 0 {
+            continue;
+        }
+        let chance = weight as f64 / total as f64;
+        match drop_for_slot(ItemLotSlot::from_param(lot, index)) {
+            Some(drop) => distribution.drops.push((drop, chance)),
+            None => distribution.nothing += chance,
+        }
+    }
+    distribution
+}
+
+/// Returns the lot's cumulate count after it's rolled with `count` and awards
+/// `drop`.
+///
+/// Awarding a slot with `cumulate_reset` set resets the count to 0. Any other
+/// roll adds 1, up to the lot's `cumulate_num_max`. Lots without a
+/// `cumulate_num_flag_id` don't keep a count, so it's returned unchanged.
+pub fn next_cumulate_count(lot: &ITEMLOT_PARAM_ST, count: u32, drop: Option<&ItemLotDrop>) -> u32 {
+    if lot.cumulate_num_flag_id() == 0 {
+        return count;
+    }
+
+    match drop {
+        Some(drop) if ItemLotSlot::from_param(lot, drop.slot).cumulate_reset => 0,
+        _ => (count + 1).min(lot.cumulate_num_max() as u32),
+    }
+}
+
+/// Rolls `lot` `trials` times and returns how often each outcome occurred,
+/// with `None` counting the rolls that awarded nothing.
+///
+/// The lot's cumulate count starts at `context`'s and is updated after each
+/// roll with [next_cumulate_count].
+pub fn simulate_item_lot<R: CSRandVmt>(
+    lot: &ITEMLOT_PARAM_ST,
+    context: &ItemLotContext,
+    rand: &mut R,
+    trials: u32,
+) -> HashMap<Option<ItemLotDrop>, u32> {
+    let mut counts = HashMap::new();
+    let mut cumulate_count = context.cumulate_count;
+    for _ in 0..trials {
+        let context = ItemLotContext {
+            cumulate_count,
+            ..*context
+        };
+        let drop = roll_item_lot(lot, &context, rand);
+        cumulate_count = next_cumulate_count(lot, cumulate_count, drop.as_ref());
+        *counts.entry(drop).or_default() += 1;
+    }
+    counts
+}
+
+/// Sums `weights` without overflowing, since scaled weights can each be up to
+/// [u32::MAX].
+fn total_weight(weights: &[u32; ITEM_LOT_SLOTS]) -> u64 {
+    weights.iter().copied().map(u64::from).sum()
+}
+
+fn drop_for_slot(slot: ItemLotSlot) -> Option<ItemLotDrop> {
+    if slot.is_empty() {
+        return None;
+    }
+
+    Some(ItemLotDrop {
+        slot: slot.index,
+        item_id: slot.item_id,
+        category: slot.category,
+        count: slot.count,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// A generator that counts up from 0, so rolls walk through every weight
+    /// in order.
+    struct Counter(u32);
+
+    impl CSRandVmt for Counter {
+        extern "C" fn destructor(&mut self, _should_free: bool) {}
+        extern "C" fn next_uint(&mut self) -> u32 {
+            self.0 += 1;
+            self.0 - 1
+        }
+        extern "C" fn next_long(&mut self) -> u64 {
+            self.next_uint() as u64
+        }
+    }
+
+    /// A lot with a 50-point empty slot, a 30-point luck slot and a 20-point
+    /// slot that's suppressed by flag 777.
+    fn lot() -> ITEMLOT_PARAM_ST {
+        // Safety: Param rows are plain old data.
+        let mut lot: ITEMLOT_PARAM_ST = unsafe { std::mem::zeroed() };
+        lot.set_lot_item_base_point01(50);
+
+        lot.set_lot_item_id02(100);
+        lot.set_lot_item_category02(1);
+        lot.set_lot_item_num02(1);
+        lot.set_lot_item_base_point02(30);
+        lot.set_enable_luck02(true);
+
+        lot.set_lot_item_id03(200);
+        lot.set_lot_item_category03(1);
+        lot.set_lot_item_num03(2);
+        lot.set_lot_item_base_point03(20);
+        lot.set_get_item_flag_id03(777);
+        lot
+    }
+
+    fn item(slot: usize, item_id: i32, count: u8) -> ItemLotDrop {
+        ItemLotDrop {
+            slot,
+            item_id,
+            category: 1,
+            count,
+        }
+    }
+
+    #[test]
+    fn distribution() {
+        let distribution = item_lot_distribution(&lot(), &Default::default());
+        assert_eq!(distribution.nothing, 0.5);
+        assert_eq!(
+            distribution.drops,
+            [(item(1, 100, 1), 0.3), (item(2, 200, 2), 0.2)]
+        );
+    }
+
+    #[test]
+    fn item_discovery_scales_luck_slots() {
+        let context = ItemLotContext {
+            item_discovery: 200,
+            ..Default::default()
+        };
+        assert_eq!(
+            item_lot_weights(&lot(), &context),
+            [50, 60, 20, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn large_item_discovery_doesnt_overflow() {
+        let mut lot = lot();
+        lot.set_lot_item_base_point01(u16::MAX);
+        lot.set_lot_item_base_point02(u16::MAX);
+        lot.set_enable_luck01(true);
+        let context = ItemLotContext {
+            item_discovery: u32::MAX,
+            ..Default::default()
+        };
+        assert_eq!(
+            item_lot_weights(&lot, &context),
+            [u16::MAX as u32, u32::MAX, 20, 0, 0, 0, 0, 0]
+        );
+
+        let distribution = item_lot_distribution(&lot, &context);
+        let total = distribution.nothing + distribution.drops.iter().map(|(_, c)| c).sum::<f64>();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert_eq!(
+            roll_item_lot(&lot, &context, &mut Counter(u32::MAX - 1)),
+            Some(item(1, 100, 1))
+        );
+    }
+
+    #[test]
+    fn get_item_flags_suppress_slots() {
+        let context = ItemLotContext {
+            is_flag_set: &|flag| flag == 777,
+            ..Default::default()
+        };
+        let distribution = item_lot_distribution(&lot(), &context);
+        assert_eq!(distribution.nothing, 0.625);
+        assert_eq!(distribution.drops, [(item(1, 100, 1), 0.375)]);
+
+        let mut taken = lot();
+        taken.set_get_item_flag_id(888);
+        let context = ItemLotContext {
+            is_flag_set: &|flag| flag == 888,
+            ..Default::default()
+        };
+        let distribution = item_lot_distribution(&taken, &context);
+        assert_eq!(distribution.nothing, 1.0);
+        assert!(distribution.drops.is_empty());
+        assert_eq!(roll_item_lot(&taken, &context, &mut Counter(0)), None);
+    }
+
+    #[test]
+    fn rolls_use_cumulative_weights() {
+        let lot = lot();
+        let context = ItemLotContext::default();
+        let mut rand = Counter(49);
+        assert_eq!(roll_item_lot(&lot, &context, &mut rand), None);
+        assert_eq!(
+            roll_item_lot(&lot, &context, &mut rand),
+            Some(item(1, 100, 1))
+        );
+
+        let mut rand = Counter(79);
+        assert_eq!(
+            roll_item_lot(&lot, &context, &mut rand),
+            Some(item(1, 100, 1))
+        );
+        assert_eq!(
+            roll_item_lot(&lot, &context, &mut rand),
+            Some(item(2, 200, 2))
+        );
+    }
+
+    /// [lot] with a cumulate count that lets slot 2 through after 2 rolls
+    /// and resets once it's awarded.
+    fn cumulate_lot() -> ITEMLOT_PARAM_ST {
+        let mut lot = lot();
+        lot.set_cumulate_num_flag_id(1000);
+        lot.set_cumulate_num_max(2);
+        lot.set_cumulate_lot_point03(100);
+        lot.set_cumulate_reset03(true);
+        lot.set_get_item_flag_id03(0);
+        lot
+    }
+
+    #[test]
+    fn cumulate_count_switches_weights() {
+        let lot = cumulate_lot();
+        let context = |cumulate_count| ItemLotContext {
+            cumulate_count,
+            ..Default::default()
+        };
+        assert_eq!(
+            item_lot_weights(&lot, &context(1)),
+            [50, 30, 20, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            item_lot_weights(&lot, &context(2)),
+            [0, 0, 100, 0, 0, 0, 0, 0]
+        );
+
+        let mut uncounted = lot.clone();
+        uncounted.set_cumulate_num_flag_id(0);
+        assert_eq!(
+            item_lot_weights(&uncounted, &context(2)),
+            [50, 30, 20, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn cumulate_count_updates() {
+        let lot = cumulate_lot();
+        assert_eq!(next_cumulate_count(&lot, 0, None), 1);
+        assert_eq!(next_cumulate_count(&lot, 1, Some(&item(1, 100, 1))), 2);
+        assert_eq!(next_cumulate_count(&lot, 2, None), 2);
+        assert_eq!(next_cumulate_count(&lot, 2, Some(&item(2, 200, 2))), 0);
+
+        let mut uncounted = lot;
+        uncounted.set_cumulate_num_flag_id(0);
+        assert_eq!(next_cumulate_count(&uncounted, 0, None), 0);
+    }
+
+    #[test]
+    fn simulation_tracks_cumulate_count() {
+        // Every third roll is guaranteed to award slot 2, which resets the
+        // count.
+        let counts = simulate_item_lot(&cumulate_lot(), &Default::default(), &mut Counter(0), 3);
+        assert_eq!(counts[&None], 2);
+        assert_eq!(counts[&Some(item(2, 200, 2))], 1);
+    }
+
+    #[test]
+    fn simulation_matches_distribution() {
+        let counts = simulate_item_lot(&lot(), &Default::default(), &mut Counter(0), 1000);
+        assert_eq!(counts[&None], 500);
+        assert_eq!(counts[&Some(item(1, 100, 1))], 300);
+        assert_eq!(counts[&Some(item(2, 200, 2))], 200);
+    }
+}
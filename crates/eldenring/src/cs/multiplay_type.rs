@@ -1,11 +1,7 @@
-use std::{
-    borrow::Cow,
-    ops::{Index, IndexMut},
-};
+use std::ops::{Index, IndexMut};
 
 use crate::cs::{ChrType, FullScreenMessage};
 use bitfield::bitfield;
-use shared::{FromStatic, load_static_direct};
 
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -267,6 +263,7 @@ pub struct MultiplayPropertyEntry {
 /// Used to lookup what [ChrType], [MultiplayRole] and [SummonParamType]
 /// a character should be treated as in multiplayer sessions.
 /// Additionally contains various other properties related to each multiplayer type.
+#[shared::singleton(rva = multiplay_properties)]
 pub struct MultiplayProperties {
     pub entries: [MultiplayPropertyEntry; 31],
 }
@@ -284,13 +281,3 @@ impl IndexMut<MultiplayType> for [MultiplayPropertyEntry; 31] {
         &mut self[index as usize]
     }
 }
-
-impl FromStatic for MultiplayProperties {
-    fn name() -> Cow<'static, str> {
-        Cow::Borrowed("MultiplayProperties")
-    }
-
-    fn instance_ptr() -> shared::InstanceResult<*mut Self> {
-        load_static_direct(crate::rva::get().multiplay_properties)
-    }
-}
@@ -8,11 +8,15 @@ use crate::{
     BasicVector, Vector,
     cs::{ChrType, MultiplayRole},
 };
-use shared::{IsEmpty, MaybeEmpty, NonEmptyIteratorExt, NonEmptyIteratorMutExt, OwnedPtr};
+use shared::{
+    GameLayout, IsEmpty, MaybeEmpty, NonEmptyIteratorExt, NonEmptyIteratorMutExt, OwnedPtr,
+};
 
 use crate::cs::{FieldInsHandle, GaitemHandle, ItemId, OptionalItemId};
 
 #[repr(C)]
+#[derive(GameLayout)]
+#[layout(size = 0xae8)]
 /// Source of name: RTTI
 pub struct PlayerGameData {
     vftable: usize,
@@ -82,7 +86,7 @@ pub struct PlayerGameData {
     /// True if the player is in their own world.
     pub is_my_world: bool,
     unke8: [u8; 0x3],
-    unke9: bool,
+    unkeb: bool,
     pub character_id: u32,
     pub invasions_success_count: u32,
     pub solo_breakin_point: u32,
@@ -199,7 +203,7 @@ pub struct PlayerGameData {
     pub quickmatch_united_combat_rank: u8,
     pub quickmatch_spirit_ashes_rank: u8,
     pub unkaa9: bool,
-    unkaa: u8,
+    unkaaa: u8,
     pub is_quick_match_host: bool,
     pub quick_match_map_load_ready: bool,
     pub quick_match_desired_team: u8,
@@ -556,7 +560,7 @@ impl InventoryItemsData {
     /// picking up a new item will fail.
     pub fn is_normal_items_full(&self) -> bool {
         self.normal_items_len >= self.normal_items_capacity
-            && self.normal_entries().iter().all(|e| !e.is_empty())
+            && self.normal_entries().iter().first_empty_slot().is_none()
     }
 
     /// A slice over all the key item [EquipInventoryDataListEntry] allocated
@@ -584,7 +588,7 @@ impl InventoryItemsData {
     /// up a new item will fail.
     pub fn is_key_items_full(&self) -> bool {
         self.key_items_len >= self.key_items_capacity
-            && self.key_entries().iter().all(|e| !e.is_empty())
+            && self.key_entries().iter().first_empty_slot().is_none()
     }
 
     /// A slice over all the multiplayer key item [EquipInventoryDataListEntry]
@@ -616,7 +620,11 @@ impl InventoryItemsData {
     /// picking up a new item will fail.
     pub fn is_multiplay_key_items_full(&self) -> bool {
         self.multiplay_key_items_len >= self.multiplay_key_items_capacity
-            && self.multiplay_key_entries().iter().all(|e| !e.is_empty())
+            && self
+                .multiplay_key_entries()
+                .iter()
+                .first_empty_slot()
+                .is_none()
     }
 
     /// A slice over all the key item [EquipInventoryDataListEntry] allocated
@@ -705,6 +713,8 @@ impl ItemIdMapping {
 }
 
 #[repr(C)]
+#[derive(IsEmpty)]
+#[empty(field = item_id, as = OptionalItemId, with = |id: &OptionalItemId| !id.is_valid())]
 pub struct EquipInventoryDataListEntry {
     /// Handle to the gaitem instance which describes additional properties to the inventory item,
     /// like durability and gems in the case of weapons.
@@ -723,13 +733,6 @@ pub struct EquipInventoryDataListEntry {
     pub pot_group: i32,
 }
 
-unsafe impl IsEmpty for EquipInventoryDataListEntry {
-    fn is_empty(value: &MaybeEmpty<EquipInventoryDataListEntry>) -> bool {
-        !OptionalItemId::from(unsafe { *value.as_non_null().cast::<u32>().offset(1).as_ref() })
-            .is_valid()
-    }
-}
-
 #[repr(C)]
 pub struct EquipMagicData {
     vftable: usize,
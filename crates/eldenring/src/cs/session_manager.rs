@@ -187,6 +187,8 @@ pub struct CSSessionManager {
 }
 
 #[repr(C)]
+#[derive(IsEmpty)]
+#[empty(field = steam_id, value = 0)]
 pub struct SessionManagerPlayerEntryBase {
     internal_thread_steam_connection: usize,
     internal_thread_steam_socket: usize,
@@ -196,13 +198,6 @@ pub struct SessionManagerPlayerEntryBase {
     voice_chat_member_ref_info: usize,
 }
 
-unsafe impl IsEmpty for SessionManagerPlayerEntryBase {
-    fn is_empty(base: &MaybeEmpty<SessionManagerPlayerEntryBase>) -> bool {
-        // Check whether steam_id is 0
-        *unsafe { base.as_non_null().cast::<u64>().offset(2).as_ref() } == 0
-    }
-}
-
 #[repr(C)]
 pub struct SessionManagerPlayerEntry {
     pub base: SessionManagerPlayerEntryBase,
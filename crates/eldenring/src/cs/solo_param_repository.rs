@@ -387,9 +387,7 @@ impl SoloParamRepository {
         use ItemCategory::*;
         match id.category() {
             Weapon => self
-                // Round to the nearest 100 in case the ID is for an upgraded
-                // weapon.
-                .get::<EquipParamWeapon>((id.param_id() / 100) * 100)
+                .get::<EquipParamWeapon>(id.equip_param_id())
                 .map(|p| EquipParam::as_enum(p)),
             Protector => self
                 .get::<EquipParamProtector>(id.param_id())
@@ -412,9 +410,7 @@ impl SoloParamRepository {
         use ItemCategory::*;
         match id.category() {
             Weapon => self
-                // Round to the nearest 100 in case the ID is for an upgraded
-                // weapon.
-                .get_mut::<EquipParamWeapon>((id.param_id() / 100) * 100)
+                .get_mut::<EquipParamWeapon>(id.equip_param_id())
                 .map(|p| EquipParam::as_enum_mut(p)),
             Protector => self
                 .get_mut::<EquipParamProtector>(id.param_id())
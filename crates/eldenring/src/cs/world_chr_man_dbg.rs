@@ -1,7 +1,5 @@
-use shared::{FromStatic, load_static_direct};
-
-use crate::{cs::ChrIns, rva};
-use std::{borrow::Cow, ptr::NonNull};
+use crate::cs::ChrIns;
+use std::ptr::NonNull;
 
 use super::PlayerSessionHolder;
 
@@ -105,6 +103,7 @@ pub enum OmissionUpdateNumType {
 #[derive(Debug)]
 /// Usually located immediately after the `WorldChrManDbg` singleton.
 /// Game also checks if WorldChrManDbg exists before accessing this struct.
+#[shared::singleton(rva = world_chr_man_dbg_flags)]
 pub struct WorldChrManDbgFlags {
     /// prevents death by setting HP to 1 when they are less than 0
     /// Read from debug property GameData.PlayerNoDead
@@ -202,13 +201,3 @@ pub struct WorldChrManDbgFlags {
     unk3c: i32,
     unk40: i32,
 }
-
-impl FromStatic for WorldChrManDbgFlags {
-    fn name() -> Cow<'static, str> {
-        Cow::Borrowed("WorldChrManDbgFlags")
-    }
-
-    fn instance_ptr() -> shared::InstanceResult<*mut Self> {
-        load_static_direct(rva::get().world_chr_man_dbg_flags)
-    }
-}
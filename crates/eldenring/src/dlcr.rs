@@ -1,10 +1,10 @@
-use std::{borrow::Cow, ptr::NonNull};
+use std::ptr::NonNull;
 
-use shared::{FromStatic, OwnedPtr};
+use shared::OwnedPtr;
 
 use vtable_rs::VPtr;
 
-use crate::{Vector, dlkr::DLAllocatorBase, rva};
+use crate::{Vector, dlkr::DLAllocatorBase};
 
 #[vtable_rs::vtable]
 pub trait DLCipherKeyVmt {
@@ -285,6 +285,7 @@ pub struct DLKeyGeneratorSPI {
 }
 
 #[repr(C)]
+#[shared::singleton(rva = crypto_spi_registry, indirect)]
 pub struct CryptoSPIRegistry {
     pub key_generators: Vector<NonNull<DLKeyGeneratorSPI>>,
     pub cipher_spis: Vector<NonNull<DLCipherSPI>>,
@@ -339,13 +340,3 @@ impl CryptoSPIRegistry {
         None
     }
 }
-
-impl FromStatic for CryptoSPIRegistry {
-    fn name() -> Cow<'static, str> {
-        Cow::Borrowed("CryptoSPIRegistry")
-    }
-
-    fn instance_ptr() -> fromsoftware_shared::InstanceResult<*mut Self> {
-        unsafe { shared::load_static_indirect(rva::get().crypto_spi_registry) }
-    }
-}
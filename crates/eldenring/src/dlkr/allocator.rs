@@ -1,11 +1,13 @@
 use std::{
     alloc::{GlobalAlloc, Layout},
+    ffi::c_void,
     mem::transmute,
     ptr::NonNull,
 };
 
+use fromsoftware_shared_stl::Allocator;
 use pelite::pe64::Pe;
-use shared::Program;
+use shared::{AllocError, GameAllocator, Program};
 use vtable_rs::VPtr;
 
 use crate::rva;
@@ -111,6 +113,43 @@ unsafe impl GlobalAlloc for DLAllocatorRef {
     }
 }
 
+impl Allocator for DLAllocatorRef {
+    unsafe fn allocate_raw(&mut self, size: usize, align: usize) -> *mut c_void {
+        let allocator = self.0.as_ptr();
+        unsafe {
+            ((*allocator).vftable.allocate_aligned)(&mut *allocator, size, align) as *mut c_void
+        }
+    }
+
+    unsafe fn deallocate_raw(&mut self, ptr: *mut c_void) {
+        let allocator = self.0.as_ptr();
+        unsafe {
+            ((*allocator).vftable.deallocate)(&mut *allocator, ptr as *const u8);
+        }
+    }
+}
+
+/// A [GameAllocator] that allocates from and frees to
+/// [DLAllocatorRef::runtime_heap_allocator].
+pub struct RuntimeHeapAllocator;
+
+impl GameAllocator for RuntimeHeapAllocator {
+    fn allocate(layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let ptr = unsafe {
+            DLAllocatorRef::runtime_heap_allocator().allocate_raw(layout.size(), layout.align())
+        };
+        NonNull::new(ptr as *mut u8)
+            .map(|ptr| NonNull::slice_from_raw_parts(ptr, layout.size()))
+            .ok_or(AllocError)
+    }
+
+    unsafe fn deallocate(ptr: NonNull<u8>, _layout: Layout) {
+        unsafe {
+            DLAllocatorRef::runtime_heap_allocator().deallocate_raw(ptr.as_ptr() as *mut c_void)
+        }
+    }
+}
+
 impl From<NonNull<DLAllocatorBase>> for DLAllocatorRef {
     fn from(ptr: NonNull<DLAllocatorBase>) -> Self {
         Self(ptr)
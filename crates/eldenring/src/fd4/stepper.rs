@@ -1,3 +1,8 @@
+use std::alloc::{Layout, alloc};
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, PoisonError};
 use std::{marker::PhantomData, ptr::NonNull};
 
 use shared::StepperStates;
@@ -36,6 +41,276 @@ pub struct FD4StepTemplateBase<Subject, Base, States: StepperStates> {
     unka4: i32,
 }
 
+impl<Subject: 'static, Base, States: StepperStates> FD4StepTemplateBase<Subject, Base, States> {
+    /// Returns the functions that run each state, indexed by
+    /// [StepperStates::index].
+    pub fn stepper_fns(&self) -> &[StepperFn<Subject>] {
+        unsafe { self.stepper_fns.as_ref() }.as_ref()
+    }
+
+    /// Returns the function that runs `state`, or `None` for the
+    /// `NotExecuting` state.
+    pub fn stepper_fn(&self, state: States) -> Option<&StepperFn<Subject>> {
+        self.stepper_fns().get(state.index()?)
+    }
+
+    /// Returns the game's name for `state`, or `None` for the `NotExecuting`
+    /// state.
+    pub fn state_name(&self, state: States) -> Option<String> {
+        self.stepper_fn(state).and_then(|f| f.name())
+    }
+
+    /// Returns the game's name for the state that's executing this frame.
+    pub fn current_state_name(&self) -> Option<String> {
+        self.state_name(self.current_state)
+    }
+
+    /// Asks the stepper to switch to `state` on the next frame.
+    pub fn request_state(&mut self, state: States) {
+        self.requested_state = state;
+    }
+
+    /// Registers `callback` to run every time the game runs `state`'s
+    /// executor, right after the executor returns.
+    ///
+    /// The first time any state is hooked, this replaces this stepper's
+    /// function array with a copy that routes every state through Rust. The
+    /// original executors still run for all states, hooked or not. Hooks are
+    /// shared by all steppers of the same `Subject` and `States` types, since
+    /// the game uses a single function array for each of them.
+    ///
+    /// Dropping the returned [StepperHook] removes the callback. This is safe
+    /// to do from inside any hook's callback, including its own, in which case
+    /// the callback doesn't run again.
+    ///
+    /// ## Panics
+    ///
+    /// If `state` is `NotExecuting` or if the stepper has more than
+    /// [MAX_HOOKED_STATES] states.
+    pub fn hook_state(
+        &mut self,
+        state: States,
+        callback: impl FnMut(&mut Subject, &FD4Time) + Send + 'static,
+    ) -> StepperHook {
+        let index = state
+            .index()
+            .expect("the NotExecuting state has no executor to hook");
+        let key = HookKey::new::<Subject, States>(index);
+        self.install_trampolines();
+
+        let mut callback = callback;
+        let callback: HookCallback = Box::new(move |subject, time| {
+            callback(unsafe { subject.cast::<Subject>().as_mut() }, time)
+        });
+
+        let removed = Arc::new(AtomicBool::new(false));
+        STEPPER_HOOKS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .states
+            .get_mut(&key)
+            .expect("trampolines were just installed")
+            .callbacks
+            .push((removed.clone(), callback));
+
+        StepperHook { key, removed }
+    }
+
+    /// Replaces this stepper's function array with one whose executors call
+    /// [trampoline], unless that's already been done.
+    fn install_trampolines(&mut self) {
+        let trampolines = trampolines::<Subject, States>();
+        let fns = self.stepper_fns();
+        assert!(
+            fns.len() <= trampolines.len(),
+            "steppers with more than {MAX_HOOKED_STATES} states can't be hooked"
+        );
+        if fns
+            .first()
+            .is_some_and(|f| f.executor as usize == trampolines[0] as usize)
+        {
+            return;
+        }
+
+        let mut hooks = STEPPER_HOOKS.lock().unwrap_or_else(PoisonError::into_inner);
+        for (index, f) in fns.iter().enumerate() {
+            hooks
+                .states
+                .entry(HookKey::new::<Subject, States>(index))
+                .or_insert_with(|| HookedState {
+                    original: f.executor as usize,
+                    callbacks: Vec::new(),
+                });
+        }
+        drop(hooks);
+
+        // The game's array is usually in read-only memory, so point this
+        // stepper at a copy instead. The copy is leaked since the game may
+        // keep using it for as long as the stepper exists.
+        let layout = Layout::new::<States::StepperFnArray<StepperFn<Subject>>>();
+        let copy = unsafe {
+            let copy = NonNull::new(alloc(layout))
+                .expect("failed to allocate stepper functions")
+                .cast::<States::StepperFnArray<StepperFn<Subject>>>();
+            std::ptr::copy_nonoverlapping(self.stepper_fns.as_ptr(), copy.as_ptr(), 1);
+            copy
+        };
+        self.stepper_fns = copy;
+
+        let fns = unsafe { self.stepper_fns.as_mut() }.as_mut();
+        for (f, trampoline) in fns.iter_mut().zip(trampolines) {
+            f.executor = trampoline;
+        }
+    }
+}
+
+/// The maximum number of states a stepper can have and still be hooked with
+/// [FD4StepTemplateBase::hook_state].
+pub const MAX_HOOKED_STATES: usize = 64;
+
+type HookCallback = Box<dyn FnMut(NonNull<()>, &FD4Time) + Send>;
+
+/// Identifies a single state of steppers with a given subject and state type.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct HookKey {
+    subject: TypeId,
+    states: TypeId,
+    index: usize,
+}
+
+impl HookKey {
+    fn new<Subject: 'static, States: 'static>(index: usize) -> Self {
+        Self {
+            subject: TypeId::of::<Subject>(),
+            states: TypeId::of::<States>(),
+            index,
+        }
+    }
+}
+
+/// The original executor and Rust callbacks for a single state of a single
+/// stepper type. Each callback is paired with the flag its [StepperHook] sets
+/// when it's dropped.
+struct HookedState {
+    original: usize,
+    callbacks: Vec<(Arc<AtomicBool>, HookCallback)>,
+}
+
+#[derive(Default)]
+struct StepperHooks {
+    states: HashMap<HookKey, HookedState>,
+}
+
+static STEPPER_HOOKS: LazyLock<Mutex<StepperHooks>> = LazyLock::new(Default::default);
+
+/// A callback registered with [FD4StepTemplateBase::hook_state]. Dropping
+/// this removes the callback.
+#[must_use = "dropping the hook removes the callback"]
+pub struct StepperHook {
+    key: HookKey,
+    removed: Arc<AtomicBool>,
+}
+
+impl StepperHook {
+    /// Keeps the callback registered for as long as the game runs.
+    pub fn detach(self) {
+        std::mem::forget(self);
+    }
+}
+
+impl Drop for StepperHook {
+    fn drop(&mut self) {
+        // The callback may be running right now, in which case it's not in
+        // the list and the trampoline removes it once it sees this flag.
+        self.removed.store(true, Ordering::Release);
+
+        let mut hooks = STEPPER_HOOKS.lock().unwrap_or_else(PoisonError::into_inner);
+        let removed = hooks
+            .states
+            .get_mut(&self.key)
+            .map(|state| take_removed(&mut state.callbacks));
+        // The callbacks may own hooks of their own, so drop them only once
+        // the lock is released.
+        drop(hooks);
+        drop(removed);
+    }
+}
+
+/// Moves the callbacks whose hooks were dropped out of `callbacks`.
+fn take_removed(
+    callbacks: &mut Vec<(Arc<AtomicBool>, HookCallback)>,
+) -> Vec<(Arc<AtomicBool>, HookCallback)> {
+    let (removed, kept) = std::mem::take(callbacks)
+        .into_iter()
+        .partition(|(removed, _)| removed.load(Ordering::Acquire));
+    *callbacks = kept;
+    removed
+}
+
+/// Runs the original executor for state `I` of steppers for `Subject` and
+/// `States`, followed by any callbacks registered for it.
+extern "C" fn trampoline<Subject: 'static, States: 'static, const I: usize>(
+    subject: &mut Subject,
+    time: &FD4Time,
+) {
+    let key = HookKey::new::<Subject, States>(I);
+    let original = STEPPER_HOOKS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .states[&key]
+        .original;
+    let original: extern "C" fn(&mut Subject, &FD4Time) = unsafe { std::mem::transmute(original) };
+    original(subject, time);
+
+    // Take the callbacks out while they run so they can add or remove hooks
+    // without deadlocking.
+    let mut callbacks = match STEPPER_HOOKS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .states
+        .get_mut(&key)
+    {
+        Some(state) => std::mem::take(&mut state.callbacks),
+        None => return,
+    };
+    let ptr = NonNull::from(&mut *subject).cast::<()>();
+    for (removed, callback) in callbacks.iter_mut() {
+        if !removed.load(Ordering::Acquire) {
+            callback(ptr, time);
+        }
+    }
+
+    let mut hooks = STEPPER_HOOKS.lock().unwrap_or_else(PoisonError::into_inner);
+    let state = hooks
+        .states
+        .get_mut(&key)
+        .expect("hooked states are never removed");
+    // Callbacks registered while these were running were added to the now
+    // empty list, so keep those after the existing ones.
+    let added = std::mem::replace(&mut state.callbacks, callbacks);
+    state.callbacks.extend(added);
+    let removed = take_removed(&mut state.callbacks);
+    drop(hooks);
+    drop(removed);
+}
+
+/// Returns a trampoline for each state index a stepper for `Subject` and
+/// `States` can have.
+fn trampolines<Subject: 'static, States: 'static>()
+-> [extern "C" fn(&mut Subject, &FD4Time); MAX_HOOKED_STATES] {
+    macro_rules! trampolines {
+        ($($i:literal)*) => {
+            [$(trampoline::<Subject, States, $i> as extern "C" fn(&mut Subject, &FD4Time)),*]
+        };
+    }
+
+    trampolines!(
+        0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31
+        32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59 60
+        61 62 63
+    )
+}
+
 /// Source of name: RTTI
 #[repr(C)]
 pub struct FD4StepTemplateInterface<Base, Subject> {
@@ -56,6 +331,22 @@ pub struct StepperFn<T> {
     pub name: *const u16,
 }
 
+impl<T> StepperFn<T> {
+    /// Decodes the state's null-terminated UTF-16 name, if it has one.
+    pub fn name(&self) -> Option<String> {
+        if self.name.is_null() {
+            return None;
+        }
+
+        let mut len = 0;
+        while unsafe { *self.name.add(len) } != 0 {
+            len += 1;
+        }
+        let slice = unsafe { std::slice::from_raw_parts(self.name, len) };
+        Some(String::from_utf16_lossy(slice))
+    }
+}
+
 /// Source of name: RTTI
 #[repr(C)]
 pub struct FD4ComponentAttachSystem {
@@ -71,3 +362,176 @@ pub struct FD4ComponentAttachSystem_Step {
     pub base: FD4ComponentAttachSystem,
     pub allocator: DLAllocatorRef,
 }
+
+#[cfg(test)]
+mod test {
+    use std::mem::MaybeUninit;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[repr(i32)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, StepperStates)]
+    enum TestState {
+        NotExecuting = -1,
+        First = 0,
+        Second = 1,
+    }
+
+    struct Subject {
+        log: Vec<&'static str>,
+    }
+
+    extern "C" fn run_first(subject: &mut Subject, _: &FD4Time) {
+        subject.log.push("first");
+    }
+
+    extern "C" fn run_second(subject: &mut Subject, _: &FD4Time) {
+        subject.log.push("second");
+    }
+
+    /// Creates a leaked stepper running [TestState::First] whose remaining
+    /// fields are zeroed.
+    fn stepper() -> &'static mut FD4StepTemplateBase<Subject, usize, TestState> {
+        let name = "First\0".encode_utf16().collect::<Vec<_>>().leak();
+        let fns = Box::leak(Box::new([
+            StepperFn {
+                executor: run_first,
+                name: name.as_ptr(),
+            },
+            StepperFn {
+                executor: run_second,
+                name: std::ptr::null(),
+            },
+        ]));
+
+        let stepper = Box::leak(Box::new(MaybeUninit::<
+            FD4StepTemplateBase<Subject, usize, TestState>,
+        >::zeroed()));
+        unsafe {
+            (&raw mut (*stepper.as_mut_ptr()).stepper_fns).write(NonNull::from(fns));
+            stepper.assume_init_mut()
+        }
+    }
+
+    #[test]
+    fn state_names() {
+        let stepper = stepper();
+        assert_eq!(stepper.stepper_fns().len(), 2);
+        assert_eq!(stepper.current_state, TestState::First);
+        assert_eq!(stepper.current_state_name().as_deref(), Some("First"));
+        assert_eq!(stepper.state_name(TestState::Second), None);
+        assert_eq!(stepper.state_name(TestState::NotExecuting), None);
+    }
+
+    #[test]
+    fn request_state() {
+        let stepper = stepper();
+        stepper.request_state(TestState::Second);
+        assert_eq!(stepper.requested_state, TestState::Second);
+    }
+
+    #[test]
+    fn hook_state() {
+        let stepper = stepper();
+        let original = stepper.stepper_fns;
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let hook = stepper.hook_state(TestState::Second, move |subject, _| {
+            subject.log.push("hook");
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        assert_ne!(stepper.stepper_fns, original);
+
+        let time = unsafe { MaybeUninit::<FD4Time>::zeroed().assume_init() };
+        let mut subject = Subject { log: Vec::new() };
+        for f in stepper.stepper_fns() {
+            (f.executor)(&mut subject, &time);
+        }
+        drop(hook);
+        (stepper.stepper_fns()[1].executor)(&mut subject, &time);
+
+        assert_eq!(subject.log, ["first", "second", "hook", "second"]);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn drop_hook_in_own_callback() {
+        // Use a subject of its own so the hooks don't mix with other tests'.
+        struct OwnSubject;
+        extern "C" fn noop(_: &mut OwnSubject, _: &FD4Time) {}
+
+        let fns = Box::leak(Box::new([
+            StepperFn {
+                executor: noop,
+                name: std::ptr::null(),
+            },
+            StepperFn {
+                executor: noop,
+                name: std::ptr::null(),
+            },
+        ]));
+        let stepper = Box::leak(Box::new(MaybeUninit::<
+            FD4StepTemplateBase<OwnSubject, usize, TestState>,
+        >::zeroed()));
+        let stepper = unsafe {
+            (&raw mut (*stepper.as_mut_ptr()).stepper_fns).write(NonNull::from(fns));
+            stepper.assume_init_mut()
+        };
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let slot = Arc::new(Mutex::new(None::<StepperHook>));
+        let (counter, own) = (calls.clone(), slot.clone());
+        let hook = stepper.hook_state(TestState::First, move |_, _| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            drop(own.lock().unwrap().take());
+        });
+        *slot.lock().unwrap() = Some(hook);
+
+        let time = unsafe { MaybeUninit::<FD4Time>::zeroed().assume_init() };
+        for _ in 0..3 {
+            (stepper.stepper_fns()[0].executor)(&mut OwnSubject, &time);
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(slot.lock().unwrap().is_none());
+        let key = HookKey::new::<OwnSubject, TestState>(0);
+        let hooks = STEPPER_HOOKS.lock().unwrap();
+        assert!(hooks.states[&key].callbacks.is_empty());
+    }
+
+    #[test]
+    fn hooks_are_keyed_by_states() {
+        #[repr(i32)]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, StepperStates)]
+        enum OtherState {
+            NotExecuting = -1,
+            Only = 0,
+        }
+
+        let first = stepper();
+        first.hook_state(TestState::First, |_, _| {}).detach();
+
+        // A stepper of the same subject with different states has its own
+        // function array, which must not be routed to the first one's.
+        let fns = Box::leak(Box::new([StepperFn {
+            executor: run_second,
+            name: std::ptr::null(),
+        }]));
+        let other = Box::leak(Box::new(MaybeUninit::<
+            FD4StepTemplateBase<Subject, usize, OtherState>,
+        >::zeroed()));
+        let other = unsafe {
+            (&raw mut (*other.as_mut_ptr()).stepper_fns).write(NonNull::from(fns));
+            other.assume_init_mut()
+        };
+        other.hook_state(OtherState::Only, |_, _| {}).detach();
+
+        let time = unsafe { MaybeUninit::<FD4Time>::zeroed().assume_init() };
+        let mut subject = Subject { log: Vec::new() };
+        (other.stepper_fns()[0].executor)(&mut subject, &time);
+        (first.stepper_fns()[0].executor)(&mut subject, &time);
+        assert_eq!(subject.log, ["second", "first"]);
+    }
+}
@@ -1,9 +1,10 @@
 use std::ptr::NonNull;
+use std::time::Duration;
 
 use vtable_rs::VPtr;
 
 use crate::{Tree, Vector, dlrf::DLRuntimeClass};
-use shared::Superclass;
+use shared::{Superclass, TaskData};
 
 use super::FD4Time;
 
@@ -15,6 +16,12 @@ pub struct FD4TaskData {
     pub seed: i32,
 }
 
+impl TaskData for FD4TaskData {
+    fn delta_time(&self) -> Option<Duration> {
+        Some((&self.delta_time).into())
+    }
+}
+
 #[vtable_rs::vtable]
 pub trait FD4TaskBaseVmt {
     fn get_runtime_class(&self) -> &DLRuntimeClass;
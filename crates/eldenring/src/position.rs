@@ -20,6 +20,8 @@ use std::{
     ops::{Add, Sub},
 };
 
+use crate::cs::{BlockId, TileScale, WorldBlockInfo, WorldInfo};
+
 /// Represents a position relative to some block center and character's yaw.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -143,9 +145,246 @@ impl Display for DirectionalVector {
     }
 }
 
+/// A source of block centers, used to convert between block and havok
+/// positions.
+///
+/// This is implemented for [WorldInfo] and [WorldBlockInfo], which hold the
+/// centers of loaded blocks, and for slices of `(BlockId, HavokPosition)`
+/// pairs so conversions can be done against a fixed table.
+pub trait CoordinateSpace {
+    /// Returns the havok position of `block_id`'s origin, if it's known.
+    fn physics_center(&self, block_id: BlockId) -> Option<HavokPosition>;
+}
+
+impl CoordinateSpace for WorldInfo {
+    fn physics_center(&self, block_id: BlockId) -> Option<HavokPosition> {
+        self.world_block_info_by_map(&block_id)
+            .map(|b| b.physics_center)
+    }
+}
+
+impl CoordinateSpace for WorldBlockInfo {
+    fn physics_center(&self, block_id: BlockId) -> Option<HavokPosition> {
+        (self.block_id == block_id).then_some(self.physics_center)
+    }
+}
+
+impl CoordinateSpace for [(BlockId, HavokPosition)] {
+    fn physics_center(&self, block_id: BlockId) -> Option<HavokPosition> {
+        self.iter()
+            .find(|(id, _)| *id == block_id)
+            .map(|(_, center)| *center)
+    }
+}
+
+impl BlockPosition {
+    /// Converts this position, which is relative to `block`, to havok space.
+    pub fn to_havok(&self, block: &WorldBlockInfo) -> HavokPosition {
+        Self::block_to_havok(*self, block.physics_center)
+    }
+
+    /// Converts this position, which is relative to `block_id`, to havok
+    /// space. Returns `None` if `space` doesn't know where the block is.
+    pub fn to_havok_in(
+        &self,
+        block_id: BlockId,
+        space: &(impl CoordinateSpace + ?Sized),
+    ) -> Option<HavokPosition> {
+        Some(Self::block_to_havok(*self, space.physics_center(block_id)?))
+    }
+
+    /// Converts this position, which is relative to `block_id`, to a position
+    /// on the overworld grid. Returns `None` if the block isn't an overworld
+    /// tile.
+    pub fn to_global(&self, block_id: BlockId) -> Option<GlobalPosition> {
+        let (x, z) = tile_center(block_id)?;
+        Some(GlobalPosition {
+            area: block_id.area(),
+            x: x + self.x,
+            y: self.y,
+            z: z + self.z,
+            yaw: self.yaw,
+        })
+    }
+
+    /// Converts this position from being relative to `from` to being relative
+    /// to `to`. Both blocks must be tiles of the same overworld area.
+    pub fn rebase(&self, from: BlockId, to: BlockId) -> Option<BlockPosition> {
+        self.to_global(from)?.to_block_in(to)
+    }
+
+    fn block_to_havok(position: BlockPosition, center: HavokPosition) -> HavokPosition {
+        HavokPosition::from_xyz(
+            center.0 + position.x,
+            center.1 + position.y,
+            center.2 + position.z,
+        )
+    }
+}
+
+impl HavokPosition {
+    /// Converts this position to a position relative to `block_id`. Returns
+    /// `None` if `space` doesn't know where the block is.
+    ///
+    /// Havok positions don't include a yaw, so the result's yaw is always 0.
+    pub fn to_block(
+        &self,
+        block_id: BlockId,
+        space: &(impl CoordinateSpace + ?Sized),
+    ) -> Option<BlockPosition> {
+        let center = space.physics_center(block_id)?;
+        Some(BlockPosition::from_xyz(
+            self.0 - center.0,
+            self.1 - center.1,
+            self.2 - center.2,
+        ))
+    }
+}
+
+/// Returns the position of the origin of the overworld tile `block_id` on its
+/// area's grid, or `None` if it isn't an overworld tile.
+///
+/// A tile's origin is at its center, and the grid's origin is at the center of
+/// tile `mAA_00_00_00`.
+fn tile_center(block_id: BlockId) -> Option<(f32, f32)> {
+    let tiles = block_id.tile_scale()?.small_tiles() as f32;
+    let center = |grid: u8| (grid as f32 * tiles + (tiles - 1.0) / 2.0) * TileScale::Small.size();
+    Some((center(block_id.block()), center(block_id.region())))
+}
+
+/// A position on an overworld area's grid, shared by all of that area's tiles.
+///
+/// This is the "global" world position space. Its origin is at the center of
+/// tile `mAA_00_00_00` and it uses the same axes as [BlockPosition].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlobalPosition {
+    /// The overworld area this position is in, such as 60 for the base game
+    /// or 61 for the Realm of Shadow.
+    pub area: u8,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub yaw: f32,
+}
+
+impl GlobalPosition {
+    /// Returns the tile at `scale` that contains this position, or `None` if
+    /// it's outside the grid.
+    pub fn tile(&self, scale: TileScale) -> Option<BlockId> {
+        // Small tiles are centered on multiples of their size, so shift by
+        // half a small tile to find the one that contains the position.
+        let grid = |v: f32| {
+            let grid = ((v + TileScale::Small.size() / 2.0) / scale.size()).floor();
+            (0.0..=u8::MAX as f32).contains(&grid).then_some(grid as u8)
+        };
+        Some(BlockId::from_parts(
+            self.area,
+            grid(self.x)?,
+            grid(self.z)?,
+            scale as u8,
+        ))
+    }
+
+    /// Converts this to a position relative to the tile at `scale` that
+    /// contains it.
+    pub fn to_block(&self, scale: TileScale) -> Option<(BlockId, BlockPosition)> {
+        let block_id = self.tile(scale)?;
+        Some((block_id, self.to_block_in(block_id)?))
+    }
+
+    /// Converts this to a position relative to `block_id`, which doesn't have
+    /// to contain it. Returns `None` if `block_id` isn't a tile of this
+    /// position's area.
+    pub fn to_block_in(&self, block_id: BlockId) -> Option<BlockPosition> {
+        if block_id.area() != self.area {
+            return None;
+        }
+
+        let (x, z) = tile_center(block_id)?;
+        Some(BlockPosition {
+            x: self.x - x,
+            y: self.y,
+            z: self.z - z,
+            yaw: self.yaw,
+        })
+    }
+}
+
+impl Display for GlobalPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { area, x, y, z, yaw } = self;
+        write!(f, "GlobalPosition(m{area}, {x}, {y}, {z}, yaw:{yaw})")
+    }
+}
+
+/// A position as it's stored in map-related params such as
+/// `BONFIRE_WARP_PARAM_ST` and `WORLD_MAP_POINT_PARAM_ST`: a small tile given
+/// by its area and grid coordinates, and a position relative to that tile.
+///
+/// Dungeons use their own area number with both grid coordinates set to 0.
+/// Converting those to the overworld map requires `WORLD_MAP_LEGACY_CONV_PARAM`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MapPosition {
+    pub area_no: u8,
+    pub grid_x_no: u8,
+    pub grid_z_no: u8,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl MapPosition {
+    /// Returns the block the position is relative to.
+    pub fn block_id(&self) -> BlockId {
+        BlockId::from_parts(self.area_no, self.grid_x_no, self.grid_z_no, 0)
+    }
+
+    /// Returns the position relative to [MapPosition::block_id].
+    pub fn block_position(&self) -> BlockPosition {
+        BlockPosition::from_xyz(self.x, self.y, self.z)
+    }
+
+    /// Converts a position relative to `block_id` to a map position. Overworld
+    /// positions are made relative to the small tile that contains them.
+    pub fn from_block(block_id: BlockId, position: BlockPosition) -> Option<Self> {
+        match position.to_global(block_id) {
+            Some(global) => Self::from_global(global),
+            None => Some(Self {
+                area_no: block_id.area(),
+                grid_x_no: block_id.block(),
+                grid_z_no: block_id.region(),
+                x: position.x,
+                y: position.y,
+                z: position.z,
+            }),
+        }
+    }
+
+    /// Converts a global position to a map position relative to the small
+    /// tile that contains it.
+    pub fn from_global(global: GlobalPosition) -> Option<Self> {
+        let (block_id, position) = global.to_block(TileScale::Small)?;
+        Some(Self {
+            area_no: block_id.area(),
+            grid_x_no: block_id.block(),
+            grid_z_no: block_id.region(),
+            x: position.x,
+            y: position.y,
+            z: position.z,
+        })
+    }
+
+    /// Converts this to a global position. Returns `None` for positions that
+    /// aren't in the overworld.
+    pub fn to_global(&self) -> Option<GlobalPosition> {
+        self.block_position().to_global(self.block_id())
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::position::{BlockPosition, PositionDelta};
+    use crate::cs::{BlockId, TileScale};
+    use crate::position::{BlockPosition, GlobalPosition, MapPosition, PositionDelta};
 
     use super::HavokPosition;
 
@@ -186,4 +425,118 @@ mod test {
             BlockPosition::from_xyz(2.0, 2.0, 2.0) - delta,
         );
     }
+
+    fn m60(x: u8, z: u8, index: u8) -> BlockId {
+        BlockId::from_parts(60, x, z, index)
+    }
+
+    #[test]
+    fn block_havok_round_trip() {
+        // Block centers as they'd be read from WorldBlockInfo::physics_center.
+        let centers = [
+            (m60(42, 54, 0), HavokPosition::from_xyz(-1024.0, 0.0, 512.0)),
+            (
+                BlockId::from_parts(10, 0, 0, 0),
+                HavokPosition::from_xyz(300.0, -50.0, 20.0),
+            ),
+        ];
+        let position = BlockPosition::from_xyz(10.0, 20.0, -30.0);
+        let havok = position.to_havok_in(m60(42, 54, 0), &centers[..]).unwrap();
+        assert_eq!(HavokPosition::from_xyz(-1014.0, 20.0, 482.0), havok);
+        assert_eq!(Some(position), havok.to_block(m60(42, 54, 0), &centers[..]));
+        assert_eq!(None, havok.to_block(m60(42, 55, 0), &centers[..]));
+    }
+
+    #[test]
+    fn tile_centers() {
+        let origin = BlockPosition::from_xyz(0.0, 5.0, 0.0);
+        let global = |block_id| origin.to_global(block_id).map(|g| (g.x, g.z));
+        assert_eq!(Some((0.0, 0.0)), global(m60(0, 0, 0)));
+        assert_eq!(Some((42.0 * 256.0, 54.0 * 256.0)), global(m60(42, 54, 0)));
+        // m60_21_27_01 covers m60_42_54_00 through m60_43_55_00.
+        assert_eq!(Some((42.5 * 256.0, 54.5 * 256.0)), global(m60(21, 27, 1)));
+        // m60_10_13_02 covers m60_40_52_00 through m60_43_55_00.
+        assert_eq!(Some((41.5 * 256.0, 53.5 * 256.0)), global(m60(10, 13, 2)));
+        assert_eq!(None, global(BlockId::from_parts(10, 0, 0, 0)));
+    }
+
+    #[test]
+    fn global_to_containing_tile() {
+        let global = BlockPosition::from_xyz(127.0, 0.0, -127.0)
+            .to_global(m60(42, 54, 0))
+            .unwrap();
+        assert_eq!(Some(m60(42, 54, 0)), global.tile(TileScale::Small));
+        assert_eq!(Some(m60(21, 27, 1)), global.tile(TileScale::Medium));
+        assert_eq!(Some(m60(10, 13, 2)), global.tile(TileScale::Large));
+
+        // Crossing into the neighboring tile.
+        let (block_id, position) = BlockPosition::from_xyz(129.0, 1.0, 0.0)
+            .to_global(m60(42, 54, 0))
+            .unwrap()
+            .to_block(TileScale::Small)
+            .unwrap();
+        assert_eq!(m60(43, 54, 0), block_id);
+        assert_eq!(BlockPosition::from_xyz(-127.0, 1.0, 0.0), position);
+
+        let outside = GlobalPosition {
+            area: 60,
+            x: -200.0,
+            y: 0.0,
+            z: 0.0,
+            yaw: 0.0,
+        };
+        assert_eq!(None, outside.tile(TileScale::Small));
+    }
+
+    #[test]
+    fn rebase_between_scales() {
+        let position = BlockPosition {
+            x: 10.0,
+            y: 2.0,
+            z: 20.0,
+            yaw: 1.5,
+        };
+        let rebased = position.rebase(m60(42, 54, 0), m60(21, 27, 1)).unwrap();
+        assert_eq!(
+            BlockPosition {
+                x: -118.0,
+                y: 2.0,
+                z: -108.0,
+                yaw: 1.5,
+            },
+            rebased
+        );
+        assert_eq!(
+            Some(position),
+            rebased.rebase(m60(21, 27, 1), m60(42, 54, 0))
+        );
+        assert_eq!(
+            None,
+            position.rebase(m60(42, 54, 0), BlockId::from_parts(61, 42, 54, 0))
+        );
+    }
+
+    #[test]
+    fn map_positions() {
+        let map =
+            MapPosition::from_block(m60(21, 27, 1), BlockPosition::from_xyz(-118.0, 2.0, 150.0))
+                .unwrap();
+        assert_eq!(
+            MapPosition {
+                area_no: 60,
+                grid_x_no: 42,
+                grid_z_no: 55,
+                x: 10.0,
+                y: 2.0,
+                z: 22.0,
+            },
+            map
+        );
+        assert_eq!(m60(42, 55, 0), map.block_id());
+
+        let dungeon = BlockId::from_parts(10, 0, 0, 0);
+        let map = MapPosition::from_block(dungeon, BlockPosition::from_xyz(1.0, 2.0, 3.0)).unwrap();
+        assert_eq!(dungeon, map.block_id());
+        assert_eq!(None, map.to_global());
+    }
 }
@@ -1,5 +1,16 @@
-use glam::EulerRot;
-use std::fmt::{self, Display};
+//! Rotations as the game stores them.
+//!
+//! The game uses the same axes as [crate::position]: Y is up and a yaw of 0
+//! faces along +Z. Euler angles are applied in ZXY order, so a rotation with
+//! angles `EulerAngles(x, y, z)` is `Rz(z) * Rx(x) * Ry(y)` and rotates a
+//! vector by the yaw first, then the pitch, then the roll.
+
+use glam::{EulerRot, Mat3, Quat, Vec3};
+use shared::F32ModelMatrix;
+use std::{
+    fmt::{self, Display},
+    ops::Mul,
+};
 
 #[repr(C, align(16))]
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -24,11 +35,125 @@ impl Display for EulerAngles {
 }
 
 impl Quaternion {
+    /// The rotation that does nothing.
+    pub const IDENTITY: Self = Self(0.0, 0.0, 0.0, 1.0);
+
     #[inline]
     pub fn to_euler_angles(&self) -> EulerAngles {
         let (z, x, y) = glam::Quat::from(*self).to_euler(EulerRot::ZXY);
         EulerAngles(x, y, z)
     }
+
+    /// Creates a rotation from Euler angles applied in ZXY order.
+    #[inline]
+    pub fn from_euler_angles(EulerAngles(x, y, z): EulerAngles) -> Self {
+        Quat::from_euler(EulerRot::ZXY, z, x, y).into()
+    }
+
+    /// Creates a rotation around the up axis, using the same convention as
+    /// [BlockPosition::yaw](crate::position::BlockPosition::yaw).
+    #[inline]
+    pub fn from_yaw(yaw: f32) -> Self {
+        Quat::from_rotation_y(yaw).into()
+    }
+
+    /// Returns the direction this rotation faces, as a yaw using the same
+    /// convention as [BlockPosition::yaw](crate::position::BlockPosition::yaw).
+    ///
+    /// This is the heading of [Quaternion::forward] and ignores any pitch or
+    /// roll, so it's not always the same as the yaw in
+    /// [Quaternion::to_euler_angles]. If the rotation faces straight up or
+    /// down, it's 0.
+    #[inline]
+    pub fn yaw(&self) -> f32 {
+        let forward = self.forward();
+        if forward.x.abs() <= f32::EPSILON && forward.z.abs() <= f32::EPSILON {
+            0.0
+        } else {
+            forward.x.atan2(forward.z)
+        }
+    }
+
+    /// Returns the direction this rotation faces, which is +Z rotated by it.
+    #[inline]
+    pub fn forward(&self) -> Vec3 {
+        self.rotate(Vec3::Z)
+    }
+
+    /// Rotates `vector` by this rotation.
+    #[inline]
+    pub fn rotate(&self, vector: Vec3) -> Vec3 {
+        Quat::from(*self) * vector
+    }
+
+    /// Creates a rotation that faces along `direction`, with its up axis as
+    /// close to `up` as possible.
+    ///
+    /// Returns [Quaternion::IDENTITY] if `direction` is zero or parallel to
+    /// `up`.
+    pub fn look_to(direction: Vec3, up: Vec3) -> Self {
+        let forward = direction.normalize_or_zero();
+        let right = up.cross(forward).normalize_or_zero();
+        if forward == Vec3::ZERO || right == Vec3::ZERO {
+            return Self::IDENTITY;
+        }
+
+        let up = forward.cross(right);
+        Quat::from_mat3(&Mat3::from_cols(right, up, forward)).into()
+    }
+
+    /// Creates a rotation that faces from `eye` towards `target`, with its up
+    /// axis as close to `up` as possible. See [Quaternion::look_to].
+    #[inline]
+    pub fn look_at(eye: Vec3, target: Vec3, up: Vec3) -> Self {
+        Self::look_to(target - eye, up)
+    }
+
+    /// Spherically interpolates between this rotation and `other`, taking the
+    /// shortest path. `t` of 0 returns this rotation and 1 returns `other`.
+    #[inline]
+    pub fn slerp(&self, other: Quaternion, t: f32) -> Self {
+        Quat::from(*self).slerp(other.into(), t).into()
+    }
+
+    /// Returns the rotation that undoes this one.
+    #[inline]
+    pub fn inverse(&self) -> Self {
+        Quat::from(*self).inverse().into()
+    }
+
+    /// Extracts the rotation from a model matrix. The matrix's rotation must
+    /// not be scaled.
+    #[inline]
+    pub fn from_model_matrix(matrix: &F32ModelMatrix) -> Self {
+        Quat::from_mat3a(&matrix.rotation()).into()
+    }
+
+    /// Returns a model matrix with this rotation and no translation.
+    #[inline]
+    pub fn to_model_matrix(&self) -> F32ModelMatrix {
+        glam::Mat4::from_quat((*self).into()).into()
+    }
+}
+
+/// Composes two rotations. As with matrices, `a * b` rotates by `b` first and
+/// then by `a`.
+impl Mul for Quaternion {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        (Quat::from(self) * Quat::from(rhs)).into()
+    }
+}
+
+impl EulerAngles {
+    /// Converts these angles to a quaternion. See
+    /// [Quaternion::from_euler_angles].
+    #[inline]
+    pub fn to_quaternion(&self) -> Quaternion {
+        Quaternion::from_euler_angles(*self)
+    }
 }
 
 impl From<Quaternion> for glam::Quat {
@@ -37,3 +162,112 @@ impl From<Quaternion> for glam::Quat {
         Self::from_xyzw(x, y, z, w)
     }
 }
+
+impl From<glam::Quat> for Quaternion {
+    #[inline]
+    fn from(quat: glam::Quat) -> Self {
+        let [x, y, z, w] = quat.to_array();
+        Self(x, y, z, w)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    use glam::Vec3;
+
+    use super::{EulerAngles, Quaternion};
+
+    const EPSILON: f32 = 1e-5;
+
+    fn assert_vec_eq(expected: Vec3, actual: Vec3) {
+        assert!(
+            expected.abs_diff_eq(actual, EPSILON),
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    fn assert_rotation_eq(expected: Quaternion, actual: Quaternion) {
+        // q and -q are the same rotation.
+        let (expected, actual) = (glam::Quat::from(expected), glam::Quat::from(actual));
+        assert!(
+            expected.abs_diff_eq(actual, EPSILON) || expected.abs_diff_eq(-actual, EPSILON),
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn euler_round_trip() {
+        let angles = EulerAngles(0.3, -1.2, 0.7);
+        let EulerAngles(x, y, z) = angles.to_quaternion().to_euler_angles();
+        assert!((x - 0.3).abs() < EPSILON);
+        assert!((y + 1.2).abs() < EPSILON);
+        assert!((z - 0.7).abs() < EPSILON);
+    }
+
+    #[test]
+    fn euler_order_is_zxy() {
+        let angles = EulerAngles(FRAC_PI_2, FRAC_PI_2, 0.0);
+        // Yaw first turns +Z to +X, which a pitch around X leaves alone.
+        assert_vec_eq(Vec3::X, angles.to_quaternion().forward());
+
+        let expected = Quaternion::from(glam::Quat::from_rotation_z(0.4))
+            * Quaternion::from(glam::Quat::from_rotation_x(0.5))
+            * Quaternion::from_yaw(0.6);
+        assert_rotation_eq(expected, EulerAngles(0.5, 0.6, 0.4).to_quaternion());
+    }
+
+    #[test]
+    fn yaw_convention() {
+        assert_vec_eq(Vec3::Z, Quaternion::from_yaw(0.0).forward());
+        assert_vec_eq(Vec3::X, Quaternion::from_yaw(FRAC_PI_2).forward());
+        assert_vec_eq(-Vec3::Z, Quaternion::from_yaw(PI).forward());
+
+        for yaw in [-2.5, -FRAC_PI_2, 0.0, 1.0, 3.0] {
+            assert!((Quaternion::from_yaw(yaw).yaw() - yaw).abs() < EPSILON);
+        }
+
+        // Pitching in the rotation's own frame doesn't change the heading.
+        let pitched = Quaternion::from_yaw(1.0) * glam::Quat::from_rotation_x(-0.4).into();
+        assert!((pitched.yaw() - 1.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn composition_and_inverse() {
+        let a = Quaternion::from_yaw(FRAC_PI_4);
+        assert_rotation_eq(Quaternion::from_yaw(FRAC_PI_2), a * a);
+        assert_rotation_eq(Quaternion::IDENTITY, a * a.inverse());
+    }
+
+    #[test]
+    fn slerp() {
+        let from = Quaternion::from_yaw(0.0);
+        let to = Quaternion::from_yaw(FRAC_PI_2);
+        assert_rotation_eq(Quaternion::from_yaw(FRAC_PI_4), from.slerp(to, 0.5));
+        assert_rotation_eq(to, from.slerp(to, 1.0));
+    }
+
+    #[test]
+    fn look_at() {
+        let rotation = Quaternion::look_at(Vec3::ZERO, Vec3::new(5.0, 0.0, 0.0), Vec3::Y);
+        assert_rotation_eq(Quaternion::from_yaw(FRAC_PI_2), rotation);
+
+        let down = Quaternion::look_to(Vec3::new(0.0, -1.0, 1.0), Vec3::Y);
+        assert_vec_eq(Vec3::new(0.0, -1.0, 1.0).normalize(), down.forward());
+        assert!(down.yaw().abs() < EPSILON);
+
+        assert_eq!(Quaternion::IDENTITY, Quaternion::look_to(Vec3::Y, Vec3::Y));
+    }
+
+    #[test]
+    fn model_matrix_round_trip() {
+        let rotation = EulerAngles(0.2, 2.0, -0.3).to_quaternion();
+        let matrix = rotation.to_model_matrix();
+        assert_rotation_eq(rotation, Quaternion::from_model_matrix(&matrix));
+        assert_vec_eq(Vec3::ZERO, matrix.translation());
+
+        let rotated: glam::Mat3A = matrix.rotation();
+        assert_vec_eq(rotation.rotate(Vec3::X), (rotated * glam::Vec3A::X).into());
+    }
+}
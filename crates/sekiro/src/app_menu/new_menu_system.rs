@@ -1,11 +1,12 @@
-use std::{borrow::Cow, ptr::NonNull, sync::atomic::AtomicI32};
+use std::{ptr::NonNull, sync::atomic::AtomicI32};
 
-use shared::{FromStatic, OwnedPtr, Subclass, Superclass, UnknownPtr, UnknownStruct};
+use shared::{OwnedPtr, Subclass, Superclass, UnknownPtr, UnknownStruct};
 
-use crate::{Vector, dlkr::DLAllocatorRef, dlut::DLFixedVector, rva, sprj::SprjScaleformValue};
+use crate::{Vector, dlkr::DLAllocatorRef, dlut::DLFixedVector, sprj::SprjScaleformValue};
 
 #[repr(C)]
 // Source of name: RTTI
+#[shared::singleton(rva = app_menu_new_menu_system_ptr, indirect)]
 pub struct NewMenuSystem {
     pub vftable: usize,
     _array_menu_window_job_1: usize,
@@ -49,16 +50,6 @@ impl NewMenuSystem {
     }
 }
 
-impl FromStatic for NewMenuSystem {
-    fn name() -> Cow<'static, str> {
-        "NewMenuSystem".into()
-    }
-
-    fn instance_ptr() -> fromsoftware_shared::InstanceResult<*mut Self> {
-        unsafe { shared::load_static_indirect(rva::get().app_menu_new_menu_system_ptr) }
-    }
-}
-
 #[repr(C)]
 #[derive(Superclass)]
 // Source of name: RTTI
@@ -1,435 +1,27 @@
-use std::borrow::Cow;
-use std::cmp::PartialEq;
-use std::fmt::Display;
-use std::ops::{Deref, DerefMut};
-use std::ptr::NonNull;
+pub use shared::dltx::*;
 
-use crate::dlkr::DLAllocatorRef;
-
-use encoding_rs::{self, DecoderResult};
-
-use thiserror::Error;
-
-use cxx_stl::string::{CxxNarrowString, CxxUtf8String, CxxUtf16String, CxxUtf32String};
-
-#[repr(u8)]
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
-pub enum DLCharacterSet {
-    UTF8 = 0,
-    #[default]
-    UTF16 = 1,
-    Iso8859_1 = 2,
-    ShiftJis = 3,
-    EucJp = 4,
-    UTF32 = 5,
-}
-
-impl DLCharacterSet {
-    /// Returns the [encoding_rs::Encoding] that corresponds to this character
-    /// set, or None if no such encoding exists (which is only possible for
-    /// [DLCharacterSet::UTF32]).
-    pub fn encoding(&self) -> Option<&'static encoding_rs::Encoding> {
-        Some(match self {
-            DLCharacterSet::UTF8 => encoding_rs::UTF_8,
-            #[cfg(target_endian = "little")]
-            DLCharacterSet::UTF16 => encoding_rs::UTF_16LE,
-            #[cfg(target_endian = "big")]
-            DLCharacterSet::UTF16 => encoding_rs::UTF_16BE,
-            DLCharacterSet::Iso8859_1 => encoding_rs::WINDOWS_1252,
-            DLCharacterSet::ShiftJis => encoding_rs::SHIFT_JIS,
-            DLCharacterSet::EucJp => encoding_rs::EUC_JP,
-            DLCharacterSet::UTF32 => return None,
-        })
-    }
-}
-
-#[derive(Error, Debug)]
-pub enum DLStringEncodingError {
-    #[error("Invalid encoding; expected {expected:?} but got {actual}")]
-    InvalidEncoding {
-        expected: DLCharacterSet,
-        actual: u8,
-    },
-    #[error("Error decoding string")]
-    DecodeError,
-    #[error("Error encoding string")]
-    EncodeError,
-    #[error("Unsupported encoding: {0}")]
-    UnsupportedEncoding(u8),
-}
-
-trait CxxString<CharType> {
-    fn new_in(allocator: DLAllocatorRef) -> Self;
-    fn from_chars_in(bytes: &[CharType], allocator: DLAllocatorRef) -> Self;
-    fn as_u8_slice(&self) -> &[u8];
-    fn as_chars(&self) -> &[CharType];
-    fn len(&self) -> usize;
-}
-
-macro_rules! impl_cxx_string {
-    ($string_type:ty, $char_type:ty) => {
-        impl CxxString<$char_type> for $string_type {
-            fn new_in(allocator: DLAllocatorRef) -> Self {
-                Self::new_in(allocator)
-            }
-
-            fn from_chars_in(bytes: &[$char_type], allocator: DLAllocatorRef) -> Self {
-                Self::from_bytes_in(bytes, allocator)
-            }
-            fn as_u8_slice(&self) -> &[u8] {
-                unsafe {
-                    std::slice::from_raw_parts(
-                        self.as_ptr() as *const u8,
-                        self.len() * std::mem::size_of::<$char_type>(),
-                    )
-                }
-            }
-            fn as_chars(&self) -> &[$char_type] {
-                self.as_bytes()
-            }
-            fn len(&self) -> usize {
-                <$string_type>::len(self)
-            }
-        }
-    };
-}
-
-impl_cxx_string!(CxxUtf16String<DLAllocatorRef>, u16);
-impl_cxx_string!(CxxUtf8String<DLAllocatorRef>, u8);
-impl_cxx_string!(CxxNarrowString<DLAllocatorRef>, u8);
-impl_cxx_string!(CxxUtf32String<DLAllocatorRef>, u32);
-
-/// This trait is used to seal the DLStringKind trait, preventing external implementations.
-trait DLStringKindSeal {}
-
-#[allow(private_bounds)]
-pub trait DLStringKind: DLStringKindSeal {
-    type InnerType: CxxString<Self::CharType>;
-    type CharType: Sized + Copy;
-    const ENCODING: DLCharacterSet;
+use fromsoftware_shared_stl::Msvc2015;
 
-    fn encode(s: &str) -> Result<Vec<Self::CharType>, DLStringEncodingError> {
-        match Self::ENCODING {
-            DLCharacterSet::UTF16 => {
-                let mut bytes: Vec<u16> = Vec::with_capacity(s.len() * size_of::<u16>());
-                bytes.extend(s.encode_utf16());
-                // SAFETY: Transmuting Vec<u16> to Vec<Self::CharType> is safe
-                // because the UTF16 arm ensures CharType is u16.
-                Ok(unsafe { std::mem::transmute::<Vec<u16>, Vec<Self::CharType>>(bytes) })
-            }
-            DLCharacterSet::UTF32 => {
-                let mut bytes: Vec<u32> = Vec::with_capacity(s.len() * size_of::<u32>());
-                bytes.extend(s.chars().map(|c| c as u32));
-                // SAFETY: Transmuting Vec<u32> to Vec<Self::CharType> is safe
-                // because the UTF32 arm ensures CharType is u32.
-                Ok(unsafe { std::mem::transmute::<Vec<u32>, Vec<Self::CharType>>(bytes) })
-            }
-            DLCharacterSet::UTF8 => {
-                // We can just slice the string as UTF-8 bytes because Rust's `str` is UTF-8 encoded.
-                let bytes = s.as_bytes().to_vec();
-                // SAFETY: Transmuting Vec<u8> to Vec<Self::CharType> is safe
-                // because this arm ensures CharType is u8.
-                Ok(unsafe { std::mem::transmute::<Vec<u8>, Vec<Self::CharType>>(bytes) })
-            }
-            _ => {
-                let (encoded_bytes, _, had_errors) = Self::ENCODING.encoding().unwrap().encode(s);
-                if had_errors {
-                    return Err(DLStringEncodingError::EncodeError);
-                }
-
-                // SAFETY: Transmuting Vec<u8> to Vec<Self::CharType> is safe
-                // because this arm ensures CharType is u8.
-                Ok(unsafe {
-                    std::mem::transmute::<Vec<u8>, Vec<Self::CharType>>(encoded_bytes.into_owned())
-                })
-            }
-        }
-    }
-
-    fn decode(s: &[u8]) -> Result<Cow<'_, str>, DLStringEncodingError> {
-        match Self::ENCODING {
-            DLCharacterSet::UTF16 => {
-                if !s.len().is_multiple_of(std::mem::size_of::<u16>()) {
-                    return Err(DLStringEncodingError::DecodeError);
-                }
-                let u16_slice =
-                    unsafe { std::slice::from_raw_parts(s.as_ptr() as *const u16, s.len() / 2) };
-                char::decode_utf16(u16_slice.iter().cloned())
-                    .map(|r| r.map_err(|_| DLStringEncodingError::DecodeError))
-                    .collect::<Result<String, _>>()
-                    .map(Cow::Owned)
-            }
-            DLCharacterSet::UTF32 => {
-                if !s.len().is_multiple_of(std::mem::size_of::<u32>()) {
-                    return Err(DLStringEncodingError::DecodeError);
-                }
-                let u32_slice =
-                    unsafe { std::slice::from_raw_parts(s.as_ptr() as *const u32, s.len() / 4) };
-                u32_slice
-                    .iter()
-                    .map(|&c| std::char::from_u32(c).ok_or(DLStringEncodingError::DecodeError))
-                    .collect::<Result<String, _>>()
-                    .map(Cow::Owned)
-            }
-            DLCharacterSet::UTF8 => {
-                let s = std::str::from_utf8(s).map_err(|_| DLStringEncodingError::DecodeError)?;
-                Ok(Cow::Borrowed(s))
-            }
-            _ => {
-                let (cow, _, had_errors) = Self::ENCODING.encoding().unwrap().decode(s);
-                if had_errors {
-                    Err(DLStringEncodingError::DecodeError)
-                } else {
-                    Ok(cow)
-                }
-            }
-        }
-    }
-}
-
-pub struct DLUTF8StringKind;
-impl DLStringKindSeal for DLUTF8StringKind {}
-impl DLStringKind for DLUTF8StringKind {
-    type InnerType = CxxUtf8String<DLAllocatorRef>;
-    type CharType = u8;
-    const ENCODING: DLCharacterSet = DLCharacterSet::UTF8;
-}
-
-pub struct DLISO8859_1StringKind;
-impl DLStringKindSeal for DLISO8859_1StringKind {}
-impl DLStringKind for DLISO8859_1StringKind {
-    type InnerType = CxxNarrowString<DLAllocatorRef>;
-    type CharType = u8;
-    const ENCODING: DLCharacterSet = DLCharacterSet::Iso8859_1;
-}
-
-pub struct DLShiftJisStringKind;
-impl DLStringKindSeal for DLShiftJisStringKind {}
-impl DLStringKind for DLShiftJisStringKind {
-    type InnerType = CxxNarrowString<DLAllocatorRef>;
-    type CharType = u8;
-    const ENCODING: DLCharacterSet = DLCharacterSet::ShiftJis;
-}
-
-pub struct DLEucJpStringKind;
-impl DLStringKindSeal for DLEucJpStringKind {}
-impl DLStringKind for DLEucJpStringKind {
-    type InnerType = CxxNarrowString<DLAllocatorRef>;
-    type CharType = u8;
-    const ENCODING: DLCharacterSet = DLCharacterSet::EucJp;
-}
-
-pub struct DLUTF16StringKind;
-impl DLStringKindSeal for DLUTF16StringKind {}
-impl DLStringKind for DLUTF16StringKind {
-    type InnerType = CxxUtf16String<DLAllocatorRef>;
-    type CharType = u16;
-    const ENCODING: DLCharacterSet = DLCharacterSet::UTF16;
-}
-
-pub struct DLUTF32StringKind;
-impl DLStringKindSeal for DLUTF32StringKind {}
-impl DLStringKind for DLUTF32StringKind {
-    type InnerType = CxxUtf32String<DLAllocatorRef>;
-    type CharType = u32;
-    const ENCODING: DLCharacterSet = DLCharacterSet::UTF32;
-}
-
-#[repr(C)]
-pub struct DLString<T: DLStringKind = DLUTF16StringKind> {
-    base: T::InnerType,
-    encoding: DLCharacterSet,
-}
-
-impl<T: DLStringKind> DLString<T> {
-    pub fn new(allocator: DLAllocatorRef) -> Self {
-        Self {
-            base: T::InnerType::new_in(allocator.clone()),
-            encoding: T::ENCODING,
-        }
-    }
-
-    pub fn from_str(allocator: DLAllocatorRef, s: &str) -> Result<Self, DLStringEncodingError> {
-        let encoded: Vec<T::CharType> = T::encode(s)?;
-
-        Ok(Self {
-            base: T::InnerType::from_chars_in(&encoded, allocator.clone()),
-            encoding: T::ENCODING,
-        })
-    }
-
-    pub fn to_str(&self) -> Result<String, DLStringEncodingError> {
-        let bytes = self.base.as_u8_slice();
-        T::decode(bytes).map(|cow| cow.into_owned())
-    }
-
-    pub fn copy<U: DLStringKind>(
-        allocator: DLAllocatorRef,
-        other: &DLString<U>,
-    ) -> Result<Self, DLStringEncodingError> {
-        // If the encodings match, we can directly copy the bytes
-        if T::ENCODING == U::ENCODING {
-            // SAFETY: T::ENCODING == U::ENCODING implies T::CharType is compatible with U::CharType.
-            let bytes: &[T::CharType] = unsafe { std::mem::transmute(other.base.as_chars()) };
-            Ok(Self {
-                base: T::InnerType::from_chars_in(bytes, allocator.clone()),
-                encoding: T::ENCODING,
-            })
-        } else {
-            // If encodings differ, we need to decode and re-encode
-            let decoded = T::decode(other.base.as_u8_slice())?;
-            DLString::from_str(allocator, &decoded)
-        }
-    }
-}
-
-impl<T: DLStringKind> Deref for DLString<T> {
-    type Target = T::InnerType;
-
-    fn deref(&self) -> &Self::Target {
-        &self.base
-    }
-}
-
-impl<T: DLStringKind> DerefMut for DLString<T> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.base
-    }
-}
-
-impl<T: DLStringKind> Display for DLString<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.to_str() {
-            Ok(s) => write!(f, "{s}"),
-            Err(_) => Err(std::fmt::Error),
-        }
-    }
-}
-
-impl<T: DLStringKind, S: AsRef<str>> PartialEq<S> for DLString<T> {
-    fn eq(&self, other: &S) -> bool {
-        let other = other.as_ref();
-        match T::ENCODING {
-            DLCharacterSet::UTF8 => self.base.as_u8_slice() == other.as_bytes(),
-            DLCharacterSet::UTF32 => {
-                // Safety: If this is UTF32, its characters are u32s.
-                unsafe { std::mem::transmute::<&[T::CharType], &[u32]>(self.base.as_chars()) }
-                    .iter()
-                    .map(|c| char::try_from(*c))
-                    .eq(other.chars().map(Ok))
-            }
-            _ => {
-                // Do equality comparisons byte-by-byte on the UTF-8
-                // representations of each string. It would be simpler to just
-                // convert to a string and compare that, but it would be
-                // substantially less efficient because it would involve an
-                // allocation for eqach comparison.
-
-                let mut decoder = T::ENCODING.encoding().unwrap().new_decoder();
-                if decoder
-                    .max_utf8_buffer_length_without_replacement(self.base.len())
-                    .map(|len| len < other.len())
-                    .unwrap_or(true)
-                {
-                    // If the other string is big enough that this string can't
-                    // possibly decode to it, exit early without even attempting
-                    // to decode. This is most likely if this is the empty
-                    // string.
-                    return false;
-                }
-
-                let mut our_bytes = self.base.as_u8_slice();
-                let mut other_bytes = other.as_bytes().iter();
-                let mut output: [u8; 0x20] = [0; 0x20];
-                loop {
-                    let (result, bytes_read, bytes_written) =
-                        decoder.decode_to_utf8_without_replacement(our_bytes, &mut output, true);
-                    if matches!(result, DecoderResult::Malformed(_, _)) {
-                        // Malformed strings are never equal to valid UTF-8.
-                        return false;
-                    }
-
-                    for &our_byte in output.iter().take(bytes_written) {
-                        let Some(&their_byte) = other_bytes.next() else {
-                            // If this string has more bytes than the other
-                            // string, they're not equal.
-                            return false;
-                        };
-
-                        if our_byte != their_byte {
-                            // If this string has different bytes than the other
-                            // string, they're not equal.
-                            return false;
-                        }
-                    }
-
-                    if matches!(result, DecoderResult::InputEmpty) {
-                        // If this string has fewer bytes than the other string,
-                        // they're not equal, but if it has the same number and
-                        // they've been equivalent up to this point then they
-                        // are equal.
-                        return other_bytes.next().is_none();
-                    }
-
-                    our_bytes = &our_bytes[bytes_read..];
-                }
-            }
-        }
-    }
-}
+use crate::dlkr::DLAllocatorRef;
 
-#[repr(C)]
-pub struct DLRawString<T: DLStringKind = DLUTF16StringKind> {
-    vftable: usize,
-    backing_string: Option<NonNull<T::CharType>>,
-    pub length: usize,
-    unk18: u32,
-    pub char_size: u16,
-    pub encoding: DLCharacterSet,
-    pub flags: u8,
-}
+/// A [shared::dltx::DLString] that allocates through the game's
+/// [DLAllocatorRef], laid out the way MSVC 2015 and later lay out
+/// `std::basic_string`.
+pub type DLString<T = DLUTF16StringKind> = shared::dltx::DLString<T, DLAllocatorRef, Msvc2015>;
 
-impl<T: DLStringKind> DLRawString<T> {
-    pub fn is_empty(&self) -> bool {
-        self.length == 0
-    }
-    pub fn to_str(&self) -> Result<String, DLStringEncodingError> {
-        let bytes: &[u8] = self.backing_string.as_ref().map_or(&[], |ptr| unsafe {
-            std::slice::from_raw_parts(
-                ptr.as_ptr() as *const u8,
-                self.length * std::mem::size_of::<T::CharType>(),
-            )
-        });
-        T::decode(bytes).map(|cow| cow.into_owned())
-    }
-}
+#[cfg(test)]
+mod test {
+    use std::ptr::NonNull;
 
-impl<T: DLStringKind> Display for DLRawString<T> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self.to_str() {
-            Ok(s) => write!(f, "{s}"),
-            Err(_) => Err(std::fmt::Error),
-        }
-    }
-}
-pub type DLCodedString<T> = DLRawString<T>;
+    use super::*;
 
-#[repr(C)]
-/// Source of name: RTTI
-/// In original code, generic type was something like `DLInplaceStr<1,16,DLTX::DLCodedStr<1>>`
-/// Where 1 is DLCharacterSet::UTF16 and 16 is the size of the inline buffer in characters.
-/// This version uses StringKind trait to get the character set and size.
-pub struct DLInplaceStr<T: DLStringKind, const N: usize> {
-    /// Underlying DLCodedString used for all operations.
-    pub base: DLCodedString<T>,
-    /// Buffer used to store the string data.
-    /// backing_string in base is a pointer to this buffer.
-    pub bytes: [T::CharType; N],
-    unk: usize,
-}
+    #[test]
+    fn proper_layout() {
+        assert_eq!(0x30, size_of::<DLString>());
 
-impl<T: DLStringKind, const N: usize> Display for DLInplaceStr<T, N> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.base.fmt(f)
+        let string = DLString::<DLUTF16StringKind>::new(DLAllocatorRef::from(NonNull::dangling()));
+        let offset =
+            std::ptr::from_ref(string.allocator()).addr() - std::ptr::from_ref(&string).addr();
+        assert_eq!(0x0, offset);
     }
 }
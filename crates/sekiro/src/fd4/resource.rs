@@ -38,7 +38,8 @@ pub struct FD4ResCap {
 /// A hash table of [FD4ResCap]s indexed by [FD4ResCap::name].
 ///
 /// The resource is hashed to a u32 using some FNV variant (using
-/// [FD4BasicHashString::hash]). That hash is then modulo'd by
+/// [FD4BasicHashString::hash]), which is probably but not yet verifiably
+/// [shared::fd4::fd4_hash_code_units]. That hash is then modulo'd by
 /// [FD4ResCapHolder::bucket_count] to find the appropriate bucket. Collisions
 /// are added as the head of the [FD4ResCap::next_item] linked list.
 ///
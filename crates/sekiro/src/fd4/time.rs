@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 #[repr(C)]
 #[derive(Debug)]
 pub struct FD4Time {
@@ -5,6 +7,15 @@ pub struct FD4Time {
     pub time: f32,
 }
 
+/// Converts the time in seconds to a [Duration], so it can be passed to
+/// functions like [shared::task::executor::sleep]. Negative times become zero and
+/// infinite times become [Duration::MAX].
+impl From<&FD4Time> for Duration {
+    fn from(time: &FD4Time) -> Self {
+        Duration::try_from_secs_f32(time.time.max(0.0)).unwrap_or(Duration::MAX)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -13,4 +24,12 @@ mod test {
     fn proper_sizes() {
         assert_eq!(0x10, size_of::<FD4Time>());
     }
+
+    #[test]
+    fn converts_to_duration() {
+        let time = |time| FD4Time { vftable: 0, time };
+        assert_eq!(Duration::from_millis(1500), (&time(1.5)).into());
+        assert_eq!(Duration::ZERO, (&time(-1.0)).into());
+        assert_eq!(Duration::MAX, (&time(f32::INFINITY)).into());
+    }
 }
@@ -1,4 +1,4 @@
-use std::{ptr::NonNull, slice};
+use std::{fmt::Display, ptr::NonNull, slice, str::FromStr};
 
 use thiserror::Error;
 
@@ -18,7 +18,7 @@ pub enum EventFlagError {
 }
 
 /// A handle pointing to a one-bit event flag in the game's event storage.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct EventFlag(u32);
 
 /// A valid event flag
@@ -85,6 +85,33 @@ impl From<EventFlag> for u32 {
     }
 }
 
+impl EventFlagLayout for EventFlag {
+    const CHUNK_SIZE: u32 = 1000;
+    type Error = EventFlagError;
+
+    fn from_id(id: u32) -> Result<Self, Self::Error> {
+        id.try_into()
+    }
+
+    fn id(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Display for EventFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for EventFlag {
+    type Err = ParseEventFlagError<EventFlagError>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
 #[repr(C)]
 // Source of name: FD4Singleton error handling
 /// The singleton that manages the game's event flags.
@@ -166,6 +193,19 @@ impl SprjEventFlagMan {
     }
 }
 
+impl EventFlagStorage for SprjEventFlagMan {
+    type Flag = EventFlag;
+    type Bits = [u32];
+
+    fn flag_bits(&self, flag: EventFlag) -> Option<&[u32]> {
+        self.get_event_zone(flag).map(EventZone::words)
+    }
+
+    fn flag_bits_mut(&mut self, flag: EventFlag) -> Option<&mut [u32]> {
+        self.get_event_zone_mut(flag).map(EventZone::words_mut)
+    }
+}
+
 #[repr(C)]
 // Source of name: Elden Ring RTTI
 /// The container for the actual event data.
@@ -1,10 +1,10 @@
-use std::{borrow::Cow, ptr::NonNull};
+use std::ptr::NonNull;
 
 use super::WorldRes;
-use crate::rva;
 use shared::*;
 
 #[repr(C)]
+#[shared::singleton(rva = field_area_ptr, indirect)]
 pub struct FieldArea {
     _vftable: usize,
     _unk08: usize,
@@ -43,16 +43,6 @@ pub struct FieldArea {
     _unkdc: u32,
 }
 
-impl FromStatic for FieldArea {
-    fn name() -> Cow<'static, str> {
-        "FieldArea".into()
-    }
-
-    fn instance_ptr() -> InstanceResult<*mut Self> {
-        unsafe { shared::load_static_indirect(rva::get().field_area_ptr) }
-    }
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
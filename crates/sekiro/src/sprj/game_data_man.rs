@@ -1,13 +1,12 @@
-use std::borrow::Cow;
-
 use pelite::pe64::Pe;
-use shared::{FromStatic, InstanceResult, OwnedPtr, Program, UnknownStruct};
+use shared::{OwnedPtr, Program, UnknownStruct};
 
 use super::{ItemId, PlayerGameData};
 use crate::{Vector, fd4::FD4Time, rva};
 
 #[repr(C)]
 // Source of name: RTTI
+#[shared::singleton(rva = game_data_man_ptr, indirect)]
 pub struct GameDataMan {
     _trophy_equip_data: OwnedPtr<UnknownStruct<0xc10>>,
 
@@ -96,16 +95,6 @@ impl GameDataMan {
     }
 }
 
-impl FromStatic for GameDataMan {
-    fn name() -> Cow<'static, str> {
-        "GameDataMan".into()
-    }
-
-    fn instance_ptr() -> InstanceResult<*mut Self> {
-        unsafe { shared::load_static_indirect(rva::get().game_data_man_ptr) }
-    }
-}
-
 #[repr(C)]
 // Source of name: debug string
 pub struct OptionsData {
@@ -1,14 +1,15 @@
 use std::alloc::{Layout, LayoutError, alloc_zeroed};
-use std::{borrow::Cow, fmt, ops, ptr, sync::LazyLock};
+use std::{fmt, ops, ptr, sync::LazyLock};
 
 use pelite::pe64::Pe;
-use shared::{FromStatic, IncompleteArrayField, InstanceResult, OwnedPtr, Program};
+use shared::{IncompleteArrayField, OwnedPtr, Program};
 
 use super::ItemId;
 use crate::{dlkr::DLAllocatorRef, rva, stl::Vector};
 
 #[repr(C)]
 // Source of name: RTTI
+#[shared::singleton(rva = map_item_man_ptr, indirect)]
 pub struct MapItemMan {
     _vftable: usize,
     _unk08: u32,
@@ -51,17 +52,6 @@ pub struct MapItemMan {
     _unk190: u16,
 }
 
-impl FromStatic for MapItemMan {
-    fn name() -> Cow<'static, str> {
-        "MapItemMan".into()
-    }
-
-    /// Returns the singleton instance of `MapItemMan`.
-    fn instance_ptr() -> InstanceResult<*mut Self> {
-        unsafe { shared::load_static_indirect(rva::get().map_item_man_ptr) }
-    }
-}
-
 /// The address for the function call that grants an item to the player with a
 /// visible popup. Callers can use this to hook the function for their needs.
 ///
@@ -1,11 +1,10 @@
-use std::borrow::Cow;
-
 use shared::*;
 
-use crate::{dltx::DLString, fd4::FD4Time, rva};
+use crate::{dltx::DLString, fd4::FD4Time};
 
 // Source of name: RTTI
 #[repr(C)]
+#[shared::singleton(rva = sprj_menu_man_ptr, indirect)]
 pub struct MenuMan {
     _vftable: usize,
     _unk08: [u8; 0x8],
@@ -511,19 +510,11 @@ impl MenuMan {
     }
 }
 
-impl FromStatic for MenuMan {
-    fn name() -> Cow<'static, str> {
-        "MenuMan".into()
-    }
-
-    fn instance_ptr() -> fromsoftware_shared::InstanceResult<*mut Self> {
-        unsafe { shared::load_static_indirect(rva::get().sprj_menu_man_ptr) }
-    }
-}
-
 /// A spot on screen that shows a display indicating that the player can
 /// interact with it with the action button.
 #[repr(C)]
+#[derive(IsEmpty)]
+#[empty(field = unique_id, value = 0)]
 // Source of name: debug menus
 pub struct MenuActionSpot {
     pub unique_id: u32,
@@ -558,12 +549,6 @@ pub struct MenuActionSpot {
     _unk46: [u8; 0xa],
 }
 
-unsafe impl IsEmpty for MenuActionSpot {
-    fn is_empty(value: &MaybeEmpty<Self>) -> bool {
-        *unsafe { value.as_non_null().cast::<u32>().as_ref() } == 0
-    }
-}
-
 /// This seems to be unused.
 #[repr(C)]
 // Source of name: debug menus
@@ -649,6 +634,8 @@ pub struct MenuInfoDataEntry {
 
 /// An enemy's status bar which indicates their health and stamina.
 #[repr(C, packed(4))]
+#[derive(IsEmpty)]
+#[empty(field = handle, as = i32, value = -1)]
 // Source of name: debug menus
 pub struct EnemyGauge {
     /// This name comes from debug data, but the behavior isn't yet well-understood.
@@ -686,12 +673,6 @@ pub struct EnemyGauge {
     pub time_since_hit: f32,
 }
 
-unsafe impl IsEmpty for EnemyGauge {
-    fn is_empty(value: &MaybeEmpty<Self>) -> bool {
-        *unsafe { value.as_non_null().cast::<i32>().as_ref() } == -1
-    }
-}
-
 #[cfg(test)]
 mod test {
     use super::*;
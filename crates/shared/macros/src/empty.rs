@@ -0,0 +1,184 @@
+use proc_macro::TokenStream;
+use quote::*;
+use syn::spanned::Spanned;
+use syn::*;
+
+/// A single `#[empty(...)]` attribute.
+enum Condition {
+    /// `#[empty(zeroed)]`
+    Zeroed,
+
+    /// `#[empty(field = ..., as = ..., value = ...)]` or
+    /// `#[empty(field = ..., as = ..., with = ...)]`
+    Field(Box<FieldCondition>),
+}
+
+struct FieldCondition {
+    field: Ident,
+    as_type: Option<Type>,
+    check: Check,
+}
+
+enum Check {
+    Value(Expr),
+    With(Expr),
+}
+
+/// A helper for [derive_is_empty] that returns a [syn::Result].
+pub fn is_empty_helper(input: TokenStream) -> Result<TokenStream> {
+    let struct_: ItemStruct = syn::parse(input)?;
+    let name = &struct_.ident;
+    let (impl_generics, ty_generics, where_clause) = struct_.generics.split_for_impl();
+
+    let conditions = struct_
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("empty"))
+        .map(parse_condition)
+        .collect::<Result<Vec<_>>>()?;
+    if conditions.is_empty() {
+        return Err(Error::new(
+            name.span(),
+            "expected at least one #[empty(...)] attribute",
+        ));
+    }
+
+    let field_types = match &struct_.fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|f| (f.ident.clone().unwrap(), f.ty.clone()))
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+
+    let checks = conditions
+        .into_iter()
+        .map(|condition| match condition {
+            Condition::Zeroed => Ok(quote! {
+                unsafe {
+                    ::std::slice::from_raw_parts(
+                        ptr as *const u8,
+                        ::std::mem::size_of::<Self>(),
+                    )
+                }
+                .iter()
+                .all(|b| *b == 0)
+            }),
+            Condition::Field(condition) => {
+                let FieldCondition {
+                    field,
+                    as_type,
+                    check,
+                } = *condition;
+                let Some((_, field_type)) = field_types.iter().find(|(name, _)| *name == field)
+                else {
+                    return Err(Error::new(
+                        field.span(),
+                        format!("{} has no field named {}", name, field),
+                    ));
+                };
+
+                let value = match &as_type {
+                    Some(as_type) => quote_spanned! { as_type.span() => {
+                        const {
+                            assert!(
+                                ::std::mem::size_of::<#as_type>()
+                                    <= ::std::mem::size_of::<#field_type>(),
+                                "#[empty(as = ...)] type is larger than the field",
+                            )
+                        };
+                        unsafe {
+                            ::std::ptr::addr_of!((*ptr).#field)
+                                .cast::<#as_type>()
+                                .read_unaligned()
+                        }
+                    }},
+                    None => quote! {
+                        unsafe { &*::std::ptr::addr_of!((*ptr).#field) }
+                    },
+                };
+                let reference = match as_type {
+                    Some(_) => quote! { &field_value },
+                    None => quote! { field_value },
+                };
+
+                let check = match check {
+                    Check::Value(expected) => quote! { *#reference == (#expected) },
+                    Check::With(predicate) => quote! { (#predicate)(#reference) },
+                };
+                Ok(quote! {{
+                    let field_value = #value;
+                    #check
+                }})
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(TokenStream::from(quote! {
+        unsafe impl #impl_generics ::fromsoftware_shared::IsEmpty for #name #ty_generics
+            #where_clause
+        {
+            fn is_empty(value: &::fromsoftware_shared::MaybeEmpty<Self>) -> bool {
+                let ptr = value.as_non_null().as_ptr() as *const Self;
+                #((#checks))&&*
+            }
+        }
+    }))
+}
+
+fn parse_condition(attr: &Attribute) -> Result<Condition> {
+    let mut zeroed = false;
+    let mut field = None;
+    let mut as_type = None;
+    let mut value = None;
+    let mut with = None;
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("zeroed") {
+            zeroed = true;
+        } else if meta.path.is_ident("field") {
+            field = Some(meta.value()?.parse::<Ident>()?);
+        } else if meta.path.is_ident("as") {
+            as_type = Some(meta.value()?.parse::<Type>()?);
+        } else if meta.path.is_ident("value") {
+            value = Some(meta.value()?.parse::<Expr>()?);
+        } else if meta.path.is_ident("with") {
+            with = Some(meta.value()?.parse::<Expr>()?);
+        } else {
+            return Err(meta.error("expected zeroed, field, as, value, or with"));
+        }
+        Ok(())
+    })?;
+
+    if zeroed {
+        if field.is_some() || as_type.is_some() || value.is_some() || with.is_some() {
+            return Err(Error::new(
+                attr.span(),
+                "#[empty(zeroed)] can't be combined with other options",
+            ));
+        }
+        return Ok(Condition::Zeroed);
+    }
+
+    let Some(field) = field else {
+        return Err(Error::new(
+            attr.span(),
+            "expected either zeroed or field = ...",
+        ));
+    };
+    let check = match (value, with) {
+        (Some(value), None) => Check::Value(value),
+        (None, Some(with)) => Check::With(with),
+        _ => {
+            return Err(Error::new(
+                attr.span(),
+                "expected exactly one of value = ... or with = ...",
+            ));
+        }
+    };
+    Ok(Condition::Field(Box::new(FieldCondition {
+        field,
+        as_type,
+        check,
+    })))
+}
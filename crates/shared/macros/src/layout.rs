@@ -0,0 +1,108 @@
+use proc_macro::TokenStream;
+use quote::*;
+use syn::spanned::Spanned;
+use syn::*;
+
+/// A helper for [derive_game_layout] that returns a [syn::Result].
+pub fn game_layout_helper(input: TokenStream) -> Result<TokenStream> {
+    let struct_: ItemStruct = syn::parse(input)?;
+    let name = &struct_.ident;
+
+    if let Some(param) = struct_
+        .generics
+        .params
+        .iter()
+        .find(|p| !matches!(p, GenericParam::Lifetime(_)))
+    {
+        return Err(Error::new(
+            param.span(),
+            "GameLayout can't check the layout of structs with type or const parameters",
+        ));
+    }
+
+    // Layout doesn't depend on lifetimes, so check the 'static version of the
+    // struct.
+    let ty: Type = if struct_.generics.params.is_empty() {
+        parse_quote! { #name }
+    } else {
+        let lifetimes = struct_.generics.lifetimes().map(|_| quote! { 'static });
+        parse_quote! { #name<#(#lifetimes),*> }
+    };
+
+    let Fields::Named(fields) = &struct_.fields else {
+        return Err(Error::new(struct_.fields.span(), "expected named fields"));
+    };
+
+    let mut assertions = Vec::new();
+    for field in &fields.named {
+        let ident = field.ident.as_ref().unwrap();
+        let explicit = parse_field_offset(&field.attrs)?;
+        let Some(offset) = explicit.or_else(|| offset_from_name(ident)) else {
+            continue;
+        };
+
+        let message = format!("{name}::{ident} isn't at offset {offset:#x}");
+        assertions.push(quote_spanned! { field.span() =>
+            assert!(::std::mem::offset_of!(#ty, #ident) == #offset, #message);
+        });
+    }
+
+    if let Some(size) = parse_struct_size(&struct_.attrs)? {
+        let message = format!("{name} isn't {size:#x} bytes large");
+        assertions.push(quote! {
+            assert!(::std::mem::size_of::<#ty>() == #size, #message);
+        });
+    }
+
+    Ok(TokenStream::from(quote! {
+        const _: () = {
+            #(#assertions)*
+        };
+    }))
+}
+
+/// Returns the offset encoded in a field name like `unk38` or `_unk7c`, if
+/// this is such a field.
+fn offset_from_name(ident: &Ident) -> Option<usize> {
+    let name = ident.to_string();
+    let hex = name
+        .strip_prefix('_')
+        .unwrap_or(&name)
+        .strip_prefix("unk")?;
+    if hex.is_empty() || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    usize::from_str_radix(hex, 16).ok()
+}
+
+/// Parses `#[layout(offset = ...)]` from a field's attributes.
+fn parse_field_offset(attrs: &[Attribute]) -> Result<Option<usize>> {
+    let mut offset = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("layout")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("offset") {
+                offset = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected offset"))
+            }
+        })?;
+    }
+    Ok(offset)
+}
+
+/// Parses `#[layout(size = ...)]` from a struct's attributes.
+fn parse_struct_size(attrs: &[Attribute]) -> Result<Option<usize>> {
+    let mut size = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("layout")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("size") {
+                size = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected size"))
+            }
+        })?;
+    }
+    Ok(size)
+}
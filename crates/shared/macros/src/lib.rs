@@ -1,38 +1,57 @@
 use proc_macro::TokenStream;
-use quote::*;
-use syn::*;
 
 mod multi_param;
 
+mod empty;
 mod for_all_subclasses;
+mod layout;
 mod researching;
+mod singleton;
 mod stepper;
 mod subclass;
 mod superclass;
 mod utils;
 
-/// Annotates a struct as a Dantelion2 singleton to be looked up using a single
-/// string argument.
+/// Annotates a struct as a static object that can be looked up with
+/// `fromsoftware_shared::FromStatic`.
 ///
-/// This is only guaranteed to make the struct work with the
-/// `fromsoftware_shared::singleton::get_instance` function. Any other added
-/// functionality is considered an implementation detail and shouldn't be relied
-/// upon.
+/// ## DLRF singletons
+///
+/// `#[singleton("Name")]` marks the struct as a Dantelion2 singleton that's
+/// looked up by its DLRF reflection name. This implements
+/// `fromsoftware_shared::FromSingleton`, which in turn provides `FromStatic`.
+///
+/// ## RVA lookup
+///
+/// `#[singleton(rva = field_name)]` looks the object up at an RVA instead. This
+/// assumes that the crate contains a `crate::rva` module whose `get()` function
+/// returns a struct with a public `field_name` field, just like the
+/// `Superclass` and `Subclass` derives.
+///
+/// By default, the RVA points directly to the object. Add `indirect` if it
+/// points to a pointer to the object instead, as is the case for objects that
+/// are allocated at runtime:
+///
+/// ```rs
+/// #[singleton(rva = game_data_man, indirect)]
+/// pub struct GameDataMan { ... }
+/// ```
+///
+/// The object's `FromStatic::name` defaults to the struct's name. Use `name =
+/// "..."` to override it.
+///
+/// ## Fallback
+///
+/// If both a DLRF name and an RVA are provided, the object is looked up through
+/// DLRF first, and only through the RVA if that fails:
+///
+/// ```rs
+/// #[singleton("CSMenuMan", rva = cs_menu_man, indirect)]
+/// pub struct CSMenuManImp { ... }
+/// ```
 #[proc_macro_attribute]
 pub fn singleton(args: TokenStream, input: TokenStream) -> TokenStream {
-    let input_struct: ItemStruct = parse_macro_input!(input as ItemStruct);
-    let input_struct_ident = input_struct.ident.clone();
-    let dlrf_name = parse_macro_input!(args as LitStr).value();
-
-    TokenStream::from(quote! {
-        #input_struct
-
-        impl ::fromsoftware_shared::FromSingleton for #input_struct_ident {
-            fn name() -> ::std::borrow::Cow<'static, str> {
-                ::std::borrow::Cow::Borrowed(#dlrf_name)
-            }
-        }
-    })
+    singleton::singleton_helper(args, input).unwrap_or_else(|err| err.into_compile_error().into())
 }
 
 /// Annotates a trait to automatically generate getters and setters that forward
@@ -306,7 +325,83 @@ pub fn for_all_subclasses(_args: TokenStream, input: TokenStream) -> TokenStream
     }
 }
 
-/// A derive macro that implements the StepperStates trait on a given enum.
+/// A derive macro for `fromsoftware_shared::IsEmpty`.
+///
+/// Each `#[empty(...)]` attribute on the struct describes one condition, and
+/// the struct is empty only if all of them hold:
+///
+/// * `#[empty(field = name, value = expr)]` checks whether the field `name` is
+///   equal to `expr`.
+///
+/// * `#[empty(field = name, with = expr)]` calls `expr` (generally a closure)
+///   with a reference to the field `name` and checks whether it returns true.
+///
+/// * Either of the above can include `as = Type` to read the field's bytes as
+///   `Type` instead of its declared type. This is useful when the empty
+///   sentinel isn't a valid value of the declared type, such as a null
+///   `OwnedPtr` or an `ItemId` of -1. `Type` can't be larger than the field.
+///
+/// * `#[empty(zeroed)]` checks whether every byte of the struct is zero.
+///
+/// ```rs
+/// #[repr(C)]
+/// #[derive(IsEmpty)]
+/// #[empty(field = handle, as = i32, value = -1)]
+/// pub struct EnemyGauge {
+///     pub handle: u32,
+///     // ...
+/// }
+/// ```
+///
+/// ## Safety
+///
+/// The `fromsoftware_shared::IsEmpty` trait is unsafe, and even though there's
+/// currently no way to require that a derive macro be explicitly flagged as
+/// unsafe, this does not add any additional safety guarantees beyond a manual
+/// implementation. In particular, the fields this checks are read from values
+/// that may be empty, so they must be valid for their declared type (or for
+/// the `as` type) even when the struct is empty.
+#[proc_macro_derive(IsEmpty, attributes(empty))]
+pub fn derive_is_empty(input: TokenStream) -> TokenStream {
+    match empty::is_empty_helper(input) {
+        Ok(stream) => stream,
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// A derive macro that checks a struct's layout at compile time.
+///
+/// Any field whose name is `unk` or `_unk` followed by a hexadecimal number,
+/// like `unk38` or `_unk7c`, is asserted to be at that offset within the
+/// struct. Other fields can be checked by annotating them with
+/// `#[layout(offset = 0x...)]`, and the size of the struct as a whole can be
+/// checked by annotating it with `#[layout(size = 0x...)]`. If any of these
+/// don't match, the crate fails to build.
+///
+/// ```rs
+/// #[repr(C)]
+/// #[derive(GameLayout)]
+/// #[layout(size = 0x18)]
+/// pub struct Example {
+///     vftable: usize,
+///     unk8: u32,
+///     #[layout(offset = 0x10)]
+///     pub count: u64,
+/// }
+/// ```
+///
+/// This only works for structs without type or const parameters, since their
+/// layout is only known once they're instantiated.
+#[proc_macro_derive(GameLayout, attributes(layout))]
+pub fn derive_game_layout(input: TokenStream) -> TokenStream {
+    match layout::game_layout_helper(input) {
+        Ok(stream) => stream,
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+/// A derive macro that implements the StepperStates trait on a given enum,
+/// including conversions to and from the states' raw discriminants.
 ///
 /// - The enum must be exhaustive (represent all states and no more).
 /// - The enum must have a -1 state for inactive steppers.
@@ -327,7 +422,7 @@ pub fn derive_stepper_states(input: TokenStream) -> TokenStream {
 
 /// A derive macro for `fromsoftware_shared::Researching` that automatically
 /// makes all `unk` fields (public or private) available through
-/// `Researching::unknown_fields`.
+/// `Researching::unknown_fields` and `Researching::unknown_field_bytes`.
 #[proc_macro_derive(Researching, attributes(superclass))]
 pub fn derive_researching(input: TokenStream) -> TokenStream {
     match researching::researching_helper(input) {
@@ -14,7 +14,7 @@ pub fn researching_helper(input: TokenStream) -> Result<TokenStream> {
     let Fields::Named(fields) = struct_.fields else {
         return Err(Error::new(struct_.fields.span(), "expected named fields"));
     };
-    let entries = fields
+    let unknown = fields
         .named
         .into_iter()
         .filter_map(|f| {
@@ -26,11 +26,26 @@ pub fn researching_helper(input: TokenStream) -> Result<TokenStream> {
                 None
             }
         })
-        .map(|(n, s)| {
-            let name = LitStr::new(&n, s);
-            let ident = Ident::new(&n, s);
-            quote_spanned! { s => (#name, &self.#ident) }
-        });
+        .map(|(n, s)| (LitStr::new(&n, s), Ident::new(&n, s), s))
+        .collect::<Vec<_>>();
+
+    let entries = unknown.iter().map(|(name, ident, s)| {
+        quote_spanned! { *s => (#name, &self.#ident) }
+    });
+    let byte_entries = unknown.iter().map(|(name, ident, s)| {
+        quote_spanned! { *s =>
+            (
+                #name,
+                ::std::mem::offset_of!(Self, #ident),
+                unsafe {
+                    ::std::slice::from_raw_parts(
+                        ::std::ptr::addr_of!(self.#ident).cast::<u8>(),
+                        ::std::mem::size_of_val(&self.#ident),
+                    )
+                },
+            )
+        }
+    });
 
     Ok(TokenStream::from(quote! {
         impl #impl_generics ::fromsoftware_shared::Researching for #name #ty_generics
@@ -41,6 +56,12 @@ pub fn researching_helper(input: TokenStream) -> Result<TokenStream> {
                     #(#entries),*
                 ]
             }
+
+            fn unknown_field_bytes(&self) -> Vec<(&'static str, usize, &[u8])> {
+                vec![
+                    #(#byte_entries),*
+                ]
+            }
         }
     }))
 }
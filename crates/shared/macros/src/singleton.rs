@@ -1,35 +1,158 @@
-use std::iter::{IntoIterator, Iterator};
-
 use proc_macro::TokenStream;
-use proc_macro2::Span;
+use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::*;
+use syn::parse::{ParseStream, Parser};
 use syn::*;
-use syn::{
-    parse::{ParseBuffer, Parser},
-    punctuated::Punctuated,
-    spanned::Spanned,
-};
-
-/// Annotates a struct as a Dantelion2 singleton to be looked up using a single
-/// string argument.
-///
-/// This is only guaranteed to make the struct work with the
-/// `fromsoftware_shared::singleton::get_instance` function. Any other added
-/// functionality is considered an implementation detail and shouldn't be relied
-/// upon.
-#[proc_macro_attribute]
-pub fn singleton(args: TokenStream, input: TokenStream) -> TokenStream {
-    let input_struct: ItemStruct = parse_macro_input!(input as ItemStruct);
-    let input_struct_ident = input_struct.ident.clone();
-    let dlrf_name = parse_macro_input!(args as LitStr).value();
-
-    TokenStream::from(quote! {
-        #input_struct
 
-        impl ::from_singleton::FromSingleton for #input_struct_ident {
-            fn name() -> ::std::borrow::Cow<'static, str> {
-                ::std::borrow::Cow::Borrowed(#dlrf_name)
+/// The parsed arguments to `#[singleton(...)]`.
+#[derive(Default)]
+struct SingletonArgs {
+    /// The DLRF name passed as a bare string literal.
+    dlrf_name: Option<LitStr>,
+
+    /// The field of the RVA bundle that contains the object's RVA.
+    rva: Option<Ident>,
+
+    /// Whether [Self::rva] points to a pointer to the object rather than the
+    /// object itself.
+    indirect: bool,
+
+    /// An explicit name for objects that aren't looked up through DLRF.
+    name: Option<LitStr>,
+}
+
+impl SingletonArgs {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut args = SingletonArgs::default();
+        if input.peek(LitStr) {
+            args.dlrf_name = Some(input.parse()?);
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
             }
         }
-    })
+
+        let rest = input.parse::<TokenStream2>()?;
+        meta::parser(|meta| {
+            if meta.path.is_ident("rva") {
+                args.rva = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("indirect") {
+                args.indirect = true;
+            } else if meta.path.is_ident("name") {
+                args.name = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("expected rva, indirect, or name"));
+            }
+            Ok(())
+        })
+        .parse2(rest)?;
+
+        Ok(args)
+    }
+}
+
+/// A helper for [singleton](crate::singleton) that returns a [syn::Result].
+pub fn singleton_helper(args: TokenStream, input: TokenStream) -> Result<TokenStream> {
+    let input_struct: ItemStruct = syn::parse(input)?;
+    let ident = &input_struct.ident;
+    let args = SingletonArgs::parse.parse(args)?;
+
+    if !input_struct.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input_struct.generics,
+            "singletons can't have generic parameters",
+        ));
+    }
+
+    if args.indirect && args.rva.is_none() {
+        return Err(Error::new(
+            Span::call_site(),
+            "indirect can only be used along with rva = ...",
+        ));
+    }
+    if let (Some(dlrf_name), Some(name)) = (&args.dlrf_name, &args.name) {
+        return Err(Error::new(
+            name.span(),
+            format!(
+                "name = ... can't be used along with a DLRF name; this is already named {:?}",
+                dlrf_name.value()
+            ),
+        ));
+    }
+
+    let Some(rva) = &args.rva else {
+        let Some(dlrf_name) = &args.dlrf_name else {
+            return Err(Error::new(
+                Span::call_site(),
+                "expected a DLRF name, rva = ..., or both",
+            ));
+        };
+
+        return Ok(TokenStream::from(quote! {
+            #input_struct
+
+            impl ::fromsoftware_shared::FromSingleton for #ident {
+                fn name() -> ::std::borrow::Cow<'static, str> {
+                    ::std::borrow::Cow::Borrowed(#dlrf_name)
+                }
+            }
+        }));
+    };
+
+    let name = args
+        .dlrf_name
+        .clone()
+        .or(args.name)
+        .unwrap_or_else(|| LitStr::new(&ident.to_string(), ident.span()));
+    let load_rva = if args.indirect {
+        quote_spanned! { rva.span() =>
+            unsafe { ::fromsoftware_shared::load_static_indirect(crate::rva::get().#rva) }
+        }
+    } else {
+        quote_spanned! { rva.span() =>
+            ::fromsoftware_shared::load_static_direct(crate::rva::get().#rva)
+        }
+    };
+
+    let Some(dlrf_name) = &args.dlrf_name else {
+        return Ok(TokenStream::from(quote! {
+            #input_struct
+
+            impl ::fromsoftware_shared::FromStatic for #ident {
+                fn name() -> ::std::borrow::Cow<'static, str> {
+                    ::std::borrow::Cow::Borrowed(#name)
+                }
+
+                fn instance_ptr() -> ::fromsoftware_shared::InstanceResult<*mut Self> {
+                    #load_rva
+                }
+            }
+        }));
+    };
+
+    // A type can't implement FromSingleton and have its own FromStatic
+    // implementation, so look up the DLRF singleton through a proxy type.
+    Ok(TokenStream::from(quote! {
+        #input_struct
+
+        const _: () = {
+            struct Dlrf;
+
+            impl ::fromsoftware_shared::FromSingleton for Dlrf {
+                fn name() -> ::std::borrow::Cow<'static, str> {
+                    ::std::borrow::Cow::Borrowed(#dlrf_name)
+                }
+            }
+
+            impl ::fromsoftware_shared::FromStatic for #ident {
+                fn name() -> ::std::borrow::Cow<'static, str> {
+                    ::std::borrow::Cow::Borrowed(#name)
+                }
+
+                fn instance_ptr() -> ::fromsoftware_shared::InstanceResult<*mut Self> {
+                    ::fromsoftware_shared::load_static_singleton::<Dlrf, Self>()
+                        .or_else(|_| #load_rva)
+                }
+            }
+        };
+    }))
 }
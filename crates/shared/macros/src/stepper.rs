@@ -18,10 +18,29 @@ pub fn stepper_states_helper(input: TokenStream) -> Result<TokenStream> {
     validate_stepper_enum_storage(&input)?;
     validate_stepper_enum_variants(e)?;
 
-    let count = e.variants.len();
+    // The game's function table only has entries for the active states, so
+    // `NotExecuting` doesn't get one.
+    let count = e.variants.len() - 1;
+    let variants = e.variants.iter().map(|v| &v.ident);
+    let discriminants = e
+        .variants
+        .iter()
+        .map(|v| read_i32_lit(&v.discriminant.as_ref().unwrap().1))
+        .collect::<Result<Vec<_>>>()?;
     let expanded = quote! {
         unsafe impl ::fromsoftware_shared::StepperStates for #input_struct_ident {
             type StepperFnArray<TStepperFn> = [TStepperFn; #count];
+
+            fn from_raw(value: i32) -> ::std::option::Option<Self> {
+                match value {
+                    #(#discriminants => ::std::option::Option::Some(Self::#variants),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn raw(self) -> i32 {
+                self as i32
+            }
         }
     };
 
@@ -62,6 +62,9 @@ fn build_subclass_enums(superclass_struct: &mut ItemStruct) -> Result<TokenStrea
     let (impl_generics_with_subclass, _, _) = generics_with_subclass.split_for_impl();
     Ok(quote! {
         /// An enum of all known subclasses of [#superclass].
+        // Subclasses often share a suffix with each other, like `PlayerIns` and
+        // `EnemyIns`.
+        #[allow(clippy::enum_variant_names)]
         #[derive(::std::marker::Copy, ::std::clone::Clone)]
         #vis enum #impl_generics #enum_name #ty_generics_with_lifetime #where_clause {
             #(
@@ -71,6 +74,7 @@ fn build_subclass_enums(superclass_struct: &mut ItemStruct) -> Result<TokenStrea
         }
 
         /// A mutable enum of all known subclasses of [#superclass].
+        #[allow(clippy::enum_variant_names)]
         #vis enum #impl_generics #mut_enum_name #ty_generics_with_lifetime #where_clause {
             #(
                 #subclasses(&#lifetime mut #subclasses)
@@ -1,3 +1,4 @@
+mod geometry;
 mod linear;
 mod matrix;
 mod special;
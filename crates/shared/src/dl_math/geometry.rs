@@ -0,0 +1,577 @@
+//! Intersection and containment queries for the primitives in [linear](super::linear).
+//!
+//! Points are passed and returned as [Vec3A]s, and the `w` components of the
+//! primitives' vectors are ignored unless noted otherwise. Ray parameters are
+//! in units of the ray's direction vector, which doesn't need to be
+//! normalized.
+
+use glam::{Mat4, Vec3A};
+
+use super::{
+    Aabb, F32ModelMatrix, F32Vector4, Frustum, Lss, Obb, Plane, Ray, Segment, Sphere, Triangle,
+};
+
+/// The tolerance used to treat near-parallel directions as parallel.
+const EPSILON: f32 = 1e-6;
+
+fn xyz(v: F32Vector4) -> Vec3A {
+    Vec3A::new(v.0, v.1, v.2)
+}
+
+impl Segment {
+    /// Returns the point the segment starts at.
+    pub fn start(&self) -> Vec3A {
+        xyz(self.origin)
+    }
+
+    /// Returns the point the segment ends at.
+    pub fn end(&self) -> Vec3A {
+        xyz(self.origin) + xyz(self.dir)
+    }
+
+    /// Returns the point on the segment that's closest to `point`.
+    pub fn closest_point(&self, point: Vec3A) -> Vec3A {
+        self.point_at(self.closest_parameter(point))
+    }
+
+    /// Returns the closest points on this and `other`, in that order.
+    pub fn closest_points(&self, other: &Segment) -> (Vec3A, Vec3A) {
+        // From Ericson, Real-Time Collision Detection, 5.1.9.
+        let d1 = xyz(self.dir);
+        let d2 = xyz(other.dir);
+        let r = xyz(self.origin) - xyz(other.origin);
+        let a = d1.length_squared();
+        let e = d2.length_squared();
+        let f = d2.dot(r);
+
+        let (s, t) = if a <= EPSILON && e <= EPSILON {
+            (0.0, 0.0)
+        } else if a <= EPSILON {
+            (0.0, (f / e).clamp(0.0, 1.0))
+        } else {
+            let c = d1.dot(r);
+            if e <= EPSILON {
+                ((-c / a).clamp(0.0, 1.0), 0.0)
+            } else {
+                let b = d1.dot(d2);
+                let denom = a * e - b * b;
+                let mut s = if denom > EPSILON {
+                    ((b * f - c * e) / denom).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let mut t = (b * s + f) / e;
+                if t < 0.0 {
+                    t = 0.0;
+                    s = (-c / a).clamp(0.0, 1.0);
+                } else if t > 1.0 {
+                    t = 1.0;
+                    s = ((b - c) / a).clamp(0.0, 1.0);
+                }
+                (s, t)
+            }
+        };
+
+        (self.point_at(s), other.point_at(t))
+    }
+
+    /// Returns the distance between `point` and the closest point on the
+    /// segment.
+    pub fn distance_to_point(&self, point: Vec3A) -> f32 {
+        self.closest_point(point).distance(point)
+    }
+
+    /// Returns the point `t` of the way along the segment.
+    fn point_at(&self, t: f32) -> Vec3A {
+        xyz(self.origin) + xyz(self.dir) * t
+    }
+
+    /// Returns how far along the segment its closest point to `point` is, from
+    /// 0 to 1.
+    fn closest_parameter(&self, point: Vec3A) -> f32 {
+        let dir = xyz(self.dir);
+        let length_squared = dir.length_squared();
+        if length_squared <= EPSILON {
+            return 0.0;
+        }
+        ((point - xyz(self.origin)).dot(dir) / length_squared).clamp(0.0, 1.0)
+    }
+}
+
+impl Ray {
+    /// Returns the point at parameter `t` along the ray.
+    pub fn point_at(&self, t: f32) -> Vec3A {
+        xyz(self.origin) + xyz(self.dir) * t
+    }
+
+    /// Returns the parameter at which the ray enters `aabb`, or 0 if it starts
+    /// inside it. Returns `None` if the ray misses.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        intersect_slabs(xyz(self.origin), xyz(self.dir), aabb.min(), aabb.max())
+    }
+
+    /// Returns the parameter at which the ray enters `obb`, or 0 if it starts
+    /// inside it. Returns `None` if the ray misses.
+    pub fn intersect_obb(&self, obb: &Obb) -> Option<f32> {
+        let to_local = obb.local_to_world().inverse();
+        let origin = to_local.transform_point3a(xyz(self.origin));
+        let dir = to_local.transform_vector3a(xyz(self.dir));
+        let extents = obb.extents();
+        intersect_slabs(origin, dir, -extents, extents)
+    }
+
+    /// Returns the parameter at which the ray enters `sphere`, or 0 if it
+    /// starts inside it. Returns `None` if the ray misses.
+    pub fn intersect_sphere(&self, sphere: &Sphere) -> Option<f32> {
+        let dir = xyz(self.dir);
+        let offset = xyz(self.origin) - sphere.center();
+        let a = dir.length_squared();
+        let b = offset.dot(dir);
+        let c = offset.length_squared() - sphere.radius() * sphere.radius();
+        if c <= 0.0 {
+            return Some(0.0);
+        }
+        if b > 0.0 || a <= EPSILON {
+            return None;
+        }
+
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        Some((-b - discriminant.sqrt()) / a)
+    }
+
+    /// Returns the parameter at which the ray hits `triangle` from either
+    /// side. Returns `None` if the ray misses or is parallel to it.
+    pub fn intersect_triangle(&self, triangle: &Triangle) -> Option<f32> {
+        // Möller–Trumbore.
+        let dir = xyz(self.dir);
+        let edge1 = xyz(triangle.edge1);
+        let edge2 = xyz(triangle.edge2);
+        let p = dir.cross(edge2);
+        let det = edge1.dot(p);
+        if det.abs() <= EPSILON {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let offset = xyz(self.origin) - xyz(triangle.origin);
+        let u = offset.dot(p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = offset.cross(edge1);
+        let v = dir.dot(q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = edge2.dot(q) * inv_det;
+        (t >= 0.0).then_some(t)
+    }
+}
+
+/// Intersects the ray `origin + dir * t` with the box between `min` and `max`.
+fn intersect_slabs(origin: Vec3A, dir: Vec3A, min: Vec3A, max: Vec3A) -> Option<f32> {
+    let mut t_min = 0.0f32;
+    let mut t_max = f32::INFINITY;
+    for axis in 0..3 {
+        if dir[axis].abs() <= EPSILON {
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let inv = 1.0 / dir[axis];
+        let t1 = (min[axis] - origin[axis]) * inv;
+        let t2 = (max[axis] - origin[axis]) * inv;
+        t_min = t_min.max(t1.min(t2));
+        t_max = t_max.min(t1.max(t2));
+        if t_min > t_max {
+            return None;
+        }
+    }
+    Some(t_min)
+}
+
+impl Plane {
+    /// Returns the plane's normal.
+    pub fn normal(&self) -> Vec3A {
+        xyz(self.plane)
+    }
+
+    /// Returns the signed distance from the plane to `point`, scaled by the
+    /// length of the normal. This is positive on the side the normal points
+    /// towards.
+    pub fn signed_distance(&self, point: Vec3A) -> f32 {
+        self.normal().dot(point) + self.plane.3
+    }
+}
+
+impl Sphere {
+    /// Returns the center of the sphere.
+    pub fn center(&self) -> Vec3A {
+        xyz(self.sphere)
+    }
+
+    /// Returns the radius of the sphere.
+    pub fn radius(&self) -> f32 {
+        self.sphere.3
+    }
+
+    /// Returns whether `point` is inside or on the sphere.
+    pub fn contains_point(&self, point: Vec3A) -> bool {
+        self.center().distance_squared(point) <= self.radius() * self.radius()
+    }
+
+    /// Returns whether this and `other` overlap.
+    pub fn intersects_sphere(&self, other: &Sphere) -> bool {
+        let radii = self.radius() + other.radius();
+        self.center().distance_squared(other.center()) <= radii * radii
+    }
+
+    /// Returns whether this overlaps `aabb`.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.contains_point(aabb.closest_point(self.center()))
+    }
+}
+
+impl Aabb {
+    /// Returns the minimum corner of the box.
+    pub fn min(&self) -> Vec3A {
+        xyz(self.min)
+    }
+
+    /// Returns the maximum corner of the box.
+    pub fn max(&self) -> Vec3A {
+        xyz(self.max)
+    }
+
+    /// Returns the center of the box.
+    pub fn center(&self) -> Vec3A {
+        (self.min() + self.max()) * 0.5
+    }
+
+    /// Returns the half-size of the box along each axis.
+    pub fn half_extents(&self) -> Vec3A {
+        (self.max() - self.min()) * 0.5
+    }
+
+    /// Returns whether `point` is inside or on the box.
+    pub fn contains_point(&self, point: Vec3A) -> bool {
+        point.cmpge(self.min()).all() && point.cmple(self.max()).all()
+    }
+
+    /// Returns whether this and `other` overlap.
+    pub fn intersects_aabb(&self, other: &Aabb) -> bool {
+        self.min().cmple(other.max()).all() && other.min().cmple(self.max()).all()
+    }
+
+    /// Returns the point in the box that's closest to `point`.
+    pub fn closest_point(&self, point: Vec3A) -> Vec3A {
+        point.clamp(self.min(), self.max())
+    }
+}
+
+impl Obb {
+    /// Returns the half-size of the box along its local axes.
+    pub fn extents(&self) -> Vec3A {
+        xyz(self.extents)
+    }
+
+    /// Returns the transform from the box's local space to world space.
+    pub fn local_to_world(&self) -> Mat4 {
+        F32ModelMatrix::from(self.xform).into()
+    }
+
+    /// Returns whether `point` is inside or on the box.
+    pub fn contains_point(&self, point: Vec3A) -> bool {
+        let local = self.local_to_world().inverse().transform_point3a(point);
+        local.abs().cmple(self.extents()).all()
+    }
+}
+
+impl Lss {
+    /// Returns whether `point` is inside or on the capsule.
+    pub fn contains_point(&self, point: Vec3A) -> bool {
+        self.segment.distance_to_point(point) <= self.radius
+    }
+
+    /// Returns whether this capsule overlaps `sphere`.
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        self.segment.distance_to_point(sphere.center()) <= self.radius + sphere.radius()
+    }
+
+    /// Returns whether this and `other` overlap.
+    pub fn intersects_lss(&self, other: &Lss) -> bool {
+        let (a, b) = self.segment.closest_points(&other.segment);
+        a.distance(b) <= self.radius + other.radius
+    }
+}
+
+impl Triangle {
+    /// Returns the triangle's three vertices.
+    pub fn vertices(&self) -> [Vec3A; 3] {
+        let origin = xyz(self.origin);
+        [origin, origin + xyz(self.edge1), origin + xyz(self.edge2)]
+    }
+
+    /// Returns the triangle's unit normal, following the winding of its
+    /// vertices.
+    pub fn normal(&self) -> Vec3A {
+        xyz(self.edge1).cross(xyz(self.edge2)).normalize_or_zero()
+    }
+}
+
+impl Frustum {
+    /// Returns whether `point` is inside the frustum. Points are considered
+    /// inside when they're on the positive side of every plane.
+    pub fn contains_point(&self, point: Vec3A) -> bool {
+        self.planes.iter().all(|p| p.signed_distance(point) >= 0.0)
+    }
+
+    /// Returns whether `sphere` might be visible. This is conservative: it
+    /// never culls a sphere that overlaps the frustum, but may keep some that
+    /// are just outside its corners.
+    pub fn intersects_sphere(&self, sphere: &Sphere) -> bool {
+        let center = sphere.center();
+        self.planes
+            .iter()
+            .all(|p| p.signed_distance(center) >= -sphere.radius() * p.normal().length())
+    }
+
+    /// Returns whether `aabb` might be visible. Like
+    /// [Frustum::intersects_sphere], this is conservative.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|p| {
+            // The corner furthest along the plane's normal.
+            let normal = p.normal();
+            let corner = Vec3A::select(normal.cmpge(Vec3A::ZERO), aabb.max(), aabb.min());
+            p.signed_distance(corner) >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::F32Matrix4x4;
+
+    fn v(x: f32, y: f32, z: f32) -> F32Vector4 {
+        F32Vector4(x, y, z, 0.0)
+    }
+
+    fn ray(origin: F32Vector4, dir: F32Vector4) -> Ray {
+        Ray { origin, dir }
+    }
+
+    fn unit_box() -> Aabb {
+        Aabb {
+            min: v(-1.0, -1.0, -1.0),
+            max: v(1.0, 1.0, 1.0),
+        }
+    }
+
+    fn sphere(x: f32, y: f32, z: f32, r: f32) -> Sphere {
+        Sphere {
+            sphere: F32Vector4(x, y, z, r),
+        }
+    }
+
+    /// An axis-aligned cube frustum from -10 to 10 with inward-facing planes.
+    fn cube_frustum() -> Frustum {
+        let plane = |x, y, z| Plane {
+            plane: F32Vector4(x, y, z, 10.0),
+        };
+        Frustum {
+            planes: [
+                plane(1.0, 0.0, 0.0),
+                plane(-1.0, 0.0, 0.0),
+                plane(0.0, 1.0, 0.0),
+                plane(0.0, -1.0, 0.0),
+                plane(0.0, 0.0, 1.0),
+                plane(0.0, 0.0, -1.0),
+            ],
+        }
+    }
+
+    #[test]
+    fn ray_aabb() {
+        let aabb = unit_box();
+        assert_eq!(
+            ray(v(-5.0, 0.0, 0.0), v(1.0, 0.0, 0.0)).intersect_aabb(&aabb),
+            Some(4.0)
+        );
+        assert_eq!(
+            ray(v(-5.0, 0.0, 0.0), v(2.0, 0.0, 0.0)).intersect_aabb(&aabb),
+            Some(2.0)
+        );
+        assert_eq!(
+            ray(v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0)).intersect_aabb(&aabb),
+            Some(0.0)
+        );
+        assert_eq!(
+            ray(v(-5.0, 2.0, 0.0), v(1.0, 0.0, 0.0)).intersect_aabb(&aabb),
+            None
+        );
+        assert_eq!(
+            ray(v(5.0, 0.0, 0.0), v(1.0, 0.0, 0.0)).intersect_aabb(&aabb),
+            None
+        );
+    }
+
+    #[test]
+    fn ray_obb() {
+        // A 2x2x2 box rotated 45 degrees around Y and moved to (10, 0, 0).
+        let xform = Mat4::from_rotation_translation(
+            glam::Quat::from_rotation_y(std::f32::consts::FRAC_PI_4),
+            glam::Vec3::new(10.0, 0.0, 0.0),
+        );
+        let obb = Obb {
+            extents: v(1.0, 1.0, 1.0),
+            xform: F32Matrix4x4::from(F32ModelMatrix::from(xform)),
+        };
+
+        // The box's corner points straight back along X.
+        let t = ray(v(0.0, 0.0, 0.0), v(1.0, 0.0, 0.0))
+            .intersect_obb(&obb)
+            .unwrap();
+        assert!((t - (10.0 - std::f32::consts::SQRT_2)).abs() < 1e-4, "{t}");
+        assert!(obb.contains_point(Vec3A::new(10.0, 0.0, 1.3)));
+        assert!(!obb.contains_point(Vec3A::new(11.0, 0.0, 1.0)));
+        assert_eq!(
+            ray(v(0.0, 5.0, 0.0), v(1.0, 0.0, 0.0)).intersect_obb(&obb),
+            None
+        );
+    }
+
+    #[test]
+    fn ray_sphere() {
+        let s = sphere(0.0, 0.0, 10.0, 2.0);
+        assert_eq!(
+            ray(v(0.0, 0.0, 0.0), v(0.0, 0.0, 1.0)).intersect_sphere(&s),
+            Some(8.0)
+        );
+        assert_eq!(
+            ray(v(0.0, 0.0, 10.0), v(0.0, 0.0, 1.0)).intersect_sphere(&s),
+            Some(0.0)
+        );
+        assert_eq!(
+            ray(v(0.0, 3.0, 0.0), v(0.0, 0.0, 1.0)).intersect_sphere(&s),
+            None
+        );
+        assert_eq!(
+            ray(v(0.0, 0.0, 0.0), v(0.0, 0.0, -1.0)).intersect_sphere(&s),
+            None
+        );
+    }
+
+    #[test]
+    fn ray_triangle() {
+        let triangle = Triangle {
+            origin: v(0.0, 0.0, 5.0),
+            edge1: v(2.0, 0.0, 0.0),
+            edge2: v(0.0, 2.0, 0.0),
+        };
+        let hit = ray(v(0.5, 0.5, 0.0), v(0.0, 0.0, 1.0)).intersect_triangle(&triangle);
+        assert_eq!(hit, Some(5.0));
+        let behind = ray(v(0.5, 0.5, 10.0), v(0.0, 0.0, 1.0)).intersect_triangle(&triangle);
+        assert_eq!(behind, None);
+        let outside = ray(v(1.5, 1.5, 0.0), v(0.0, 0.0, 1.0)).intersect_triangle(&triangle);
+        assert_eq!(outside, None);
+        assert_eq!(triangle.normal(), Vec3A::Z);
+    }
+
+    #[test]
+    fn closest_point_on_segment() {
+        let segment = Segment {
+            origin: v(0.0, 0.0, 0.0),
+            dir: v(10.0, 0.0, 0.0),
+        };
+        let closest = |x, y, z| segment.closest_point(Vec3A::new(x, y, z));
+        assert_eq!(closest(5.0, 3.0, 0.0), Vec3A::new(5.0, 0.0, 0.0));
+        assert_eq!(closest(-5.0, 3.0, 0.0), Vec3A::ZERO);
+        assert_eq!(closest(15.0, 0.0, 3.0), Vec3A::new(10.0, 0.0, 0.0));
+        assert_eq!(segment.distance_to_point(Vec3A::new(13.0, 4.0, 0.0)), 5.0);
+    }
+
+    #[test]
+    fn closest_points_between_segments() {
+        let a = Segment {
+            origin: v(0.0, 0.0, 0.0),
+            dir: v(10.0, 0.0, 0.0),
+        };
+        let b = Segment {
+            origin: v(4.0, -5.0, 3.0),
+            dir: v(0.0, 10.0, 0.0),
+        };
+        assert_eq!(
+            a.closest_points(&b),
+            (Vec3A::new(4.0, 0.0, 0.0), Vec3A::new(4.0, 0.0, 3.0))
+        );
+
+        // Parallel segments.
+        let c = Segment {
+            origin: v(12.0, 1.0, 0.0),
+            dir: v(5.0, 0.0, 0.0),
+        };
+        assert_eq!(
+            a.closest_points(&c),
+            (Vec3A::new(10.0, 0.0, 0.0), Vec3A::new(12.0, 1.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn capsule_overlap() {
+        let capsule = |x: f32, radius| Lss {
+            segment: Segment {
+                origin: v(x, 0.0, 0.0),
+                dir: v(0.0, 4.0, 0.0),
+            },
+            radius,
+        };
+        assert!(capsule(0.0, 1.0).intersects_lss(&capsule(1.5, 1.0)));
+        assert!(!capsule(0.0, 1.0).intersects_lss(&capsule(2.5, 1.0)));
+        assert!(capsule(0.0, 1.0).contains_point(Vec3A::new(0.5, 4.5, 0.0)));
+        assert!(!capsule(0.0, 1.0).contains_point(Vec3A::new(0.0, 5.5, 0.0)));
+        assert!(capsule(0.0, 1.0).intersects_sphere(&sphere(0.0, 6.0, 0.0, 1.5)));
+    }
+
+    #[test]
+    fn aabb_and_sphere_overlap() {
+        let aabb = unit_box();
+        assert!(aabb.contains_point(Vec3A::new(1.0, 0.0, -1.0)));
+        assert!(!aabb.contains_point(Vec3A::new(1.1, 0.0, 0.0)));
+        assert!(aabb.intersects_aabb(&Aabb {
+            min: v(0.5, 0.5, 0.5),
+            max: v(3.0, 3.0, 3.0),
+        }));
+        assert!(!aabb.intersects_aabb(&Aabb {
+            min: v(1.5, 0.0, 0.0),
+            max: v(3.0, 3.0, 3.0),
+        }));
+        assert!(sphere(2.0, 2.0, 0.0, 1.5).intersects_aabb(&aabb));
+        assert!(!sphere(2.0, 2.0, 0.0, 1.4).intersects_aabb(&aabb));
+        assert!(sphere(0.0, 0.0, 0.0, 1.0).intersects_sphere(&sphere(1.5, 0.0, 0.0, 0.5)));
+    }
+
+    #[test]
+    fn frustum_culling() {
+        let frustum = cube_frustum();
+        assert!(frustum.contains_point(Vec3A::new(9.0, -9.0, 0.0)));
+        assert!(!frustum.contains_point(Vec3A::new(11.0, 0.0, 0.0)));
+
+        assert!(frustum.intersects_sphere(&sphere(11.0, 0.0, 0.0, 2.0)));
+        assert!(!frustum.intersects_sphere(&sphere(13.0, 0.0, 0.0, 2.0)));
+
+        let aabb = |min: f32, max: f32| Aabb {
+            min: v(min, 0.0, 0.0),
+            max: v(max, 1.0, 1.0),
+        };
+        assert!(frustum.intersects_aabb(&aabb(9.0, 12.0)));
+        assert!(!frustum.intersects_aabb(&aabb(11.0, 12.0)));
+    }
+}
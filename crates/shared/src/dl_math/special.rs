@@ -1,6 +1,6 @@
-use std::mem;
+use std::{mem, ops::Mul};
 
-use glam::{Mat3A, Mat4, Vec3A, Vec4};
+use glam::{Mat3A, Mat4, Quat, Vec3, Vec3A, Vec4};
 
 use crate::{F32Matrix4x4, F32Vector4};
 
@@ -44,6 +44,51 @@ impl F32ModelMatrix {
     pub fn translation<T: From<Vec3A>>(&self) -> T {
         Vec3A::from_vec4(self.3.into()).into()
     }
+
+    /// The identity matrix.
+    pub const IDENTITY: Self = Self(
+        F32Vector4(1.0, 0.0, 0.0, 0.0),
+        F32Vector4(0.0, 1.0, 0.0, 0.0),
+        F32Vector4(0.0, 0.0, 1.0, 0.0),
+        F32Vector4(0.0, 0.0, 0.0, 1.0),
+    );
+
+    /// Compose from a scale, rotation and translation, applied in that order.
+    #[inline]
+    pub fn from_scale_rotation_translation(scale: Vec3, rotation: Quat, translation: Vec3) -> Self {
+        Mat4::from_scale_rotation_translation(scale, rotation, translation).into()
+    }
+
+    /// Compose from a rotation and translation, applied in that order.
+    #[inline]
+    pub fn from_rotation_translation(rotation: Quat, translation: Vec3) -> Self {
+        Mat4::from_rotation_translation(rotation, translation).into()
+    }
+
+    /// Decompose into a scale, rotation and translation. The scale can be
+    /// non-uniform, but the matrix must not be sheared.
+    #[inline]
+    pub fn to_scale_rotation_translation(&self) -> (Vec3, Quat, Vec3) {
+        Mat4::from(*self).to_scale_rotation_translation()
+    }
+
+    /// Invert the matrix, assuming it's affine.
+    #[inline]
+    pub fn inverse(&self) -> Self {
+        Mat4::from(*self).inverse().into()
+    }
+
+    /// Transform a point, applying the rotation, scale and translation.
+    #[inline]
+    pub fn transform_point3<T: From<Vec3A>>(&self, point: impl Into<Vec3A>) -> T {
+        Mat4::from(*self).transform_point3a(point.into()).into()
+    }
+
+    /// Transform a direction, applying only the rotation and scale.
+    #[inline]
+    pub fn transform_vector3<T: From<Vec3A>>(&self, vector: impl Into<Vec3A>) -> T {
+        Mat4::from(*self).transform_vector3a(vector.into()).into()
+    }
 }
 
 impl F32PackedModelMatrix {
@@ -67,6 +112,50 @@ impl F32PackedModelMatrix {
         Vec3A::from_vec4(self.w_axis().into()).into()
     }
 
+    /// The identity matrix.
+    pub const IDENTITY: Self = Self(
+        F32Vector4(1.0, 0.0, 0.0, 0.0),
+        F32Vector4(0.0, 1.0, 0.0, 0.0),
+        F32Vector4(0.0, 0.0, 1.0, 0.0),
+    );
+
+    /// Compose from a scale, rotation and translation, applied in that order.
+    #[inline]
+    pub fn from_scale_rotation_translation(scale: Vec3, rotation: Quat, translation: Vec3) -> Self {
+        Mat4::from_scale_rotation_translation(scale, rotation, translation).into()
+    }
+
+    /// Compose from a rotation and translation, applied in that order.
+    #[inline]
+    pub fn from_rotation_translation(rotation: Quat, translation: Vec3) -> Self {
+        Mat4::from_rotation_translation(rotation, translation).into()
+    }
+
+    /// Decompose into a scale, rotation and translation. The scale can be
+    /// non-uniform, but the matrix must not be sheared.
+    #[inline]
+    pub fn to_scale_rotation_translation(&self) -> (Vec3, Quat, Vec3) {
+        Mat4::from(*self).to_scale_rotation_translation()
+    }
+
+    /// Invert the matrix, assuming it's affine.
+    #[inline]
+    pub fn inverse(&self) -> Self {
+        Mat4::from(*self).inverse().into()
+    }
+
+    /// Transform a point, applying the rotation, scale and translation.
+    #[inline]
+    pub fn transform_point3<T: From<Vec3A>>(&self, point: impl Into<Vec3A>) -> T {
+        Mat4::from(*self).transform_point3a(point.into()).into()
+    }
+
+    /// Transform a direction, applying only the rotation and scale.
+    #[inline]
+    pub fn transform_vector3<T: From<Vec3A>>(&self, vector: impl Into<Vec3A>) -> T {
+        Mat4::from(*self).transform_vector3a(vector.into()).into()
+    }
+
     /// Extract the x axis.
     #[inline]
     pub fn x_axis(&self) -> F32Vector4 {
@@ -196,3 +285,132 @@ impl From<Mat4> for F32PackedModelMatrix {
         Self(x_axis, y_axis, z_axis)
     }
 }
+
+/// Composes two matrices. As with [Mat4], `a * b` applies `b` first and then
+/// `a`.
+impl Mul for F32ModelMatrix {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        (Mat4::from(self) * Mat4::from(rhs)).into()
+    }
+}
+
+/// Composes two matrices. As with [Mat4], `a * b` applies `b` first and then
+/// `a`.
+impl Mul for F32PackedModelMatrix {
+    type Output = Self;
+
+    #[inline]
+    fn mul(self, rhs: Self) -> Self::Output {
+        (Mat4::from(self) * Mat4::from(rhs)).into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::FRAC_PI_2;
+
+    use glam::{Mat4, Quat, Vec3};
+
+    use super::{F32ModelMatrix, F32PackedModelMatrix};
+    use crate::F32Vector4;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn assert_vec_eq(expected: Vec3, actual: Vec3) {
+        assert!(
+            expected.abs_diff_eq(actual, EPSILON),
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn compose_preserves_row_layout() {
+        let m = F32ModelMatrix::from_rotation_translation(
+            Quat::from_rotation_y(FRAC_PI_2),
+            Vec3::new(1.0, 2.0, 3.0),
+        );
+        assert_eq!(F32Vector4(1.0, 2.0, 3.0, 1.0), m.3);
+        assert_vec_eq(Vec3::new(1.0, 2.0, 3.0), m.translation());
+
+        let packed = F32PackedModelMatrix::from(m);
+        assert_eq!(
+            packed,
+            F32PackedModelMatrix::from_rotation_translation(
+                Quat::from_rotation_y(FRAC_PI_2),
+                Vec3::new(1.0, 2.0, 3.0),
+            )
+        );
+        assert_eq!(F32ModelMatrix::from(packed), m);
+    }
+
+    #[test]
+    fn transform_points_and_vectors() {
+        let m = F32ModelMatrix::from_scale_rotation_translation(
+            Vec3::new(2.0, 1.0, 1.0),
+            Quat::from_rotation_y(FRAC_PI_2),
+            Vec3::new(10.0, 0.0, 0.0),
+        );
+        // Scale X by 2, turn +X to -Z, then move.
+        assert_vec_eq(Vec3::new(10.0, 0.0, -2.0), m.transform_point3(Vec3::X));
+        assert_vec_eq(Vec3::new(0.0, 0.0, -2.0), m.transform_vector3(Vec3::X));
+
+        let packed = F32PackedModelMatrix::from(m);
+        assert_vec_eq(Vec3::new(10.0, 0.0, -2.0), packed.transform_point3(Vec3::X));
+        assert_vec_eq(Vec3::new(0.0, 0.0, -2.0), packed.transform_vector3(Vec3::X));
+    }
+
+    #[test]
+    fn inverse_and_compose() {
+        let m = F32ModelMatrix::from_scale_rotation_translation(
+            Vec3::new(1.0, 3.0, 0.5),
+            Quat::from_rotation_x(0.7),
+            Vec3::new(-4.0, 5.0, 6.0),
+        );
+        let point = Vec3::new(1.0, 2.0, 3.0);
+        let transformed: Vec3 = m.transform_point3(point);
+        assert_vec_eq(point, m.inverse().transform_point3(transformed));
+        assert!(
+            Mat4::from(m * m.inverse()).abs_diff_eq(Mat4::IDENTITY, EPSILON),
+            "{:?}",
+            m * m.inverse()
+        );
+
+        let packed = F32PackedModelMatrix::from(m);
+        assert_vec_eq(point, packed.inverse().transform_point3(transformed));
+
+        // Composition applies the right-hand matrix first.
+        let a = F32ModelMatrix::from_rotation_translation(Quat::IDENTITY, Vec3::X);
+        let b =
+            F32ModelMatrix::from_rotation_translation(Quat::from_rotation_y(FRAC_PI_2), Vec3::ZERO);
+        assert_vec_eq(Vec3::new(1.0, 0.0, -1.0), (a * b).transform_point3(Vec3::X));
+        assert_vec_eq(
+            Vec3::new(1.0, 0.0, -1.0),
+            (F32PackedModelMatrix::from(a) * F32PackedModelMatrix::from(b))
+                .transform_point3(Vec3::X),
+        );
+    }
+
+    #[test]
+    fn decompose_non_uniform_scale() {
+        let scale = Vec3::new(2.0, 0.5, 3.0);
+        let rotation = Quat::from_rotation_z(0.4) * Quat::from_rotation_y(1.1);
+        let translation = Vec3::new(7.0, -8.0, 9.0);
+        let m = F32ModelMatrix::from_scale_rotation_translation(scale, rotation, translation);
+
+        for (s, r, t) in [
+            m.to_scale_rotation_translation(),
+            F32PackedModelMatrix::from(m).to_scale_rotation_translation(),
+        ] {
+            assert_vec_eq(scale, s);
+            assert!(r.abs_diff_eq(rotation, EPSILON) || r.abs_diff_eq(-rotation, EPSILON));
+            assert_vec_eq(translation, t);
+        }
+        assert_eq!(
+            F32ModelMatrix::IDENTITY,
+            F32ModelMatrix::from_scale_rotation_translation(Vec3::ONE, Quat::IDENTITY, Vec3::ZERO)
+        );
+    }
+}
@@ -0,0 +1,501 @@
+use std::borrow::Cow;
+use std::cmp::PartialEq;
+use std::fmt::Display;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+
+use encoding_rs::{self, DecoderResult};
+use fromsoftware_shared_stl::{Allocator, BasicString, CodeUnit, Msvc2015, StringLayout};
+use thiserror::Error;
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum DLCharacterSet {
+    UTF8 = 0,
+    #[default]
+    UTF16 = 1,
+    Iso8859_1 = 2,
+    ShiftJis = 3,
+    EucJp = 4,
+    UTF32 = 5,
+}
+
+impl DLCharacterSet {
+    /// Returns the [encoding_rs::Encoding] that corresponds to this character
+    /// set, or None if no such encoding exists (which is only possible for
+    /// [DLCharacterSet::UTF32]).
+    pub fn encoding(&self) -> Option<&'static encoding_rs::Encoding> {
+        Some(match self {
+            DLCharacterSet::UTF8 => encoding_rs::UTF_8,
+            #[cfg(target_endian = "little")]
+            DLCharacterSet::UTF16 => encoding_rs::UTF_16LE,
+            #[cfg(target_endian = "big")]
+            DLCharacterSet::UTF16 => encoding_rs::UTF_16BE,
+            DLCharacterSet::Iso8859_1 => encoding_rs::WINDOWS_1252,
+            DLCharacterSet::ShiftJis => encoding_rs::SHIFT_JIS,
+            DLCharacterSet::EucJp => encoding_rs::EUC_JP,
+            DLCharacterSet::UTF32 => return None,
+        })
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum DLStringEncodingError {
+    #[error("Invalid encoding; expected {expected:?} but got {actual}")]
+    InvalidEncoding {
+        expected: DLCharacterSet,
+        actual: u8,
+    },
+    #[error("Error decoding string")]
+    DecodeError,
+    #[error("Error encoding string")]
+    EncodeError,
+    #[error("Unsupported encoding: {0}")]
+    UnsupportedEncoding(u8),
+}
+
+/// This trait is used to seal the DLStringKind trait, preventing external implementations.
+trait DLStringKindSeal {}
+
+#[allow(private_bounds)]
+pub trait DLStringKind: DLStringKindSeal {
+    type CharType: CodeUnit;
+    const ENCODING: DLCharacterSet;
+
+    fn encode(s: &str) -> Result<Vec<Self::CharType>, DLStringEncodingError> {
+        match Self::ENCODING {
+            DLCharacterSet::UTF16 => {
+                let mut bytes: Vec<u16> = Vec::with_capacity(s.len() * size_of::<u16>());
+                bytes.extend(s.encode_utf16());
+                // SAFETY: Transmuting Vec<u16> to Vec<Self::CharType> is safe
+                // because the UTF16 arm ensures CharType is u16.
+                Ok(unsafe { std::mem::transmute::<Vec<u16>, Vec<Self::CharType>>(bytes) })
+            }
+            DLCharacterSet::UTF32 => {
+                let mut bytes: Vec<u32> = Vec::with_capacity(s.len() * size_of::<u32>());
+                bytes.extend(s.chars().map(|c| c as u32));
+                // SAFETY: Transmuting Vec<u32> to Vec<Self::CharType> is safe
+                // because the UTF32 arm ensures CharType is u32.
+                Ok(unsafe { std::mem::transmute::<Vec<u32>, Vec<Self::CharType>>(bytes) })
+            }
+            DLCharacterSet::UTF8 => {
+                // We can just slice the string as UTF-8 bytes because Rust's `str` is UTF-8 encoded.
+                let bytes = s.as_bytes().to_vec();
+                // SAFETY: Transmuting Vec<u8> to Vec<Self::CharType> is safe
+                // because this arm ensures CharType is u8.
+                Ok(unsafe { std::mem::transmute::<Vec<u8>, Vec<Self::CharType>>(bytes) })
+            }
+            _ => {
+                let (encoded_bytes, _, had_errors) = Self::ENCODING.encoding().unwrap().encode(s);
+                if had_errors {
+                    return Err(DLStringEncodingError::EncodeError);
+                }
+
+                // SAFETY: Transmuting Vec<u8> to Vec<Self::CharType> is safe
+                // because this arm ensures CharType is u8.
+                Ok(unsafe {
+                    std::mem::transmute::<Vec<u8>, Vec<Self::CharType>>(encoded_bytes.into_owned())
+                })
+            }
+        }
+    }
+
+    fn decode(s: &[u8]) -> Result<Cow<'_, str>, DLStringEncodingError> {
+        match Self::ENCODING {
+            DLCharacterSet::UTF16 => {
+                if !s.len().is_multiple_of(std::mem::size_of::<u16>()) {
+                    return Err(DLStringEncodingError::DecodeError);
+                }
+                char::decode_utf16(
+                    s.chunks_exact(2)
+                        .map(|pair| u16::from_ne_bytes([pair[0], pair[1]])),
+                )
+                .map(|r| r.map_err(|_| DLStringEncodingError::DecodeError))
+                .collect::<Result<String, _>>()
+                .map(Cow::Owned)
+            }
+            DLCharacterSet::UTF32 => {
+                if !s.len().is_multiple_of(std::mem::size_of::<u32>()) {
+                    return Err(DLStringEncodingError::DecodeError);
+                }
+                s.chunks_exact(4)
+                    .map(|quad| u32::from_ne_bytes([quad[0], quad[1], quad[2], quad[3]]))
+                    .map(|c| std::char::from_u32(c).ok_or(DLStringEncodingError::DecodeError))
+                    .collect::<Result<String, _>>()
+                    .map(Cow::Owned)
+            }
+            DLCharacterSet::UTF8 => {
+                let s = std::str::from_utf8(s).map_err(|_| DLStringEncodingError::DecodeError)?;
+                Ok(Cow::Borrowed(s))
+            }
+            _ => {
+                let (cow, _, had_errors) = Self::ENCODING.encoding().unwrap().decode(s);
+                if had_errors {
+                    Err(DLStringEncodingError::DecodeError)
+                } else {
+                    Ok(cow)
+                }
+            }
+        }
+    }
+}
+
+pub struct DLUTF8StringKind;
+impl DLStringKindSeal for DLUTF8StringKind {}
+impl DLStringKind for DLUTF8StringKind {
+    type CharType = u8;
+    const ENCODING: DLCharacterSet = DLCharacterSet::UTF8;
+}
+
+pub struct DLISO8859_1StringKind;
+impl DLStringKindSeal for DLISO8859_1StringKind {}
+impl DLStringKind for DLISO8859_1StringKind {
+    type CharType = u8;
+    const ENCODING: DLCharacterSet = DLCharacterSet::Iso8859_1;
+}
+
+pub struct DLShiftJisStringKind;
+impl DLStringKindSeal for DLShiftJisStringKind {}
+impl DLStringKind for DLShiftJisStringKind {
+    type CharType = u8;
+    const ENCODING: DLCharacterSet = DLCharacterSet::ShiftJis;
+}
+
+pub struct DLEucJpStringKind;
+impl DLStringKindSeal for DLEucJpStringKind {}
+impl DLStringKind for DLEucJpStringKind {
+    type CharType = u8;
+    const ENCODING: DLCharacterSet = DLCharacterSet::EucJp;
+}
+
+pub struct DLUTF16StringKind;
+impl DLStringKindSeal for DLUTF16StringKind {}
+impl DLStringKind for DLUTF16StringKind {
+    type CharType = u16;
+    const ENCODING: DLCharacterSet = DLCharacterSet::UTF16;
+}
+
+pub struct DLUTF32StringKind;
+impl DLStringKindSeal for DLUTF32StringKind {}
+impl DLStringKind for DLUTF32StringKind {
+    type CharType = u32;
+    const ENCODING: DLCharacterSet = DLCharacterSet::UTF32;
+}
+
+/// A `DLTX::DLString`, which pairs an MSVC `std::basic_string` with the
+/// character set it's encoded in.
+///
+/// `A` is the game's allocator type and `L` is the [StringLayout] of the MSVC
+/// version the game was built with. Each game crate exposes an alias that
+/// fills them in, so most code should use that instead of naming this
+/// directly.
+#[repr(C)]
+pub struct DLString<T: DLStringKind, A: Allocator, L: StringLayout = Msvc2015> {
+    base: BasicString<T::CharType, A, L>,
+    encoding: DLCharacterSet,
+}
+
+impl<T: DLStringKind, A: Allocator, L: StringLayout> DLString<T, A, L> {
+    pub fn new(allocator: A) -> Self {
+        Self {
+            base: BasicString::new_in(allocator),
+            encoding: T::ENCODING,
+        }
+    }
+
+    pub fn from_str(allocator: A, s: &str) -> Result<Self, DLStringEncodingError> {
+        let encoded: Vec<T::CharType> = T::encode(s)?;
+
+        Ok(Self {
+            base: BasicString::from_units_in(&encoded, allocator),
+            encoding: T::ENCODING,
+        })
+    }
+
+    pub fn to_str(&self) -> Result<String, DLStringEncodingError> {
+        T::decode(self.base.as_bytes()).map(|cow| cow.into_owned())
+    }
+
+    /// The character set the game believes this string to be encoded in.
+    pub fn encoding(&self) -> DLCharacterSet {
+        self.encoding
+    }
+
+    pub fn copy<U: DLStringKind, B: Allocator, M: StringLayout>(
+        allocator: A,
+        other: &DLString<U, B, M>,
+    ) -> Result<Self, DLStringEncodingError> {
+        // If the encodings match, we can directly copy the bytes
+        if T::ENCODING == U::ENCODING {
+            // SAFETY: T::ENCODING == U::ENCODING implies T::CharType is compatible with U::CharType.
+            let chars: &[T::CharType] = unsafe { std::mem::transmute(other.base.as_code_units()) };
+            Ok(Self {
+                base: BasicString::from_units_in(chars, allocator),
+                encoding: T::ENCODING,
+            })
+        } else {
+            // If encodings differ, we need to decode and re-encode
+            let decoded = U::decode(other.base.as_bytes())?;
+            DLString::from_str(allocator, &decoded)
+        }
+    }
+}
+
+impl<T: DLStringKind, A: Allocator, L: StringLayout> Deref for DLString<T, A, L> {
+    type Target = BasicString<T::CharType, A, L>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+impl<T: DLStringKind, A: Allocator, L: StringLayout> DerefMut for DLString<T, A, L> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.base
+    }
+}
+
+impl<T: DLStringKind, A: Allocator, L: StringLayout> Display for DLString<T, A, L> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.to_str() {
+            Ok(s) => write!(f, "{s}"),
+            Err(_) => Err(std::fmt::Error),
+        }
+    }
+}
+
+impl<T: DLStringKind, A: Allocator, L: StringLayout, S: AsRef<str>> PartialEq<S>
+    for DLString<T, A, L>
+{
+    fn eq(&self, other: &S) -> bool {
+        let other = other.as_ref();
+        match T::ENCODING {
+            DLCharacterSet::UTF8 => self.base.as_bytes() == other.as_bytes(),
+            DLCharacterSet::UTF32 => {
+                // Safety: If this is UTF32, its characters are u32s.
+                unsafe { std::mem::transmute::<&[T::CharType], &[u32]>(self.base.as_code_units()) }
+                    .iter()
+                    .map(|c| char::try_from(*c))
+                    .eq(other.chars().map(Ok))
+            }
+            _ => {
+                // Do equality comparisons byte-by-byte on the UTF-8
+                // representations of each string. It would be simpler to just
+                // convert to a string and compare that, but it would be
+                // substantially less efficient because it would involve an
+                // allocation for eqach comparison.
+
+                let mut decoder = T::ENCODING.encoding().unwrap().new_decoder();
+                if decoder
+                    .max_utf8_buffer_length_without_replacement(self.base.as_bytes().len())
+                    .map(|len| len < other.len())
+                    .unwrap_or(true)
+                {
+                    // If the other string is big enough that this string can't
+                    // possibly decode to it, exit early without even attempting
+                    // to decode. This is most likely if this is the empty
+                    // string.
+                    return false;
+                }
+
+                let mut our_bytes = self.base.as_bytes();
+                let mut other_bytes = other.as_bytes().iter();
+                let mut output: [u8; 0x20] = [0; 0x20];
+                loop {
+                    let (result, bytes_read, bytes_written) =
+                        decoder.decode_to_utf8_without_replacement(our_bytes, &mut output, true);
+                    if matches!(result, DecoderResult::Malformed(_, _)) {
+                        // Malformed strings are never equal to valid UTF-8.
+                        return false;
+                    }
+
+                    for &our_byte in output.iter().take(bytes_written) {
+                        let Some(&their_byte) = other_bytes.next() else {
+                            // If this string has more bytes than the other
+                            // string, they're not equal.
+                            return false;
+                        };
+
+                        if our_byte != their_byte {
+                            // If this string has different bytes than the other
+                            // string, they're not equal.
+                            return false;
+                        }
+                    }
+
+                    if matches!(result, DecoderResult::InputEmpty) {
+                        // If this string has fewer bytes than the other string,
+                        // they're not equal, but if it has the same number and
+                        // they've been equivalent up to this point then they
+                        // are equal.
+                        return other_bytes.next().is_none();
+                    }
+
+                    our_bytes = &our_bytes[bytes_read..];
+                }
+            }
+        }
+    }
+}
+
+#[repr(C)]
+pub struct DLRawString<T: DLStringKind = DLUTF16StringKind> {
+    vftable: usize,
+    backing_string: Option<NonNull<T::CharType>>,
+    pub length: usize,
+    unk18: u32,
+    pub char_size: u16,
+    pub encoding: DLCharacterSet,
+    pub flags: u8,
+}
+
+impl<T: DLStringKind> DLRawString<T> {
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+    pub fn to_str(&self) -> Result<String, DLStringEncodingError> {
+        let bytes: &[u8] = self.backing_string.as_ref().map_or(&[], |ptr| unsafe {
+            std::slice::from_raw_parts(
+                ptr.as_ptr() as *const u8,
+                self.length * std::mem::size_of::<T::CharType>(),
+            )
+        });
+        T::decode(bytes).map(|cow| cow.into_owned())
+    }
+}
+
+impl<T: DLStringKind> Display for DLRawString<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.to_str() {
+            Ok(s) => write!(f, "{s}"),
+            Err(_) => Err(std::fmt::Error),
+        }
+    }
+}
+pub type DLCodedString<T> = DLRawString<T>;
+
+#[repr(C)]
+/// Source of name: RTTI
+/// In original code, generic type was something like `DLInplaceStr<1,16,DLTX::DLCodedStr<1>>`
+/// Where 1 is DLCharacterSet::UTF16 and 16 is the size of the inline buffer in characters.
+/// This version uses StringKind trait to get the character set and size.
+pub struct DLInplaceStr<T: DLStringKind, const N: usize> {
+    /// Underlying DLCodedString used for all operations.
+    pub base: DLCodedString<T>,
+    /// Buffer used to store the string data.
+    /// backing_string in base is a pointer to this buffer.
+    pub bytes: [T::CharType; N],
+    unk: usize,
+}
+
+impl<T: DLStringKind, const N: usize> Display for DLInplaceStr<T, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.base.fmt(f)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::{
+        alloc::{Layout, alloc, dealloc},
+        ffi::c_void,
+    };
+
+    use super::*;
+
+    /// An [Allocator] backed by Rust's global allocator, for use in tests.
+    #[derive(Clone, Default)]
+    pub(crate) struct TestAllocator;
+
+    /// Stores the requested size and alignment in front of each allocation so
+    /// it can be freed without a side table.
+    const HEADER: usize = size_of::<[usize; 2]>();
+
+    impl Allocator for TestAllocator {
+        unsafe fn allocate_raw(&mut self, size: usize, align: usize) -> *mut c_void {
+            let layout = Layout::from_size_align(HEADER + size, align.max(HEADER)).unwrap();
+            unsafe {
+                let raw = alloc(layout);
+                (raw as *mut usize).write(size);
+                (raw as *mut usize).add(1).write(align);
+                raw.add(HEADER) as *mut c_void
+            }
+        }
+
+        unsafe fn deallocate_raw(&mut self, ptr: *mut c_void) {
+            unsafe {
+                let raw = (ptr as *mut u8).sub(HEADER);
+                let size = (raw as *mut usize).read();
+                let align = (raw as *mut usize).add(1).read();
+                dealloc(
+                    raw,
+                    Layout::from_size_align(HEADER + size, align.max(HEADER)).unwrap(),
+                );
+            }
+        }
+    }
+
+    fn round_trip<T: DLStringKind>(s: &str) {
+        let string = DLString::<T, _>::from_str(TestAllocator, s).unwrap();
+        assert_eq!(string.encoding(), T::ENCODING);
+        assert_eq!(string.to_str().unwrap(), s);
+        assert!(string == s, "{:?} != {s:?}", string.to_str());
+        assert!(string != format!("{s}!"));
+    }
+
+    #[test]
+    fn round_trips_every_encoding() {
+        for s in [
+            "",
+            "short",
+            "a string long enough to spill out of the SSO buffer",
+        ] {
+            round_trip::<DLUTF8StringKind>(s);
+            round_trip::<DLUTF16StringKind>(s);
+            round_trip::<DLUTF32StringKind>(s);
+            round_trip::<DLISO8859_1StringKind>(s);
+            round_trip::<DLShiftJisStringKind>(s);
+            round_trip::<DLEucJpStringKind>(s);
+        }
+
+        round_trip::<DLUTF16StringKind>("褪せ人");
+        round_trip::<DLUTF32StringKind>("褪せ人");
+        round_trip::<DLShiftJisStringKind>("褪せ人");
+        round_trip::<DLEucJpStringKind>("褪せ人");
+        round_trip::<DLISO8859_1StringKind>("Café");
+    }
+
+    #[test]
+    fn unencodable_characters_are_rejected() {
+        assert!(matches!(
+            DLString::<DLISO8859_1StringKind, _>::from_str(TestAllocator, "褪せ人"),
+            Err(DLStringEncodingError::EncodeError)
+        ));
+    }
+
+    #[test]
+    fn copy_between_encodings() {
+        let original =
+            DLString::<DLShiftJisStringKind, _>::from_str(TestAllocator, "褪せ人").unwrap();
+
+        let same = DLString::<DLShiftJisStringKind, _>::copy(TestAllocator, &original).unwrap();
+        assert_eq!(same.as_bytes(), original.as_bytes());
+
+        let wide = DLString::<DLUTF16StringKind, _>::copy(TestAllocator, &original).unwrap();
+        assert_eq!(wide.to_str().unwrap(), "褪せ人");
+        assert_eq!(wide.len(), 3);
+    }
+
+    #[test]
+    fn malformed_strings_never_compare_equal() {
+        let mut string = DLString::<DLShiftJisStringKind, _>::new(TestAllocator);
+        string.push_slice([0x81]);
+        assert!(string.to_str().is_err());
+        assert!(string != "\u{fffd}");
+    }
+
+    #[test]
+    fn proper_sizes() {
+        assert_eq!(0x20, size_of::<DLRawString>());
+    }
+}
@@ -1,4 +1,5 @@
-use std::{fmt, iter::FusedIterator, mem::MaybeUninit, ops::Drop, ptr::NonNull};
+use std::iter::{Enumerate, FusedIterator};
+use std::{fmt, mem::MaybeUninit, ops::Drop, ptr::NonNull};
 
 /// A wrapper type to represent an instance of a type `T` that may be in a
 /// well-known "empty" state where its usual guarantees aren't upheld.
@@ -153,6 +154,14 @@ where
 {
     /// Filters out any empty values from this iterator.
     fn non_empty(self) -> NonEmptyIter<'a, E, Self>;
+
+    /// Filters out any empty values from this iterator, pairing each remaining
+    /// value with its index in the original iterator.
+    fn enumerate_non_empty(self) -> EnumerateNonEmptyIter<'a, E, Self>;
+
+    /// Returns the index of the first empty value in this iterator, or `None`
+    /// if every value is non-empty.
+    fn first_empty_slot(self) -> Option<usize>;
 }
 
 impl<'a, E, I> NonEmptyIteratorExt<'a, E> for I
@@ -163,6 +172,41 @@ where
     fn non_empty(self) -> NonEmptyIter<'a, E, Self> {
         NonEmptyIter(self)
     }
+
+    fn enumerate_non_empty(self) -> EnumerateNonEmptyIter<'a, E, Self> {
+        EnumerateNonEmptyIter(self.enumerate())
+    }
+
+    fn first_empty_slot(mut self) -> Option<usize> {
+        self.position(|entry| entry.is_empty())
+    }
+}
+
+/// An iterator adapter that omits empty elements and pairs the rest with their
+/// original indices.
+pub struct EnumerateNonEmptyIter<'a, E, I>(Enumerate<I>)
+where
+    E: IsEmpty + 'a,
+    I: Iterator<Item = &'a MaybeEmpty<E>>;
+
+impl<'a, E, I> Iterator for EnumerateNonEmptyIter<'a, E, I>
+where
+    E: IsEmpty + 'a,
+    I: Iterator<Item = &'a MaybeEmpty<E>>,
+{
+    type Item = (usize, &'a E);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0
+            .find_map(|(i, entry)| entry.as_option().map(|entry| (i, entry)))
+    }
+}
+
+impl<'a, E, I> FusedIterator for EnumerateNonEmptyIter<'a, E, I>
+where
+    E: IsEmpty + 'a,
+    I: Iterator<Item = &'a MaybeEmpty<E>> + FusedIterator,
+{
 }
 
 /// A mutable iterator adapter that omits empty elements.
@@ -201,6 +245,20 @@ where
 {
     /// Filters out any empty values from this iterator.
     fn non_empty(self) -> NonEmptyIterMut<'a, E, Self>;
+
+    /// Filters out any empty values from this iterator, pairing each remaining
+    /// value with its index in the original iterator.
+    fn enumerate_non_empty(self) -> EnumerateNonEmptyIterMut<'a, E, Self>;
+
+    /// Returns the index of the first empty value in this iterator, or `None`
+    /// if every value is non-empty.
+    fn first_empty_slot(self) -> Option<usize>;
+
+    /// Moves `value` into the first empty slot in this iterator and returns
+    /// that slot's index along with a reference to the newly-inserted value.
+    ///
+    /// If there are no empty slots, returns `value` back as an error.
+    fn insert_into_first_empty(self, value: E) -> Result<(usize, &'a mut E), E>;
 }
 
 impl<'a, E, I> NonEmptyIteratorMutExt<'a, E> for I
@@ -211,4 +269,149 @@ where
     fn non_empty(self) -> NonEmptyIterMut<'a, E, Self> {
         NonEmptyIterMut(self)
     }
+
+    fn enumerate_non_empty(self) -> EnumerateNonEmptyIterMut<'a, E, Self> {
+        EnumerateNonEmptyIterMut(self.enumerate())
+    }
+
+    fn first_empty_slot(mut self) -> Option<usize> {
+        self.position(|entry| entry.is_empty())
+    }
+
+    fn insert_into_first_empty(self, value: E) -> Result<(usize, &'a mut E), E> {
+        match self.enumerate().find(|(_, entry)| entry.is_empty()) {
+            Some((i, entry)) => {
+                // Empty values don't need to be dropped, so it's safe to
+                // overwrite this without dropping it first.
+                entry.0 = MaybeUninit::new(value);
+                Ok((i, unsafe { entry.0.assume_init_mut() }))
+            }
+            None => Err(value),
+        }
+    }
+}
+
+/// A mutable iterator adapter that omits empty elements and pairs the rest
+/// with their original indices.
+pub struct EnumerateNonEmptyIterMut<'a, E, I>(Enumerate<I>)
+where
+    E: IsEmpty + 'a,
+    I: Iterator<Item = &'a mut MaybeEmpty<E>>;
+
+impl<'a, E, I> Iterator for EnumerateNonEmptyIterMut<'a, E, I>
+where
+    E: IsEmpty + 'a,
+    I: Iterator<Item = &'a mut MaybeEmpty<E>>,
+{
+    type Item = (usize, &'a mut E);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0
+            .find_map(|(i, entry)| entry.as_option_mut().map(|entry| (i, entry)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::IsEmpty;
+
+    #[repr(C)]
+    #[derive(Debug, IsEmpty, PartialEq)]
+    #[empty(field = id, value = -1)]
+    struct Slot {
+        id: i32,
+        quantity: u32,
+    }
+
+    impl Slot {
+        fn empty() -> MaybeEmpty<Self> {
+            MaybeEmpty::new(Slot {
+                id: -1,
+                quantity: 0,
+            })
+        }
+
+        fn full(id: i32) -> MaybeEmpty<Self> {
+            MaybeEmpty::new(Slot { id, quantity: 1 })
+        }
+    }
+
+    #[repr(C)]
+    #[derive(IsEmpty)]
+    #[empty(field = handle, as = i32, value = -1)]
+    #[empty(field = flags, with = |flags: &u8| flags & 1 == 0)]
+    struct Reinterpreted {
+        handle: u32,
+        flags: u8,
+    }
+
+    #[repr(C)]
+    #[derive(IsEmpty)]
+    #[empty(zeroed)]
+    struct Zeroed {
+        a: u32,
+        b: u32,
+    }
+
+    #[test]
+    fn derived_field_value() {
+        assert!(Slot::empty().is_empty());
+        assert!(!Slot::full(0).is_empty());
+    }
+
+    #[test]
+    fn derived_conditions_must_all_hold() {
+        let empty = |handle, flags| MaybeEmpty::new(Reinterpreted { handle, flags }).is_empty();
+        assert!(empty(u32::MAX, 0));
+        assert!(!empty(u32::MAX, 1));
+        assert!(!empty(0, 0));
+    }
+
+    #[test]
+    fn derived_zeroed() {
+        assert!(MaybeEmpty::new(Zeroed { a: 0, b: 0 }).is_empty());
+        assert!(!MaybeEmpty::new(Zeroed { a: 0, b: 1 }).is_empty());
+    }
+
+    #[test]
+    fn enumerate_non_empty_keeps_indices() {
+        let mut slots = [Slot::empty(), Slot::full(10), Slot::empty(), Slot::full(30)];
+        let ids: Vec<_> = slots
+            .iter()
+            .enumerate_non_empty()
+            .map(|(i, slot)| (i, slot.id))
+            .collect();
+        assert_eq!(ids, [(1, 10), (3, 30)]);
+
+        for (i, slot) in slots.iter_mut().enumerate_non_empty() {
+            slot.quantity = i as u32;
+        }
+        assert_eq!(3, slots[3].as_option().unwrap().quantity);
+    }
+
+    #[test]
+    fn first_empty_slot() {
+        let slots = [Slot::full(1), Slot::empty(), Slot::empty()];
+        assert_eq!(Some(1), slots.iter().first_empty_slot());
+        assert_eq!(None, slots[..1].iter().first_empty_slot());
+    }
+
+    #[test]
+    fn insert_into_first_empty() {
+        let mut slots = [Slot::full(1), Slot::empty(), Slot::empty()];
+        let (i, slot) = slots
+            .iter_mut()
+            .insert_into_first_empty(Slot { id: 2, quantity: 5 })
+            .unwrap();
+        assert_eq!(1, i);
+        slot.quantity += 1;
+        assert_eq!(Some(&Slot { id: 2, quantity: 6 }), slots[1].as_option());
+        assert_eq!(Some(2), slots.iter_mut().first_empty_slot());
+
+        let rejected = slots[..2]
+            .iter_mut()
+            .insert_into_first_empty(Slot { id: 3, quantity: 1 });
+        assert_eq!(Err(Slot { id: 3, quantity: 1 }), rejected.map(|_| ()));
+    }
 }
@@ -0,0 +1,650 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display},
+    iter::FusedIterator,
+    marker::PhantomData,
+    num::ParseIntError,
+    ops::Range,
+};
+
+use thiserror::Error;
+
+/// Describes how a game turns numeric event flag IDs into the locations where
+/// their bits are stored.
+///
+/// Every game stores flags in chunks of [CHUNK_SIZE](Self::CHUNK_SIZE)
+/// consecutive IDs, each of which has to be looked up separately. Operating on
+/// an [EventFlagRange] one chunk at a time avoids repeating that lookup for
+/// every flag.
+pub trait EventFlagLayout: Copy + Eq + Sized {
+    /// The number of consecutive flag IDs, starting at a multiple of this,
+    /// that are stored together.
+    const CHUNK_SIZE: u32;
+
+    /// The error returned for IDs that aren't valid flags in this game.
+    type Error: std::error::Error;
+
+    /// Validates `id` and returns the flag it refers to.
+    fn from_id(id: u32) -> Result<Self, Self::Error>;
+
+    /// Returns this flag's numeric ID.
+    fn id(&self) -> u32;
+
+    /// Returns the index of the chunk this flag is stored in.
+    fn chunk(&self) -> u32 {
+        self.id() / Self::CHUNK_SIZE
+    }
+
+    /// Returns the index of this flag within its chunk.
+    fn offset(&self) -> u32 {
+        self.id() % Self::CHUNK_SIZE
+    }
+
+    /// Parses a flag from its decimal ID. Underscores may be used as digit
+    /// separators, so `1_0000_0000` and `100000000` are the same flag.
+    ///
+    /// Games use this to implement [FromStr](std::str::FromStr).
+    fn parse(s: &str) -> Result<Self, ParseEventFlagError<Self::Error>> {
+        let digits: String = s.trim().chars().filter(|c| *c != '_').collect();
+        let id = digits.parse::<u32>()?;
+        Self::from_id(id).map_err(ParseEventFlagError::Invalid)
+    }
+}
+
+/// The error type returned when parsing an event flag fails.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ParseEventFlagError<E: std::error::Error> {
+    /// The string isn't a decimal number that fits in a `u32`.
+    #[error("Event flag isn't a valid number: {0}")]
+    NotANumber(#[from] ParseIntError),
+
+    /// The number isn't a valid flag for this game.
+    #[error(transparent)]
+    Invalid(E),
+}
+
+/// A range of consecutive event flag IDs, such as all the flags for a single
+/// map or a block reserved for a mod.
+///
+/// Iterating the range yields each valid flag in order, skipping IDs that the
+/// game doesn't allow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventFlagRange<F: EventFlagLayout> {
+    ids: Range<u32>,
+    _layout: PhantomData<F>,
+}
+
+impl<F: EventFlagLayout> EventFlagRange<F> {
+    /// Creates a range from `start` up to but not including `end`.
+    pub fn new(start: u32, end: u32) -> Self {
+        Self {
+            ids: start..end.max(start),
+            _layout: PhantomData,
+        }
+    }
+
+    /// Creates a range of the `len` IDs starting at `start`.
+    pub fn with_len(start: u32, len: u32) -> Self {
+        Self::new(start, start.saturating_add(len))
+    }
+
+    /// Creates a range covering the whole chunk that contains `flag`.
+    pub fn chunk_of(flag: F) -> Self {
+        let start = flag.chunk() * F::CHUNK_SIZE;
+        Self::with_len(start, F::CHUNK_SIZE)
+    }
+
+    /// Returns the first ID in the range.
+    pub fn start(&self) -> u32 {
+        self.ids.start
+    }
+
+    /// Returns the ID just after the end of the range.
+    pub fn end(&self) -> u32 {
+        self.ids.end
+    }
+
+    /// Returns the number of IDs in the range, including invalid ones.
+    pub fn len(&self) -> u32 {
+        self.ids.end - self.ids.start
+    }
+
+    /// Returns whether the range has no IDs.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Returns whether `flag` is in the range.
+    pub fn contains(&self, flag: F) -> bool {
+        self.ids.contains(&flag.id())
+    }
+
+    /// Splits the range into the parts that fall in each chunk, in order.
+    /// Each part only needs one chunk lookup to access all of its flags.
+    pub fn chunks(&self) -> impl Iterator<Item = EventFlagRange<F>> + use<F> {
+        let Range { mut start, end } = self.ids.clone();
+        std::iter::from_fn(move || {
+            if start >= end {
+                return None;
+            }
+
+            let chunk_end = (start / F::CHUNK_SIZE)
+                .saturating_add(1)
+                .saturating_mul(F::CHUNK_SIZE)
+                .min(end);
+            let chunk = Self::new(start, chunk_end);
+            start = chunk_end;
+            Some(chunk)
+        })
+    }
+}
+
+impl<F: EventFlagLayout> From<Range<u32>> for EventFlagRange<F> {
+    fn from(value: Range<u32>) -> Self {
+        Self::new(value.start, value.end)
+    }
+}
+
+impl<F: EventFlagLayout> Display for EventFlagRange<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.ids.start, self.ids.end)
+    }
+}
+
+impl<F: EventFlagLayout> IntoIterator for EventFlagRange<F> {
+    type Item = F;
+    type IntoIter = EventFlagRangeIter<F>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        EventFlagRangeIter {
+            ids: self.ids,
+            _layout: PhantomData,
+        }
+    }
+}
+
+impl<F: EventFlagLayout> IntoIterator for &EventFlagRange<F> {
+    type Item = F;
+    type IntoIter = EventFlagRangeIter<F>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.clone().into_iter()
+    }
+}
+
+/// An iterator over the valid flags in an [EventFlagRange].
+#[derive(Debug, Clone)]
+pub struct EventFlagRangeIter<F: EventFlagLayout> {
+    ids: Range<u32>,
+    _layout: PhantomData<F>,
+}
+
+impl<F: EventFlagLayout> Iterator for EventFlagRangeIter<F> {
+    type Item = F;
+
+    fn next(&mut self) -> Option<F> {
+        self.ids.by_ref().find_map(|id| F::from_id(id).ok())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.ids.size_hint().1)
+    }
+}
+
+impl<F: EventFlagLayout> FusedIterator for EventFlagRangeIter<F> {}
+
+/// A copy of the state of every flag in some set of chunks, taken at one point
+/// in time.
+///
+/// Each chunk is stored as the bits of its flags in order, with the flag at
+/// offset `0` in the most-significant bit of the first byte. Games that store
+/// flags differently convert to and from this layout when taking and
+/// restoring snapshots, so snapshots can be compared and serialized (with the
+/// `serde` feature) without knowing anything about the game's memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound = "")
+)]
+pub struct EventFlagSnapshot<F: EventFlagLayout> {
+    chunks: BTreeMap<u32, Vec<u8>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    _layout: PhantomData<F>,
+}
+
+impl<F: EventFlagLayout> EventFlagSnapshot<F> {
+    /// Creates a snapshot with no chunks.
+    pub fn new() -> Self {
+        Self {
+            chunks: BTreeMap::new(),
+            _layout: PhantomData,
+        }
+    }
+
+    /// Adds the bits for the chunk with the given index, replacing any that
+    /// were already there.
+    pub fn insert_chunk(&mut self, chunk: u32, bits: impl Into<Vec<u8>>) {
+        self.chunks.insert(chunk, bits.into());
+    }
+
+    /// Returns the bits for the chunk with the given index, if it's part of
+    /// this snapshot.
+    pub fn chunk(&self, chunk: u32) -> Option<&[u8]> {
+        self.chunks.get(&chunk).map(Vec::as_slice)
+    }
+
+    /// Returns the index and bits of every chunk in this snapshot, in order.
+    pub fn chunks(&self) -> impl Iterator<Item = (u32, &[u8])> {
+        self.chunks
+            .iter()
+            .map(|(chunk, bits)| (*chunk, bits.as_slice()))
+    }
+
+    /// Returns the number of chunks in this snapshot.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Returns whether this snapshot has no chunks.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Returns the recorded state of `flag`, or `None` if its chunk isn't part
+    /// of this snapshot.
+    pub fn get(&self, flag: F) -> Option<bool> {
+        let bits = self.chunk(flag.chunk())?;
+        let offset = flag.offset() as usize;
+        let byte = bits.get(offset / 8)?;
+        Some(byte & (0x80 >> (offset % 8)) != 0)
+    }
+
+    /// Returns every flag whose state is different in `other`, along with its
+    /// state there, in order of ID.
+    ///
+    /// Chunks that are only part of one snapshot are compared as though every
+    /// flag in the other were unset.
+    pub fn diff(&self, other: &Self) -> Vec<EventFlagChange<F>> {
+        let mut indices = self
+            .chunks
+            .keys()
+            .chain(other.chunks.keys())
+            .collect::<Vec<_>>();
+        indices.sort();
+        indices.dedup();
+
+        let mut changes = Vec::new();
+        for &chunk in indices {
+            let before = self.chunk(chunk).unwrap_or_default();
+            let after = other.chunk(chunk).unwrap_or_default();
+            for index in 0..before.len().max(after.len()) {
+                let old = before.get(index).copied().unwrap_or_default();
+                let new = after.get(index).copied().unwrap_or_default();
+                let mut changed = old ^ new;
+                while changed != 0 {
+                    let bit = changed.leading_zeros();
+                    changed &= !(0x80 >> bit);
+
+                    let offset = index as u32 * 8 + bit;
+                    if offset >= F::CHUNK_SIZE {
+                        break;
+                    }
+                    let Ok(flag) = F::from_id(chunk * F::CHUNK_SIZE + offset) else {
+                        continue;
+                    };
+                    changes.push(EventFlagChange {
+                        flag,
+                        state: new & (0x80 >> bit) != 0,
+                    });
+                }
+            }
+        }
+        changes
+    }
+}
+
+impl<F: EventFlagLayout> Default for EventFlagSnapshot<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A flag whose state differs between two [EventFlagSnapshot]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventFlagChange<F: EventFlagLayout> {
+    /// The flag that changed.
+    pub flag: F,
+
+    /// The flag's state in the later snapshot.
+    pub state: bool,
+}
+
+/// The bits that store one chunk of event flags, which can be read and written
+/// by flag.
+///
+/// This is implemented for `[u8]`, where the flag at offset `0` is the
+/// most-significant bit of the first byte, and for `[u32]`, where it's the
+/// most-significant bit of the first word. Both match [EventFlagSnapshot]'s
+/// layout once the words are converted to big-endian bytes. Flags past the end
+/// of the slice read as unset and can't be set.
+pub trait EventFlagBits<F: EventFlagLayout> {
+    /// Returns the state of `flag`, which must be stored in this chunk.
+    fn get_flag(&self, flag: F) -> bool;
+
+    /// Sets the state of `flag`, which must be stored in this chunk.
+    fn set_flag(&mut self, flag: F, state: bool);
+}
+
+impl<F: EventFlagLayout> EventFlagBits<F> for [u8] {
+    fn get_flag(&self, flag: F) -> bool {
+        let offset = flag.offset() as usize;
+        self.get(offset / 8)
+            .is_some_and(|byte| byte & (0x80 >> (offset % 8)) != 0)
+    }
+
+    fn set_flag(&mut self, flag: F, state: bool) {
+        let offset = flag.offset() as usize;
+        if let Some(byte) = self.get_mut(offset / 8) {
+            let mask = 0x80 >> (offset % 8);
+            if state {
+                *byte |= mask;
+            } else {
+                *byte &= !mask;
+            }
+        }
+    }
+}
+
+impl<F: EventFlagLayout> EventFlagBits<F> for [u32] {
+    fn get_flag(&self, flag: F) -> bool {
+        let offset = flag.offset() as usize;
+        self.get(offset / 32)
+            .is_some_and(|word| word & (0x8000_0000 >> (offset % 32)) != 0)
+    }
+
+    fn set_flag(&mut self, flag: F, state: bool) {
+        let offset = flag.offset() as usize;
+        if let Some(word) = self.get_mut(offset / 32) {
+            let mask = 0x8000_0000 >> (offset % 32);
+            if state {
+                *word |= mask;
+            } else {
+                *word &= !mask;
+            }
+        }
+    }
+}
+
+/// Looks up where each chunk of a game's event flags is stored.
+///
+/// Games only implement the lookup. The provided methods use it to access a
+/// whole [EventFlagRange] while only looking up each chunk once, which is much
+/// faster than looking up every flag separately.
+pub trait EventFlagStorage {
+    /// The game's event flag type.
+    type Flag: EventFlagLayout;
+
+    /// The bits that store a single chunk of flags.
+    type Bits: EventFlagBits<Self::Flag> + ?Sized;
+
+    /// Returns the bits for the chunk that contains `flag`, or `None` if
+    /// that chunk can't currently be accessed.
+    fn flag_bits(&self, flag: Self::Flag) -> Option<&Self::Bits>;
+
+    /// Returns the mutable bits for the chunk that contains `flag`, or `None`
+    /// if that chunk can't currently be accessed.
+    fn flag_bits_mut(&mut self, flag: Self::Flag) -> Option<&mut Self::Bits>;
+
+    /// Retrieves the states of every valid flag in `range`, in order. Flags in
+    /// chunks that can't be accessed are `false`.
+    fn get_flags(&self, range: &EventFlagRange<Self::Flag>) -> Vec<bool> {
+        let mut states = Vec::with_capacity(range.len() as usize);
+        for chunk in range.chunks() {
+            let mut flags = chunk.into_iter().peekable();
+            let bits = flags.peek().and_then(|flag| self.flag_bits(*flag));
+            states.extend(flags.map(|flag| bits.is_some_and(|bits| bits.get_flag(flag))));
+        }
+        states
+    }
+
+    /// Sets every valid flag in `range` to `state`. Flags in chunks that
+    /// can't be accessed are skipped.
+    fn set_flags(&mut self, range: &EventFlagRange<Self::Flag>, state: bool) {
+        for chunk in range.chunks() {
+            let mut flags = chunk.into_iter().peekable();
+            let Some(bits) = flags
+                .peek()
+                .copied()
+                .and_then(|flag| self.flag_bits_mut(flag))
+            else {
+                continue;
+            };
+
+            for flag in flags {
+                bits.set_flag(flag, state);
+            }
+        }
+    }
+
+    /// Sets every valid flag in `range` to its state in `snapshot`, leaving
+    /// flags outside of it alone. Flags whose chunks aren't in `snapshot` or
+    /// can't be accessed are skipped.
+    fn restore_flags(
+        &mut self,
+        snapshot: &EventFlagSnapshot<Self::Flag>,
+        range: &EventFlagRange<Self::Flag>,
+    ) {
+        for chunk in range.chunks() {
+            let mut flags = chunk.into_iter().peekable();
+            let Some(bits) = flags
+                .peek()
+                .copied()
+                .filter(|flag| snapshot.chunk(flag.chunk()).is_some())
+                .and_then(|flag| self.flag_bits_mut(flag))
+            else {
+                continue;
+            };
+
+            for flag in flags {
+                if let Some(state) = snapshot.get(flag) {
+                    bits.set_flag(flag, state);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::Infallible;
+
+    use super::*;
+
+    /// A layout whose IDs ending in 9 are invalid.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Flag(u32);
+
+    #[derive(Error, Debug, PartialEq, Eq)]
+    #[error("invalid flag {0}")]
+    struct InvalidFlag(u32);
+
+    impl EventFlagLayout for Flag {
+        const CHUNK_SIZE: u32 = 10;
+        type Error = InvalidFlag;
+
+        fn from_id(id: u32) -> Result<Self, Self::Error> {
+            if id % 10 == 9 {
+                Err(InvalidFlag(id))
+            } else {
+                Ok(Flag(id))
+            }
+        }
+
+        fn id(&self) -> u32 {
+            self.0
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct AnyFlag(u32);
+
+    impl EventFlagLayout for AnyFlag {
+        const CHUNK_SIZE: u32 = 1000;
+        type Error = Infallible;
+
+        fn from_id(id: u32) -> Result<Self, Self::Error> {
+            Ok(AnyFlag(id))
+        }
+
+        fn id(&self) -> u32 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(Ok(Flag(1234)), Flag::parse("1234"));
+        assert_eq!(Ok(Flag(10000000)), Flag::parse(" 1000_0000 "));
+        assert_eq!(
+            Err(ParseEventFlagError::Invalid(InvalidFlag(19))),
+            Flag::parse("19")
+        );
+        assert!(matches!(
+            Flag::parse("-1"),
+            Err(ParseEventFlagError::NotANumber(_))
+        ));
+        assert!(matches!(
+            Flag::parse(""),
+            Err(ParseEventFlagError::NotANumber(_))
+        ));
+    }
+
+    #[test]
+    fn chunk_and_offset() {
+        let flag = AnyFlag(10400123);
+        assert_eq!(10400, flag.chunk());
+        assert_eq!(123, flag.offset());
+        let chunk = EventFlagRange::chunk_of(flag);
+        assert_eq!((10400000, 10401000), (chunk.start(), chunk.end()));
+    }
+
+    #[test]
+    fn iterate_skips_invalid_flags() {
+        let range = EventFlagRange::<Flag>::new(5, 12);
+        assert_eq!(7, range.len());
+        assert_eq!(
+            vec![Flag(5), Flag(6), Flag(7), Flag(8), Flag(10), Flag(11)],
+            range.into_iter().collect::<Vec<_>>()
+        );
+        assert!(EventFlagRange::<Flag>::new(5, 3).is_empty());
+    }
+
+    #[test]
+    fn split_into_chunks() {
+        let chunks = EventFlagRange::<Flag>::from(5..27)
+            .chunks()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["5..10", "10..20", "20..27"], chunks);
+
+        let chunks = EventFlagRange::<AnyFlag>::with_len(u32::MAX - 5, 10)
+            .chunks()
+            .collect::<Vec<_>>();
+        assert_eq!(1, chunks.len());
+        assert_eq!(u32::MAX, chunks[0].end());
+    }
+    #[test]
+    fn snapshot_get() {
+        let mut snapshot = EventFlagSnapshot::<AnyFlag>::new();
+        snapshot.insert_chunk(10400, [0b1000_0001, 0b0100_0000]);
+        assert_eq!(Some(true), snapshot.get(AnyFlag(10400000)));
+        assert_eq!(Some(false), snapshot.get(AnyFlag(10400001)));
+        assert_eq!(Some(true), snapshot.get(AnyFlag(10400007)));
+        assert_eq!(Some(true), snapshot.get(AnyFlag(10400009)));
+        assert_eq!(None, snapshot.get(AnyFlag(10400016)));
+        assert_eq!(None, snapshot.get(AnyFlag(10401000)));
+    }
+
+    #[test]
+    fn snapshot_diff() {
+        let mut before = EventFlagSnapshot::<Flag>::new();
+        before.insert_chunk(0, [0b1010_0000, 0b0000_0000]);
+        before.insert_chunk(1, [0b0000_0001, 0b1100_0000]);
+
+        let mut after = EventFlagSnapshot::<Flag>::new();
+        after.insert_chunk(0, [0b0110_0000, 0b0100_0000]);
+        after.insert_chunk(2, [0b0000_0000, 0b0000_0001]);
+
+        let changes = before
+            .diff(&after)
+            .into_iter()
+            .map(|change| (change.flag.0, change.state))
+            .collect::<Vec<_>>();
+        // Flags 9 and 19 are invalid and offsets past the chunk size are ignored.
+        assert_eq!(
+            vec![(0, false), (1, true), (17, false), (18, false)],
+            changes
+        );
+        assert!(after.diff(&after).is_empty());
+    }
+
+    /// Stores chunks of [Flag]s as bytes, leaving out chunks it doesn't have.
+    struct Storage(BTreeMap<u32, [u8; 2]>);
+
+    impl EventFlagStorage for Storage {
+        type Flag = Flag;
+        type Bits = [u8];
+
+        fn flag_bits(&self, flag: Flag) -> Option<&[u8]> {
+            self.0.get(&flag.chunk()).map(|bits| &bits[..])
+        }
+
+        fn flag_bits_mut(&mut self, flag: Flag) -> Option<&mut [u8]> {
+            self.0.get_mut(&flag.chunk()).map(|bits| &mut bits[..])
+        }
+    }
+
+    #[test]
+    fn bits_match_snapshot_layout() {
+        let mut bytes = [0u8; 4];
+        let mut words = [0u32; 1];
+        for id in [1000, 1007, 1009, 1031] {
+            bytes.set_flag(AnyFlag(id), true);
+            words.set_flag(AnyFlag(id), true);
+        }
+        assert_eq!([0b1000_0001, 0b0100_0000, 0, 1], bytes);
+        assert_eq!(bytes, words[0].to_be_bytes());
+
+        words.set_flag(AnyFlag(1007), false);
+        assert!(!words.get_flag(AnyFlag(1007)));
+        assert!(words.get_flag(AnyFlag(1009)));
+
+        // Flags past the end are ignored.
+        words.set_flag(AnyFlag(1032), true);
+        assert!(!words.get_flag(AnyFlag(1032)));
+        assert_eq!([0b1000_0000, 0b0100_0000, 0, 1], words[0].to_be_bytes());
+    }
+
+    #[test]
+    fn bulk_access() {
+        let mut storage = Storage(BTreeMap::from([(0, [0; 2]), (2, [0; 2])]));
+        let range = EventFlagRange::<Flag>::new(5, 23);
+        storage.set_flags(&range, true);
+        assert_eq!([0b0000_0111, 0b1000_0000], storage.0[&0]);
+        assert_eq!([0b1110_0000, 0], storage.0[&2]);
+
+        // Flags in chunk 1 can't be accessed, and flags 9 and 19 are skipped.
+        let states = storage.get_flags(&EventFlagRange::new(7, 22));
+        assert_eq!(
+            [[true; 2].as_slice(), &[false; 9], &[true; 2]].concat(),
+            states
+        );
+
+        let mut snapshot = EventFlagSnapshot::new();
+        snapshot.insert_chunk(0, [0b1010_1010, 0b1000_0000]);
+        storage.restore_flags(&snapshot, &EventFlagRange::new(4, 30));
+        assert_eq!([0b0000_1010, 0b1000_0000], storage.0[&0]);
+        assert_eq!([0b1110_0000, 0], storage.0[&2]);
+    }
+}
@@ -0,0 +1,3 @@
+mod basic_hash_string;
+
+pub use basic_hash_string::*;
@@ -0,0 +1,196 @@
+use std::{cmp::PartialEq, fmt::Display};
+
+use fromsoftware_shared_stl::{Allocator, Msvc2015, StringLayout};
+
+use crate::dltx::{DLString, DLStringEncodingError, DLStringKind};
+
+const FNV_OFFSET_BASIS: u32 = 0x811c9dc5;
+const FNV_PRIME: u32 = 0x01000193;
+
+/// Computes what's believed to be the hash the engine caches in
+/// [FD4BasicHashString::hash] for a sequence of code units.
+///
+/// This is 32-bit FNV-1a applied to whole code units rather than bytes, after
+/// normalizing each unit so that lookups are case- and separator-insensitive:
+///
+/// * ASCII uppercase letters are folded to lowercase. Other characters are
+///   left alone.
+/// * Backslashes are replaced with forward slashes, so `data0:\foo` and
+///   `data0:/foo` hash identically.
+///
+/// For ASCII input this is identical to standard FNV-1a over the normalized
+/// bytes. This hasn't been checked against the engine's hashing routine or
+/// against hashes read from a running game's `FD4ResCap` names yet, so don't
+/// rely on it matching [FD4BasicHashString::hash] until it has.
+pub fn fd4_hash_code_units<I>(units: I) -> u32
+where
+    I: IntoIterator,
+    I::Item: Into<u32>,
+{
+    units.into_iter().fold(FNV_OFFSET_BASIS, |hash, unit| {
+        let unit = match unit.into() {
+            c @ 0x41..=0x5a => c + 0x20,
+            0x5c => 0x2f,
+            c => c,
+        };
+        (hash ^ unit).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Computes the hash that a UTF-16 [FD4BasicHashString] containing `s` would
+/// have. See [fd4_hash_code_units] for details.
+pub fn fd4_hash_str(s: &str) -> u32 {
+    fd4_hash_code_units(s.encode_utf16())
+}
+
+#[repr(C)]
+/// A string wrapper that caches the associated hash code.
+///
+/// This is frequently used in the resource system, which is built on hash maps.
+/// It's occasioanlly used elsewhere as well.
+///
+/// `A` and `L` are the game's allocator type and string layout, as for
+/// [DLString]. Each game crate exposes an alias that fills them in.
+///
+/// Source of name: RTTI
+pub struct FD4BasicHashString<T: DLStringKind, A: Allocator, L: StringLayout = Msvc2015> {
+    vftable: usize,
+
+    /// The inner string.
+    pub inner: DLString<T, A, L>,
+
+    /// The string's hash code, or 0 if it hasn't yet been computed.
+    pub hash: u32,
+
+    /// Whether [Self::hash] is stale and has to be recomputed before it's
+    /// used.
+    pub needs_hashing: bool,
+    // _pad3d: [u8; 0x3],
+}
+
+impl<T: DLStringKind, A: Allocator, L: StringLayout> FD4BasicHashString<T, A, L>
+where
+    T::CharType: Into<u32>,
+{
+    /// Creates a new hash string containing `s` with its hash already
+    /// computed.
+    ///
+    /// The result has no vftable, so it's only suitable for passing to game
+    /// functions that look resources up by name. Ownership of it must never be
+    /// handed over to the game.
+    pub fn from_str(allocator: A, s: &str) -> Result<Self, DLStringEncodingError> {
+        let mut result = Self {
+            vftable: 0,
+            inner: DLString::from_str(allocator, s)?,
+            hash: 0,
+            needs_hashing: true,
+        };
+        result.ensure_hashed();
+        Ok(result)
+    }
+
+    /// Computes the hash of [Self::inner] with [fd4_hash_code_units],
+    /// regardless of what's cached in [Self::hash].
+    pub fn compute_hash(&self) -> u32 {
+        fd4_hash_code_units(self.inner.as_code_units().iter().copied())
+    }
+
+    /// Returns [Self::hash], computing and caching it first if
+    /// [Self::needs_hashing] is set.
+    pub fn ensure_hashed(&mut self) -> u32 {
+        if self.needs_hashing {
+            self.hash = self.compute_hash();
+            self.needs_hashing = false;
+        }
+        self.hash
+    }
+}
+
+impl<T: DLStringKind, A: Allocator, L: StringLayout> AsRef<DLString<T, A, L>>
+    for FD4BasicHashString<T, A, L>
+{
+    fn as_ref(&self) -> &DLString<T, A, L> {
+        &self.inner
+    }
+}
+
+impl<T: DLStringKind, A: Allocator, L: StringLayout> Display for FD4BasicHashString<T, A, L> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl<T: DLStringKind, A: Allocator, L: StringLayout, S: AsRef<str>> PartialEq<S>
+    for FD4BasicHashString<T, A, L>
+{
+    fn eq(&self, other: &S) -> bool {
+        self.inner.eq(other)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dltx::{DLUTF8StringKind, DLUTF16StringKind, test::TestAllocator};
+
+    #[test]
+    fn matches_fnv1a_for_ascii() {
+        // Reference values for 32-bit FNV-1a.
+        assert_eq!(0x811c9dc5, fd4_hash_str(""));
+        assert_eq!(0xe40c292c, fd4_hash_str("a"));
+        assert_eq!(0xbf9cf968, fd4_hash_str("foobar"));
+    }
+
+    #[test]
+    fn folds_case_and_separators() {
+        assert_eq!(fd4_hash_str("foobar"), fd4_hash_str("FooBar"));
+        assert_eq!(
+            fd4_hash_str("data0:/param/gameparam/gameparam.parambnd.dcx"),
+            fd4_hash_str("DATA0:\\Param\\GameParam\\GameParam.parambnd.dcx"),
+        );
+        assert_ne!(fd4_hash_str("foo/bar"), fd4_hash_str("foo.bar"));
+        // Non-ASCII characters are hashed as-is.
+        assert_ne!(fd4_hash_str("É"), fd4_hash_str("é"));
+    }
+
+    #[test]
+    fn normalizes_before_hashing() {
+        // Reference FNV-1a values for the normalized names, computed
+        // independently of this crate. These aren't hashes taken from the
+        // game.
+        assert_eq!(0xa9f37ed7, fd4_hash_str("FOO"));
+        assert_eq!(0x39aaa18a, fd4_hash_str("FooBa"));
+        assert_eq!(
+            0x71c9dc53,
+            fd4_hash_str("data0:\\param\\gameparam\\GameParam.parambnd.dcx")
+        );
+        assert_eq!(0x1fdd4b57, fd4_hash_str("MENU:\\01_Common.tpf.dcx"));
+    }
+
+    #[test]
+    fn hashes_each_kind_by_code_unit() {
+        let wide =
+            FD4BasicHashString::<DLUTF16StringKind, _>::from_str(TestAllocator, "褪せ人").unwrap();
+        assert_eq!(wide.hash, fd4_hash_code_units([0x892au16, 0x305b, 0x4eba]));
+        assert_eq!(wide.hash, fd4_hash_str("褪せ人"));
+        assert!(!wide.needs_hashing);
+
+        let narrow =
+            FD4BasicHashString::<DLUTF8StringKind, _>::from_str(TestAllocator, "Foobar").unwrap();
+        assert_eq!(narrow.hash, 0xbf9cf968);
+    }
+
+    #[test]
+    fn ensure_hashed_recomputes_stale_hashes() {
+        let mut string =
+            FD4BasicHashString::<DLUTF16StringKind, _>::from_str(TestAllocator, "a").unwrap();
+        string
+            .inner
+            .push_slice("foobar".encode_utf16().collect::<Vec<_>>());
+        assert_eq!(0xe40c292c, string.ensure_hashed());
+
+        string.needs_hashing = true;
+        assert_eq!(fd4_hash_str("afoobar"), string.ensure_hashed());
+        assert_eq!(string.hash, string.compute_hash());
+    }
+}
@@ -0,0 +1,182 @@
+use std::{num::ParseIntError, str::FromStr};
+
+use thiserror::Error;
+
+/// An error indicating that a valid item ID or item category couldn't be
+/// constructed.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ItemIdError {
+    /// The category, or the bits in the ID representing the category, is
+    /// invalid. This is always the error used when interpreting a [u32]
+    /// directly as an item ID.
+    #[error("Invalid item category {0}")]
+    InvalidCategory(u8),
+
+    /// The parameter ID isn't valid. Because the only valid parameter IDs are
+    /// those whose 1 bits overlap with the category, this error is only used
+    /// when supplying a parameter ID separately from a category.
+    #[error("Invalid param ID {0}")]
+    InvalidParamId(u32),
+}
+
+/// An error indicating that an item ID or item category couldn't be parsed
+/// from a string.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParseItemIdError {
+    /// The string isn't in the `category:param_id` or `0x` hex format.
+    #[error("Item ID must be \"category:param_id\" or a hex value starting with 0x")]
+    InvalidFormat,
+
+    /// The category name isn't one of the [ItemCategoryName::name]s.
+    #[error("Unknown item category \"{0}\"")]
+    UnknownCategory(String),
+
+    /// The parameter ID or hex value isn't a valid number.
+    #[error("Invalid number in item ID: {0}")]
+    InvalidNumber(#[from] ParseIntError),
+
+    /// The numbers were parsed, but don't make up a valid item ID.
+    #[error(transparent)]
+    Invalid(#[from] ItemIdError),
+}
+
+/// The names of a game's item categories, as used in the `category:param_id`
+/// notation for its item IDs.
+pub trait ItemCategoryName: Copy + 'static {
+    /// Every item category, in order of their numeric values.
+    const ALL: &'static [Self];
+
+    /// Returns the lowercase name used for this category in an item ID's
+    /// string format.
+    fn name(&self) -> &'static str;
+
+    /// Parses a category from its [name](Self::name), ignoring case.
+    fn from_name(name: &str) -> Result<Self, ParseItemIdError> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|category| category.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| ParseItemIdError::UnknownCategory(name.to_string()))
+    }
+}
+
+/// An item ID written in one of the string notations shared by every game,
+/// before it's been checked against that game's rules.
+///
+/// Each game's item ID types parse their strings into this and convert it to
+/// themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemIdNotation<C> {
+    /// A category and parameter ID, such as `goods:2919`. Category names are
+    /// case-insensitive.
+    Parts(C, u32),
+
+    /// A raw item ID value in hex starting with `0x`, such as `0x40000b67`.
+    Raw(u32),
+
+    /// `none`, for the absence of an item.
+    None,
+}
+
+impl<C: ItemCategoryName> FromStr for ItemIdNotation<C> {
+    type Err = ParseItemIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("none") {
+            return Ok(Self::None);
+        }
+        if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            return Ok(Self::Raw(u32::from_str_radix(hex, 16)?));
+        }
+
+        let (category, param_id) = s.split_once(':').ok_or(ParseItemIdError::InvalidFormat)?;
+        Ok(Self::Parts(
+            C::from_name(category.trim())?,
+            param_id.trim().parse()?,
+        ))
+    }
+}
+
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+pub use serde as __serde;
+
+/// Implements serde's traits for types using their [Display](std::fmt::Display)
+/// and [FromStr] implementations, so they're stored in the same notation that
+/// users write by hand.
+///
+/// This requires the `serde` feature of this crate.
+#[cfg(feature = "serde")]
+#[macro_export]
+macro_rules! serde_via_str {
+    ($($type:ty),* $(,)?) => {
+        $(
+            impl $crate::__serde::Serialize for $type {
+                fn serialize<S: $crate::__serde::Serializer>(
+                    &self,
+                    serializer: S,
+                ) -> Result<S::Ok, S::Error> {
+                    serializer.collect_str(self)
+                }
+            }
+
+            impl<'de> $crate::__serde::Deserialize<'de> for $type {
+                fn deserialize<D: $crate::__serde::Deserializer<'de>>(
+                    deserializer: D,
+                ) -> Result<Self, D::Error> {
+                    let s = <String as $crate::__serde::Deserialize>::deserialize(deserializer)?;
+                    s.parse().map_err($crate::__serde::de::Error::custom)
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Category {
+        Weapon,
+        Goods,
+    }
+
+    impl ItemCategoryName for Category {
+        const ALL: &'static [Self] = &[Category::Weapon, Category::Goods];
+
+        fn name(&self) -> &'static str {
+            match self {
+                Category::Weapon => "weapon",
+                Category::Goods => "goods",
+            }
+        }
+    }
+
+    #[test]
+    fn parse_notation() {
+        assert_eq!(
+            Ok(ItemIdNotation::Parts(Category::Goods, 2919)),
+            " Goods : 2919 ".parse()
+        );
+        assert_eq!(
+            Ok(ItemIdNotation::<Category>::Raw(0x40000b67)),
+            "0X40000B67".parse()
+        );
+        assert_eq!(Ok(ItemIdNotation::<Category>::None), "None".parse());
+
+        assert_eq!(
+            Err(ParseItemIdError::UnknownCategory("sword".to_string())),
+            "sword:1000000".parse::<ItemIdNotation<Category>>()
+        );
+        assert_eq!(
+            Err(ParseItemIdError::InvalidFormat),
+            "1000000".parse::<ItemIdNotation<Category>>()
+        );
+        assert!(matches!(
+            "weapon:abc".parse::<ItemIdNotation<Category>>(),
+            Err(ParseItemIdError::InvalidNumber(_))
+        ));
+    }
+}
@@ -1,26 +1,44 @@
+// Allows the derive macros, which refer to `::fromsoftware_shared`, to be used
+// within this crate.
+extern crate self as fromsoftware_shared;
+
 pub mod arxan;
 pub mod dl_math;
+pub mod dltx;
 pub mod empty;
+mod event_flag;
 pub mod ext;
+pub mod fd4;
 mod game_allocator;
+mod item_id;
+mod map_id;
 pub mod owned_pointer;
 pub mod program;
+mod registered_heap;
 mod researching;
 pub mod rtti;
+#[cfg(test)]
+mod rva;
 mod r#static;
 pub mod steam;
 pub mod stepper;
 mod subclass;
 pub mod task;
+mod tracking_allocator;
 mod unknown_pointer;
 pub mod util;
+mod vmt_hook;
 
 pub use arxan::*;
 pub use dl_math::*;
 pub use empty::*;
+pub use event_flag::*;
 pub use game_allocator::*;
+pub use item_id::*;
+pub use map_id::*;
 pub use owned_pointer::*;
 pub use program::*;
+pub use registered_heap::*;
 pub use researching::*;
 pub use rtti::*;
 pub use r#static::*;
@@ -28,8 +46,10 @@ pub use steam::*;
 pub use stepper::*;
 pub use subclass::*;
 pub use task::*;
+pub use tracking_allocator::*;
 pub use unknown_pointer::*;
 pub use util::*;
+pub use vmt_hook::*;
 
 pub use from_singleton::FromSingleton;
 pub use fromsoftware_shared_macros::*;
@@ -0,0 +1,128 @@
+use std::{fmt::Display, str::FromStr};
+
+use thiserror::Error;
+
+/// A map ID in the `mAA_BB_CC_DD` form used by the file names of every game's
+/// maps, such as `m60_42_36_00` in Elden Ring or `m30_00_00_00` in Dark Souls
+/// III.
+///
+/// Each game packs these into its own block ID type, which converts to and
+/// from this.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MapId {
+    /// The area of the map, such as 60 for the Lands Between.
+    pub area: u8,
+
+    /// The block within the area. For grid maps, this is the X coordinate.
+    pub block: u8,
+
+    /// For grid maps, this is the Z coordinate. Otherwise, it's usually 0.
+    pub region: u8,
+
+    /// For grid maps, this is the tile's level of detail. Otherwise, it's used
+    /// for variants of the same map.
+    pub index: u8,
+}
+
+impl MapId {
+    /// Creates a map ID from its parts.
+    pub const fn new(area: u8, block: u8, region: u8, index: u8) -> Self {
+        Self {
+            area,
+            block,
+            region,
+            index,
+        }
+    }
+}
+
+impl Display for MapId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "m{:0>2}_{:0>2}_{:0>2}_{:0>2}",
+            self.area, self.block, self.region, self.index
+        )
+    }
+}
+
+/// The error type returned when parsing a [MapId] fails.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseMapIdError {
+    #[error("Map ID doesn't start with \"m\"")]
+    MissingPrefix,
+    #[error("Map ID has {0} parts but should have 4")]
+    WrongPartCount(usize),
+    #[error("Map ID part {0:?} isn't a number from 0 to 255")]
+    InvalidPart(String),
+    #[error("Map ID {0} can't be represented in this game")]
+    Unsupported(MapId),
+}
+
+/// The error type returned when converting a game's block ID to a [MapId]
+/// fails because the ID has bits set that no map ID corresponds to.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("Block ID {0:#010x} doesn't correspond to a map ID")]
+pub struct BlockIdToMapIdError(pub u32);
+
+impl FromStr for MapId {
+    type Err = ParseMapIdError;
+
+    /// Parses a map ID in the `mAA_BB_CC_DD` form. Parts can have any number of
+    /// digits as long as they fit in a byte.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix('m').ok_or(ParseMapIdError::MissingPrefix)?;
+        let parts = s
+            .split('_')
+            .map(|part| {
+                if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(ParseMapIdError::InvalidPart(part.to_string()));
+                }
+                part.parse::<u8>()
+                    .map_err(|_| ParseMapIdError::InvalidPart(part.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        match parts[..] {
+            [area, block, region, index] => Ok(Self::new(area, block, region, index)),
+            _ => Err(ParseMapIdError::WrongPartCount(parts.len())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let id: MapId = "m60_42_36_00".parse().unwrap();
+        assert_eq!(MapId::new(60, 42, 36, 0), id);
+        assert_eq!("m60_42_36_00", id.to_string());
+        assert_eq!(Ok(MapId::new(10, 0, 0, 0)), "m10_0_0_0".parse());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            Err(ParseMapIdError::MissingPrefix),
+            "60_42_36_00".parse::<MapId>()
+        );
+        assert_eq!(
+            Err(ParseMapIdError::WrongPartCount(3)),
+            "m60_42_36".parse::<MapId>()
+        );
+        assert_eq!(
+            Err(ParseMapIdError::InvalidPart("256".to_string())),
+            "m60_256_36_00".parse::<MapId>()
+        );
+        assert_eq!(
+            Err(ParseMapIdError::InvalidPart("+1".to_string())),
+            "m60_+1_36_00".parse::<MapId>()
+        );
+        assert_eq!(
+            Err(ParseMapIdError::InvalidPart("".to_string())),
+            "m60__36_00".parse::<MapId>()
+        );
+    }
+}
@@ -1,8 +1,9 @@
 use std::alloc::{Layout, handle_alloc_error};
+use std::mem::ManuallyDrop;
 use std::ops::{Deref, DerefMut};
 use std::{fmt, marker::PhantomData, ptr::NonNull};
 
-use crate::{GameAllocator, NoOpAllocator};
+use crate::{AllocError, GameAllocator, NoOpAllocator};
 
 /// Pointer to a structure that the containing structure owns. You will generally use this to model
 /// structures in foreign memory when extending the game libraries. Do not use this in your own
@@ -54,6 +55,22 @@ use crate::{GameAllocator, NoOpAllocator};
 /// code has a destroy method, it should be called from [Drop::drop]; otherwise,
 /// the implementation of [Drop::drop] should drop these fields in reverse
 /// declaration order.
+///
+/// ### Allocating and freeing from Rust
+///
+/// Dropping an `OwnedPtr` drops `T` in place and then frees its memory with
+/// [`A::deallocate`]. Since [`NoOpAllocator`] can neither allocate nor free,
+/// this is opt-in: an `OwnedPtr<T>` can't be created with [`OwnedPtr::new`] and
+/// panics if it's dropped, whereas an `OwnedPtr<T, A>` with a real game
+/// allocator can be created, copied with [`OwnedPtr::try_clone`], and dropped
+/// much like a [Box].
+///
+/// To hand an object that was created in Rust over to the game, turn it into a
+/// raw pointer with [`OwnedPtr::into_raw`] (or [`OwnedPtr::leak`]) and store
+/// that wherever the game expects it. From then on the game is responsible for
+/// freeing it. [`OwnedPtr::from_raw`] does the opposite.
+///
+/// [`A::deallocate`]: GameAllocator::deallocate
 #[repr(transparent)]
 pub struct OwnedPtr<T, A: GameAllocator = NoOpAllocator> {
     ptr: NonNull<T>,
@@ -69,27 +86,106 @@ impl<T, A: GameAllocator> OwnedPtr<T, A> {
     /// [Drop::drop] implementation defined. [See above](#ownedptr-and-drop) for
     /// details.
     pub fn new(value: T) -> Self {
+        match Self::try_new(value) {
+            Ok(result) => result,
+            Err(_) => handle_alloc_error(Layout::new::<T>()),
+        }
+    }
+
+    /// Like [`OwnedPtr::new`], but returns an error instead of aborting if the
+    /// allocation fails.
+    pub fn try_new(value: T) -> Result<Self, AllocError> {
         let layout = Layout::new::<T>();
-        if layout.size() == 0 {
-            OwnedPtr {
-                ptr: NonNull::dangling(),
-                _marker: Default::default(),
-            }
-        } else if let Ok(ptr) = A::allocate(layout) {
-            let ptr = ptr.cast::<T>();
-            unsafe { ptr.write(value) };
-            OwnedPtr {
-                ptr,
-                _marker: Default::default(),
-            }
+        let ptr = if layout.size() == 0 {
+            NonNull::dangling()
         } else {
-            handle_alloc_error(layout)
+            let ptr = A::allocate(layout)?.cast::<T>();
+            unsafe { ptr.write(value) };
+            ptr
+        };
+
+        Ok(OwnedPtr {
+            ptr,
+            _marker: Default::default(),
+        })
+    }
+
+    /// Constructs an `OwnedPtr` that takes ownership of `ptr`.
+    ///
+    /// ## Safety
+    ///
+    /// `ptr` must point to a valid, initialized `T` that nothing else owns. If
+    /// `T` isn't zero-sized, `ptr` must also have been allocated by an
+    /// allocator that's [*compatible with*] `A` using [`Layout::new::<T>()`].
+    ///
+    /// [*compatible with*]: GameAllocator#allocator-compatibility
+    pub unsafe fn from_raw(ptr: NonNull<T>) -> Self {
+        OwnedPtr {
+            ptr,
+            _marker: Default::default(),
         }
     }
 
+    /// Consumes the `OwnedPtr` and returns the pointer it owned without
+    /// dropping or freeing it.
+    ///
+    /// The caller becomes responsible for the memory. This is generally how an
+    /// object created in Rust is handed over to the game.
+    pub fn into_raw(self) -> NonNull<T> {
+        ManuallyDrop::new(self).ptr
+    }
+
+    /// Consumes the `OwnedPtr` and returns a reference to its contents that
+    /// lives for as long as the caller chooses. The memory is never freed.
+    pub fn leak<'a>(self) -> &'a mut T
+    where
+        A: 'a,
+    {
+        unsafe { self.into_raw().as_mut() }
+    }
+
+    /// Moves the value out of the `OwnedPtr` and frees the memory that held it.
+    pub fn into_inner(self) -> T {
+        let ptr = self.into_raw();
+        unsafe {
+            let value = ptr.read();
+            Self::free(ptr);
+            value
+        }
+    }
+
+    /// Moves the value out of the `OwnedPtr`, passes it through `f`, and
+    /// places the result in a new allocation from `A`.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> OwnedPtr<U, A> {
+        OwnedPtr::new(f(self.into_inner()))
+    }
+
+    /// Allocates a copy of the contents with `A`.
+    ///
+    /// `OwnedPtr` doesn't implement [Clone] because most of them use
+    /// [`NoOpAllocator`], which can't allocate at all.
+    pub fn try_clone(&self) -> Result<Self, AllocError>
+    where
+        T: Clone,
+    {
+        Self::try_new(self.deref().clone())
+    }
+
     pub fn as_ptr(&self) -> *mut T {
         self.ptr.as_ptr()
     }
+
+    /// Frees `ptr` without dropping its contents.
+    ///
+    /// ## Safety
+    ///
+    /// Same as [`OwnedPtr::from_raw`]. `ptr` must not be used afterwards.
+    unsafe fn free(ptr: NonNull<T>) {
+        let layout = Layout::new::<T>();
+        if layout.size() > 0 {
+            unsafe { A::deallocate(ptr.cast::<u8>(), layout) };
+        }
+    }
 }
 
 impl<T: Default, A: GameAllocator> Default for OwnedPtr<T, A> {
@@ -124,9 +220,9 @@ impl<T, A: GameAllocator> AsMut<T> for OwnedPtr<T, A> {
     }
 }
 
-impl<T, A: GameAllocator> fmt::Debug for OwnedPtr<T, A> {
+impl<T: fmt::Debug, A: GameAllocator> fmt::Debug for OwnedPtr<T, A> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        self.ptr.fmt(f)
+        self.deref().fmt(f)
     }
 }
 
@@ -140,12 +236,161 @@ impl<T, A: GameAllocator> Drop for OwnedPtr<T, A> {
     fn drop(&mut self) {
         unsafe {
             self.ptr.drop_in_place();
-            if Layout::new::<T>().size() > 0 {
-                A::deallocate(self.ptr.cast::<u8>(), Layout::new::<T>());
-            }
+            Self::free(self.ptr);
         }
     }
 }
 
 unsafe impl<T: Send, A: GameAllocator + Send> Send for OwnedPtr<T, A> {}
 unsafe impl<T: Sync, A: GameAllocator + Sync> Sync for OwnedPtr<T, A> where T: Sync {}
+
+#[cfg(test)]
+mod test {
+    use std::alloc::{GlobalAlloc, System};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    /// Counts how many allocations are live per thread, so tests can run in
+    /// parallel without seeing each other's allocations.
+    struct CountingAllocator;
+
+    thread_local! {
+        static LIVE: Cell<isize> = const { Cell::new(0) };
+        static TOTAL: Cell<usize> = const { Cell::new(0) };
+    }
+
+    impl CountingAllocator {
+        /// The number of allocations that haven't been freed yet.
+        fn live() -> isize {
+            LIVE.get()
+        }
+
+        /// The number of allocations ever made.
+        fn total() -> usize {
+            TOTAL.get()
+        }
+    }
+
+    impl GameAllocator for CountingAllocator {
+        fn allocate(layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            let ptr = NonNull::new(unsafe { System.alloc(layout) }).ok_or(AllocError)?;
+            LIVE.set(LIVE.get() + 1);
+            TOTAL.set(TOTAL.get() + 1);
+            Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+        }
+
+        unsafe fn deallocate(ptr: NonNull<u8>, layout: Layout) {
+            LIVE.set(LIVE.get() - 1);
+            unsafe { System.dealloc(ptr.as_ptr(), layout) };
+        }
+    }
+
+    type Ptr<T> = OwnedPtr<T, CountingAllocator>;
+
+    /// Records in a shared counter when it's dropped.
+    #[derive(Clone)]
+    struct DropCounter(Rc<Cell<usize>>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn drop_frees_and_drops_contents() {
+        let drops = Rc::new(Cell::new(0));
+        let ptr = Ptr::new(DropCounter(drops.clone()));
+        assert_eq!(1, CountingAllocator::live());
+
+        drop(ptr);
+        assert_eq!(0, CountingAllocator::live());
+        assert_eq!(1, drops.get());
+    }
+
+    #[test]
+    fn clone_allocates_separately() {
+        let a = Ptr::new(vec![1, 2, 3]);
+        let mut b = a.try_clone().unwrap();
+        assert_eq!(2, CountingAllocator::live());
+        assert_ne!(a.as_ptr(), b.as_ptr());
+
+        b.push(4);
+        assert_eq!(*a, [1, 2, 3]);
+        assert_eq!(*b, [1, 2, 3, 4]);
+
+        drop((a, b));
+        assert_eq!(0, CountingAllocator::live());
+    }
+
+    #[test]
+    fn raw_round_trip_neither_frees_nor_drops() {
+        let drops = Rc::new(Cell::new(0));
+        let raw = Ptr::new(DropCounter(drops.clone())).into_raw();
+        assert_eq!(1, CountingAllocator::live());
+        assert_eq!(0, drops.get());
+
+        drop(unsafe { Ptr::from_raw(raw) });
+        assert_eq!(0, CountingAllocator::live());
+        assert_eq!(1, drops.get());
+    }
+
+    #[test]
+    fn into_inner_frees_without_dropping() {
+        let drops = Rc::new(Cell::new(0));
+        let value = Ptr::new(DropCounter(drops.clone())).into_inner();
+        assert_eq!(0, CountingAllocator::live());
+        assert_eq!(0, drops.get());
+
+        drop(value);
+        assert_eq!(1, drops.get());
+    }
+
+    #[test]
+    fn map_replaces_allocation() {
+        let ptr = Ptr::new(21u32).map(|x| u64::from(x) * 2);
+        assert_eq!(1, CountingAllocator::live());
+        assert_eq!(42u64, *ptr);
+
+        drop(ptr);
+        assert_eq!(0, CountingAllocator::live());
+    }
+
+    #[test]
+    fn leak_never_frees() {
+        let leaked: &'static mut u32 = Ptr::new(5).leak();
+        *leaked += 1;
+        assert_eq!(1, CountingAllocator::live());
+
+        // Clean up so the count stays meaningful for this thread.
+        drop(unsafe { Ptr::from_raw(NonNull::from(leaked)) });
+        assert_eq!(0, CountingAllocator::live());
+    }
+
+    #[test]
+    fn zero_sized_types_never_allocate() {
+        let before = CountingAllocator::total();
+        let ptr = Ptr::new(());
+        let ptr = ptr.try_clone().unwrap();
+        ptr.into_inner();
+        assert_eq!(0, CountingAllocator::live());
+        assert_eq!(before, CountingAllocator::total());
+    }
+
+    #[test]
+    fn no_op_allocator_fails_to_allocate() {
+        assert!(OwnedPtr::<u32>::try_new(1).is_err());
+
+        let mut value = 1u32;
+        let ptr = unsafe { OwnedPtr::<u32>::from_raw(NonNull::from(&mut value)) };
+        assert!(ptr.try_clone().is_err());
+        ptr.into_raw();
+    }
+
+    #[test]
+    fn debug_formats_contents() {
+        assert_eq!("[1, 2]", format!("{:?}", Ptr::new([1, 2])));
+    }
+}
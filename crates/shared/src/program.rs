@@ -1,9 +1,19 @@
+#[cfg(any(test, feature = "test-util"))]
+use std::cell::Cell;
+#[cfg(any(test, feature = "test-util"))]
+use std::marker::PhantomData;
 use std::sync::LazyLock;
 
 use pelite::pe64::{Pe, PeFile, PeObject, PeView};
 use windows::Win32::System::LibraryLoader::GetModuleHandleA;
 use windows::core::PCSTR;
 
+#[cfg(any(test, feature = "test-util"))]
+mod mock;
+
+#[cfg(any(test, feature = "test-util"))]
+pub use mock::*;
+
 #[derive(Copy, Clone)]
 pub enum Program<'a> {
     File(PeFile<'a>),
@@ -15,13 +25,65 @@ static CURRENT_BASE: LazyLock<Program> = LazyLock::new(|| {
     Program::Mapping(unsafe { PeView::module(module) })
 });
 
+#[cfg(any(test, feature = "test-util"))]
+thread_local! {
+    static OVERRIDE: Cell<Option<Program<'static>>> = const { Cell::new(None) };
+}
+
 impl Program<'_> {
     /// Returns the currently running programing.
+    ///
+    /// With the `test-util` feature, if `Program::override_current` is
+    /// active on this thread, this returns the overriding program instead.
     pub fn current() -> Self {
+        #[cfg(any(test, feature = "test-util"))]
+        if let Some(program) = OVERRIDE.get() {
+            return program;
+        }
+
         *CURRENT_BASE
     }
 }
 
+#[cfg(any(test, feature = "test-util"))]
+impl Program<'static> {
+    /// Makes [Program::current] return this program on the current thread
+    /// until the returned guard is dropped.
+    ///
+    /// This is meant for tests, where there's no game executable to look up
+    /// RVAs in, and requires the `test-util` feature. See [MockImage] for a
+    /// way to build a program with the RTTI that
+    /// [Superclass](crate::Superclass) relies on.
+    ///
+    /// Overrides can be nested. Dropping a guard restores whichever program
+    /// was current when it was created, so guards should be dropped in the
+    /// reverse order they were created.
+    pub fn override_current(self) -> ProgramOverride {
+        ProgramOverride {
+            previous: OVERRIDE.replace(Some(self)),
+            _thread: PhantomData,
+        }
+    }
+}
+
+/// A guard returned by [Program::override_current] that restores the previous
+/// [Program::current] when dropped.
+#[cfg(any(test, feature = "test-util"))]
+#[must_use = "the override ends as soon as the guard is dropped"]
+pub struct ProgramOverride {
+    previous: Option<Program<'static>>,
+
+    // The override is thread-local, so the guard has to stay on its thread.
+    _thread: PhantomData<*const ()>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl Drop for ProgramOverride {
+    fn drop(&mut self) {
+        OVERRIDE.set(self.previous.take());
+    }
+}
+
 unsafe impl<'a> Pe<'a> for Program<'a> {}
 unsafe impl<'a> PeObject<'a> for Program<'a> {
     fn image(&self) -> &'a [u8] {
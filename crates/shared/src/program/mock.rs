@@ -0,0 +1,401 @@
+use std::alloc::{Layout, alloc_zeroed, handle_alloc_error};
+use std::collections::HashMap;
+use std::slice;
+
+use pelite::pe64::{PeView, Rva, Va};
+
+use super::{Program, ProgramOverride};
+
+const PAGE_SIZE: usize = 0x1000;
+const TEXT_RVA: Rva = 0x1000;
+const RDATA_RVA: Rva = 0x2000;
+
+/// The number of function pointers in each mock virtual method table.
+const VMT_LEN: usize = 4;
+
+/// An in-memory x64 PE image that contains MSVC RTTI and a virtual method
+/// table for each of a set of classes, but no real code.
+///
+/// This makes it possible to test code that depends on [Program::current] and
+/// RTTI, like [Superclass](crate::Superclass) casts, without a running game.
+/// Fake objects just need to start with one of the image's VMT addresses.
+///
+/// This requires the `test-util` feature, which is meant to be enabled only
+/// in `dev-dependencies`.
+///
+/// ```
+/// # #[cfg(feature = "test-util")] {
+/// use fromsoftware_shared::MockImage;
+///
+/// let image = MockImage::builder()
+///     .class("CS::ChrIns", &[])
+///     .class("CS::PlayerIns", &["CS::ChrIns"])
+///     .build();
+/// let _guard = image.install();
+///
+/// let player_ins_vmt = image.vmt_va("CS::PlayerIns");
+/// # }
+/// ```
+pub struct MockImage {
+    view: PeView<'static>,
+    base: Va,
+    vmts: HashMap<String, Rva>,
+}
+
+/// A builder for [MockImage].
+#[derive(Default)]
+pub struct MockImageBuilder {
+    classes: Vec<(String, Vec<String>)>,
+}
+
+impl MockImage {
+    /// Returns a builder for a new image.
+    pub fn builder() -> MockImageBuilder {
+        Default::default()
+    }
+
+    /// Returns this image as a [Program].
+    pub fn program(&self) -> Program<'static> {
+        Program::Mapping(self.view)
+    }
+
+    /// Makes this image the [Program::current] on this thread until the
+    /// returned guard is dropped.
+    pub fn install(&self) -> ProgramOverride {
+        self.program().override_current()
+    }
+
+    /// Returns the RVA of the virtual method table for `class`.
+    ///
+    /// Panics if `class` wasn't added to this image.
+    pub fn vmt_rva(&self, class: &str) -> Rva {
+        *self
+            .vmts
+            .get(class)
+            .unwrap_or_else(|| panic!("{class} isn't in the mock image"))
+    }
+
+    /// Returns the address of the virtual method table for `class`.
+    ///
+    /// Panics if `class` wasn't added to this image.
+    pub fn vmt_va(&self, class: &str) -> Va {
+        self.base + self.vmt_rva(class) as Va
+    }
+}
+
+impl MockImageBuilder {
+    /// Adds a class with the fully-qualified name `name`, such as
+    /// `CS::PlayerIns`, that directly inherits from `bases`.
+    ///
+    /// Panics if any of `bases` hasn't been added yet.
+    pub fn class(mut self, name: &str, bases: &[&str]) -> Self {
+        for base in bases {
+            assert!(
+                self.classes.iter().any(|(class, _)| class == base),
+                "{base} must be added before its subclass {name}"
+            );
+        }
+        assert!(
+            !self.classes.iter().any(|(class, _)| class == name),
+            "{name} was added twice"
+        );
+
+        self.classes.push((
+            name.to_string(),
+            bases.iter().map(|base| base.to_string()).collect(),
+        ));
+        self
+    }
+
+    /// Lays out the image in memory.
+    ///
+    /// Because [Program::current] hands out `'static` references into the
+    /// image, it's leaked rather than ever being freed.
+    pub fn build(self) -> &'static MockImage {
+        let mut rdata = Rdata::default();
+        let mut base_class_arrays = HashMap::<&str, Vec<Rva>>::new();
+        let mut vmts = HashMap::new();
+
+        for (name, bases) in &self.classes {
+            let type_descriptor = rdata.rva();
+            rdata.push(&0u64.to_le_bytes()); // type_info vftable
+            rdata.push(&0u64.to_le_bytes()); // spare
+            rdata.push(mangle(name).as_bytes());
+            rdata.push(&[0]);
+            rdata.align();
+
+            // Each base class array starts with the class itself, followed by
+            // all of its bases' arrays.
+            let inherited = bases
+                .iter()
+                .flat_map(|base| base_class_arrays[base.as_str()].iter().copied())
+                .collect::<Vec<_>>();
+
+            let base_class_descriptor = rdata.rva();
+            let class_descriptor = base_class_descriptor + 0x20;
+            let base_class_array = class_descriptor + 0x10;
+            rdata.push_u32(type_descriptor);
+            rdata.push_u32(inherited.len() as u32);
+            rdata.push_u32(0); // mdisp
+            rdata.push_u32(u32::MAX); // pdisp
+            rdata.push_u32(0); // vdisp
+            rdata.push_u32(0x40); // BCD_HASPCHD
+            rdata.push_u32(class_descriptor);
+            rdata.align();
+
+            rdata.push_u32(0); // signature
+            rdata.push_u32(if bases.len() > 1 { 1 } else { 0 }); // CHD_MULTINH
+            rdata.push_u32(inherited.len() as u32 + 1);
+            rdata.push_u32(base_class_array);
+
+            let mut array = vec![base_class_descriptor];
+            array.extend(inherited);
+            for rva in &array {
+                rdata.push_u32(*rva);
+            }
+            rdata.align();
+            base_class_arrays.insert(name, array);
+
+            let complete_object_locator = rdata.rva();
+            rdata.push_u32(1); // signature
+            rdata.push_u32(0); // offset
+            rdata.push_u32(0); // cd_offset
+            rdata.push_u32(type_descriptor);
+            rdata.push_u32(class_descriptor);
+            rdata.push_u32(complete_object_locator);
+
+            // The complete object locator's address comes right before the
+            // table itself.
+            rdata.push_va(complete_object_locator);
+            vmts.insert(name.clone(), rdata.rva());
+            for _ in 0..VMT_LEN {
+                rdata.push_va(TEXT_RVA);
+            }
+        }
+
+        let rdata_size = rdata.bytes.len().next_multiple_of(PAGE_SIZE);
+        let size = RDATA_RVA as usize + rdata_size;
+        let layout = Layout::from_size_align(size, PAGE_SIZE).unwrap();
+        let ptr = unsafe { alloc_zeroed(layout) };
+        if ptr.is_null() {
+            handle_alloc_error(layout);
+        }
+        let image: &'static mut [u8] = unsafe { slice::from_raw_parts_mut(ptr, size) };
+        let base = ptr as Va;
+
+        write_headers(image, base, rdata_size as u32);
+        image[TEXT_RVA as usize..RDATA_RVA as usize].fill(0xc3); // ret
+        image[RDATA_RVA as usize..][..rdata.bytes.len()].copy_from_slice(&rdata.bytes);
+        for offset in rdata.va_offsets {
+            let start = RDATA_RVA as usize + offset;
+            let rva = u64::from_le_bytes(image[start..start + 8].try_into().unwrap());
+            image[start..start + 8].copy_from_slice(&(base + rva).to_le_bytes());
+        }
+
+        let view = PeView::from_bytes(image).expect("mock image has invalid headers");
+        Box::leak(Box::new(MockImage { view, base, vmts }))
+    }
+}
+
+/// The contents of the `.rdata` section while it's being built.
+#[derive(Default)]
+struct Rdata {
+    bytes: Vec<u8>,
+
+    /// Offsets of values that are written as RVAs but need to be converted to
+    /// VAs once the image's address is known.
+    va_offsets: Vec<usize>,
+}
+
+impl Rdata {
+    fn rva(&self) -> Rva {
+        RDATA_RVA + self.bytes.len() as Rva
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn push_u32(&mut self, value: u32) {
+        self.push(&value.to_le_bytes());
+    }
+
+    fn push_va(&mut self, rva: Rva) {
+        self.va_offsets.push(self.bytes.len());
+        self.push(&(rva as u64).to_le_bytes());
+    }
+
+    fn align(&mut self) {
+        let len = self.bytes.len().next_multiple_of(size_of::<Va>());
+        self.bytes.resize(len, 0);
+    }
+}
+
+/// Returns the name MSVC uses in type descriptors for `name`, for example
+/// `.?AVChrIns@CS@@` for `CS::ChrIns`.
+fn mangle(name: &str) -> String {
+    let mut mangled = String::from(".?AV");
+    for segment in name.rsplit("::") {
+        mangled.push_str(segment);
+        mangled.push('@');
+    }
+    mangled.push('@');
+    mangled
+}
+
+/// Writes the DOS, NT and section headers for an image at `base` with a
+/// single page of `.text` followed by `rdata_size` bytes of `.rdata`.
+fn write_headers(image: &mut [u8], base: Va, rdata_size: u32) {
+    let mut put = |offset: usize, bytes: &[u8]| {
+        image[offset..offset + bytes.len()].copy_from_slice(bytes);
+    };
+
+    // IMAGE_DOS_HEADER
+    put(0x0, b"MZ");
+    put(0x3c, &0x40u32.to_le_bytes()); // e_lfanew
+
+    // IMAGE_NT_HEADERS64
+    put(0x40, b"PE\0\0");
+    put(0x44, &0x8664u16.to_le_bytes()); // Machine = AMD64
+    put(0x46, &2u16.to_le_bytes()); // NumberOfSections
+    put(0x54, &0xf0u16.to_le_bytes()); // SizeOfOptionalHeader
+    put(0x56, &0x22u16.to_le_bytes()); // Characteristics
+
+    // IMAGE_OPTIONAL_HEADER64
+    let optional = 0x58;
+    put(optional, &0x20bu16.to_le_bytes()); // Magic
+    put(optional + 0x4, &(PAGE_SIZE as u32).to_le_bytes()); // SizeOfCode
+    put(optional + 0x8, &rdata_size.to_le_bytes()); // SizeOfInitializedData
+    put(optional + 0x14, &TEXT_RVA.to_le_bytes()); // BaseOfCode
+    put(optional + 0x18, &base.to_le_bytes()); // ImageBase
+    put(optional + 0x20, &(PAGE_SIZE as u32).to_le_bytes()); // SectionAlignment
+    put(optional + 0x24, &(PAGE_SIZE as u32).to_le_bytes()); // FileAlignment
+    put(optional + 0x30, &6u16.to_le_bytes()); // MajorSubsystemVersion
+    put(optional + 0x38, &(RDATA_RVA + rdata_size).to_le_bytes()); // SizeOfImage
+    put(optional + 0x3c, &(PAGE_SIZE as u32).to_le_bytes()); // SizeOfHeaders
+    put(optional + 0x44, &3u16.to_le_bytes()); // Subsystem = console
+    put(optional + 0x6c, &16u32.to_le_bytes()); // NumberOfRvaAndSizes
+
+    // IMAGE_SECTION_HEADER
+    let sections = [
+        (b".text\0\0\0", TEXT_RVA, PAGE_SIZE as u32, 0x6000_0020u32),
+        (b".rdata\0\0", RDATA_RVA, rdata_size, 0x4000_0040u32),
+    ];
+    for (i, (name, rva, size, characteristics)) in sections.into_iter().enumerate() {
+        let header = optional + 0xf0 + i * 0x28;
+        put(header, name);
+        put(header + 0x8, &size.to_le_bytes()); // VirtualSize
+        put(header + 0xc, &rva.to_le_bytes()); // VirtualAddress
+        put(header + 0x10, &size.to_le_bytes()); // SizeOfRawData
+        put(header + 0x14, &rva.to_le_bytes()); // PointerToRawData
+        put(header + 0x24, &characteristics.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pelite::pe64::{Pe, msvc::RTTICompleteObjectLocator};
+
+    use super::*;
+    use crate::program::OVERRIDE;
+    use crate::{find_rtti_classes, is_base_class, vftable_classname};
+
+    fn image() -> &'static MockImage {
+        MockImage::builder()
+            .class("CS::ChrIns", &[])
+            .class("CS::PlayerIns", &["CS::ChrIns"])
+            .class("CS::ReplayGhostIns", &["CS::PlayerIns"])
+            .class("GlobalClass", &[])
+            .build()
+    }
+
+    fn col(image: &MockImage, class: &str) -> &'static RTTICompleteObjectLocator {
+        let va = image.vmt_va(class) - size_of::<Va>() as Va;
+        unsafe { &**(va as *const *const RTTICompleteObjectLocator) }
+    }
+
+    #[test]
+    fn vmt_addresses_are_in_image() {
+        let image = image();
+        let program = image.program();
+        let rva = image.vmt_rva("CS::PlayerIns");
+        assert_eq!(Ok(image.vmt_va("CS::PlayerIns")), program.rva_to_va(rva));
+        assert_eq!(Ok(rva), program.va_to_rva(image.vmt_va("CS::PlayerIns")));
+    }
+
+    #[test]
+    fn vftable_classnames() {
+        let image = image();
+        let program = image.program();
+        for class in ["CS::ChrIns", "CS::ReplayGhostIns", "GlobalClass"] {
+            assert_eq!(
+                Some(class.to_string()),
+                vftable_classname(&program, image.vmt_va(class) as usize)
+            );
+        }
+    }
+
+    #[test]
+    fn find_classes() {
+        let image = image();
+        let program = image.program();
+        let mut classes = find_rtti_classes(&program)
+            .map(|class| (class.name, class.vftable))
+            .collect::<Vec<_>>();
+        classes.sort();
+        assert_eq!(
+            vec![
+                ("CS::ChrIns".to_string(), image.vmt_rva("CS::ChrIns")),
+                ("CS::PlayerIns".to_string(), image.vmt_rva("CS::PlayerIns")),
+                (
+                    "CS::ReplayGhostIns".to_string(),
+                    image.vmt_rva("CS::ReplayGhostIns")
+                ),
+                ("GlobalClass".to_string(), image.vmt_rva("GlobalClass")),
+            ],
+            classes
+        );
+    }
+
+    #[test]
+    fn base_classes() {
+        let image = image();
+        let program = image.program();
+        let chr_ins = col(image, "CS::ChrIns");
+        let player_ins = col(image, "CS::PlayerIns");
+        let replay_ghost_ins = col(image, "CS::ReplayGhostIns");
+        let global = col(image, "GlobalClass");
+
+        assert_eq!(Ok(true), is_base_class(&program, chr_ins, chr_ins));
+        assert_eq!(Ok(true), is_base_class(&program, chr_ins, replay_ghost_ins));
+        assert_eq!(
+            Ok(true),
+            is_base_class(&program, player_ins, replay_ghost_ins)
+        );
+        assert_eq!(Ok(false), is_base_class(&program, player_ins, chr_ins));
+        assert_eq!(Ok(false), is_base_class(&program, global, player_ins));
+    }
+
+    #[test]
+    fn override_current() {
+        let first = image();
+        let second = image();
+        let current_base = || Program::current().optional_header().ImageBase;
+
+        let outer = first.install();
+        assert_eq!(first.base, current_base());
+        {
+            let _inner = second.install();
+            assert_eq!(second.base, current_base());
+        }
+        assert_eq!(first.base, current_base());
+
+        // Overrides don't leak into other threads.
+        std::thread::spawn(|| assert!(OVERRIDE.get().is_none()))
+            .join()
+            .unwrap();
+
+        drop(outer);
+        assert!(OVERRIDE.get().is_none());
+    }
+}
@@ -0,0 +1,147 @@
+use std::alloc::Layout;
+use std::ffi::c_void;
+use std::sync::OnceLock;
+use std::{marker::PhantomData, ptr::NonNull};
+
+use fromsoftware_shared_stl::Allocator;
+
+use crate::{AllocError, GameAllocator};
+
+/// A game heap whose address isn't known ahead of time, so it has to be taken
+/// from a game structure that holds it and registered at runtime before
+/// [`RegisteredHeapAllocator`] can use it.
+///
+/// This is implemented by each game's allocator reference type for games whose
+/// global heaps haven't been mapped yet.
+pub trait RegisteredHeap: Allocator + 'static {
+    /// Returns the slot that holds the registered heap. This should be a
+    /// `static` of its own for each implementer.
+    fn slot() -> &'static HeapSlot<Self>;
+}
+
+/// Storage for a [`RegisteredHeap`].
+pub struct HeapSlot<H>(OnceLock<H>);
+
+impl<H> HeapSlot<H> {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self(OnceLock::new())
+    }
+}
+
+// Safety: a heap can only be stored through RegisteredHeapAllocator::register,
+// whose caller guarantees that it's usable from any thread.
+unsafe impl<H> Send for HeapSlot<H> {}
+unsafe impl<H> Sync for HeapSlot<H> {}
+
+/// A [`GameAllocator`] that allocates from and frees to the heap registered
+/// for `H`.
+///
+/// Until a heap is registered with [`RegisteredHeapAllocator::register`],
+/// [`GameAllocator::allocate`] always fails. Since nothing could have been
+/// allocated before then, [`GameAllocator::deallocate`] leaks rather than
+/// panics if it's somehow reached without a heap, so dropping an
+/// [`OwnedPtr`](crate::OwnedPtr) never panics because of it.
+pub struct RegisteredHeapAllocator<H: RegisteredHeap>(PhantomData<H>);
+
+impl<H: RegisteredHeap> RegisteredHeapAllocator<H> {
+    /// Registers the heap that this should allocate from.
+    ///
+    /// Returns `false` without doing anything if a heap was already
+    /// registered, since memory allocated by it couldn't be freed otherwise.
+    ///
+    /// ## Safety
+    ///
+    /// `heap` must be safe to use from any thread and remain valid for the rest
+    /// of the process's lifetime.
+    pub unsafe fn register(heap: H) -> bool {
+        H::slot().0.set(heap).is_ok()
+    }
+
+    /// Returns the registered heap, if there is one.
+    pub fn get() -> Option<H> {
+        H::slot().0.get().cloned()
+    }
+}
+
+impl<H: RegisteredHeap> GameAllocator for RegisteredHeapAllocator<H> {
+    fn allocate(layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let mut heap = Self::get().ok_or(AllocError)?;
+        let ptr = unsafe { heap.allocate_raw(layout.size(), layout.align()) };
+        NonNull::new(ptr as *mut u8)
+            .map(|ptr| NonNull::slice_from_raw_parts(ptr, layout.size()))
+            .ok_or(AllocError)
+    }
+
+    unsafe fn deallocate(ptr: NonNull<u8>, _layout: Layout) {
+        if let Some(mut heap) = Self::get() {
+            unsafe { heap.deallocate_raw(ptr.as_ptr() as *mut c_void) }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::alloc::{GlobalAlloc, System};
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::OwnedPtr;
+
+    thread_local! {
+        static LIVE: Cell<isize> = const { Cell::new(0) };
+    }
+
+    // Each test uses its own heap type since registration can't be undone.
+    macro_rules! system_heap {
+        ($name:ident) => {
+            #[derive(Clone)]
+            struct $name;
+
+            impl Allocator for $name {
+                unsafe fn allocate_raw(&mut self, size: usize, align: usize) -> *mut c_void {
+                    LIVE.set(LIVE.get() + 1);
+                    // The test allocations all use the same layout.
+                    unsafe { System.alloc(Layout::from_size_align(size, align).unwrap()) as _ }
+                }
+
+                unsafe fn deallocate_raw(&mut self, ptr: *mut c_void) {
+                    LIVE.set(LIVE.get() - 1);
+                    unsafe { System.dealloc(ptr as _, Layout::new::<u64>()) };
+                }
+            }
+
+            impl RegisteredHeap for $name {
+                fn slot() -> &'static HeapSlot<Self> {
+                    static SLOT: HeapSlot<$name> = HeapSlot::new();
+                    &SLOT
+                }
+            }
+        };
+    }
+
+    #[test]
+    fn allocates_once_registered() {
+        system_heap!(Heap);
+        type Registered = RegisteredHeapAllocator<Heap>;
+
+        assert!(OwnedPtr::<u64, Registered>::try_new(1).is_err());
+
+        assert!(unsafe { Registered::register(Heap) });
+        assert!(!unsafe { Registered::register(Heap) });
+        let ptr = OwnedPtr::<u64, Registered>::new(1);
+        assert_eq!(1, LIVE.get());
+
+        drop(ptr);
+        assert_eq!(0, LIVE.get());
+    }
+
+    #[test]
+    fn deallocate_without_heap_leaks() {
+        system_heap!(Heap);
+        type Registered = RegisteredHeapAllocator<Heap>;
+
+        let value = Box::new(1u64);
+        drop(unsafe { OwnedPtr::<u64, Registered>::from_raw(NonNull::from(Box::leak(value))) });
+    }
+}
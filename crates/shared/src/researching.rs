@@ -1,4 +1,6 @@
-use std::fmt::Debug;
+use std::any::type_name;
+use std::fmt::{self, Debug, Write as _};
+use std::io;
 
 /// A trait that's intended to be applied to a type using a derive macro when
 /// actively trying to research the behavior and meaning of that type's fields.
@@ -11,4 +13,427 @@ pub trait Researching {
     /// When `#[derive(Researching)]` is used, it treats and field whose name
     /// begins with `_unk` or `unk` as unknown.
     fn unknown_fields(&self) -> Vec<(&str, &dyn Debug)>;
+
+    /// Returns the name, offset within `Self`, and raw in-memory bytes of each
+    /// unknown field.
+    ///
+    /// If an unknown field's type has internal padding, the corresponding
+    /// bytes are whatever happens to be in memory there.
+    fn unknown_field_bytes(&self) -> Vec<(&'static str, usize, &[u8])>;
+}
+
+/// A copy of the raw bytes of all of a [Researching] value's unknown fields at
+/// a single point in time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResearchSnapshot {
+    /// The name of the type that was captured.
+    pub type_name: &'static str,
+
+    /// The unknown fields, in declaration order.
+    pub fields: Vec<FieldSnapshot>,
+}
+
+/// The bytes of a single unknown field in a [ResearchSnapshot].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldSnapshot {
+    /// The field's name, such as `unk1a0`.
+    pub name: &'static str,
+
+    /// The field's offset from the beginning of the struct.
+    pub offset: usize,
+
+    /// The field's bytes, in memory order.
+    pub bytes: Vec<u8>,
+}
+
+/// A single unknown field whose bytes differ between two
+/// [ResearchSnapshot]s.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    /// The field's name, such as `unk1a0`.
+    pub name: &'static str,
+
+    /// The field's offset from the beginning of the struct.
+    pub offset: usize,
+
+    /// The field's bytes in the earlier snapshot.
+    pub before: Vec<u8>,
+
+    /// The field's bytes in the later snapshot.
+    pub after: Vec<u8>,
+}
+
+impl ResearchSnapshot {
+    /// Captures the current bytes of all of `value`'s unknown fields.
+    pub fn capture<T: Researching>(value: &T) -> Self {
+        ResearchSnapshot {
+            type_name: type_name::<T>(),
+            fields: value
+                .unknown_field_bytes()
+                .into_iter()
+                .map(|(name, offset, bytes)| FieldSnapshot {
+                    name,
+                    offset,
+                    bytes: bytes.to_vec(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns all the fields whose bytes are different in `later` than they
+    /// are in `self`.
+    ///
+    /// Fields are matched up by name, so fields that only exist in one of the
+    /// two snapshots are ignored.
+    pub fn diff(&self, later: &ResearchSnapshot) -> Vec<FieldChange> {
+        self.fields
+            .iter()
+            .filter_map(|before| {
+                let after = later.fields.iter().find(|f| f.name == before.name)?;
+                (before.bytes != after.bytes).then(|| FieldChange {
+                    name: before.name,
+                    offset: before.offset,
+                    before: before.bytes.clone(),
+                    after: after.bytes.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} changed {} \u{2192} {}",
+            self.name,
+            display_bytes(&self.before),
+            display_bytes(&self.after)
+        )
+    }
+}
+
+/// A single [FieldChange] recorded by a [ResearchLog].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResearchLogEntry {
+    /// The frame (or any other caller-defined timestamp) at which the change
+    /// was observed.
+    pub frame: u64,
+
+    /// The game event that was passed to [ResearchLog::record_event] when the
+    /// change was observed, if any.
+    pub event: Option<String>,
+
+    /// The name of the type whose field changed.
+    pub type_name: &'static str,
+
+    /// The change itself.
+    pub change: FieldChange,
+}
+
+/// A history of changes to a [Researching] value's unknown fields.
+///
+/// Call [ResearchLog::record] once per frame (or whenever's convenient) with
+/// the value being researched, and [ResearchLog::record_event] when something
+/// interesting happens in-game. Each call compares the value against the
+/// previous call and logs any fields that changed, which can then be written
+/// out with [ResearchLog::write_csv] or [ResearchLog::write_jsonl] for offline
+/// analysis.
+#[derive(Clone, Debug, Default)]
+pub struct ResearchLog {
+    previous: Option<ResearchSnapshot>,
+    entries: Vec<ResearchLogEntry>,
+}
+
+impl ResearchLog {
+    /// Creates an empty log.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Snapshots `value` and logs any unknown fields that changed since the
+    /// last call, attributing them to `frame`. Returns the newly-logged
+    /// entries.
+    ///
+    /// The first call just establishes a baseline and never logs anything.
+    pub fn record<T: Researching>(&mut self, frame: u64, value: &T) -> &[ResearchLogEntry] {
+        self.record_snapshot(frame, None, ResearchSnapshot::capture(value))
+    }
+
+    /// Like [ResearchLog::record], but labels any changes with `event` to
+    /// indicate what happened in-game since the last snapshot.
+    pub fn record_event<T: Researching>(
+        &mut self,
+        frame: u64,
+        event: impl Into<String>,
+        value: &T,
+    ) -> &[ResearchLogEntry] {
+        self.record_snapshot(frame, Some(event.into()), ResearchSnapshot::capture(value))
+    }
+
+    /// Like [ResearchLog::record], but for a snapshot that was already
+    /// captured.
+    pub fn record_snapshot(
+        &mut self,
+        frame: u64,
+        event: Option<String>,
+        snapshot: ResearchSnapshot,
+    ) -> &[ResearchLogEntry] {
+        let start = self.entries.len();
+        if let Some(previous) = &self.previous {
+            self.entries
+                .extend(
+                    previous
+                        .diff(&snapshot)
+                        .into_iter()
+                        .map(|change| ResearchLogEntry {
+                            frame,
+                            event: event.clone(),
+                            type_name: snapshot.type_name,
+                            change,
+                        }),
+                );
+        }
+        self.previous = Some(snapshot);
+        &self.entries[start..]
+    }
+
+    /// Returns all entries logged so far, oldest first.
+    pub fn entries(&self) -> &[ResearchLogEntry] {
+        &self.entries
+    }
+
+    /// Removes all logged entries. The most recent snapshot is kept, so the
+    /// next call to [ResearchLog::record] still compares against it.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Writes all entries as CSV with a header row to `writer`.
+    ///
+    /// The `before` and `after` columns contain the field's bytes in hex, in
+    /// memory order.
+    pub fn write_csv(&self, mut writer: impl io::Write) -> io::Result<()> {
+        writeln!(writer, "frame,event,type,field,offset,before,after")?;
+        for entry in &self.entries {
+            writeln!(
+                writer,
+                "{},{},{},{},0x{:x},{},{}",
+                entry.frame,
+                csv_escape(entry.event.as_deref().unwrap_or_default()),
+                csv_escape(entry.type_name),
+                entry.change.name,
+                entry.change.offset,
+                hex_bytes(&entry.change.before),
+                hex_bytes(&entry.change.after),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes all entries to `writer` as newline-delimited JSON objects.
+    ///
+    /// The `before` and `after` properties contain the field's bytes in hex,
+    /// in memory order.
+    pub fn write_jsonl(&self, mut writer: impl io::Write) -> io::Result<()> {
+        for entry in &self.entries {
+            let event = match &entry.event {
+                Some(event) => json_string(event),
+                None => "null".to_string(),
+            };
+            writeln!(
+                writer,
+                r#"{{"frame":{},"event":{},"type":{},"field":{},"offset":{},"before":"{}","after":"{}"}}"#,
+                entry.frame,
+                event,
+                json_string(entry.type_name),
+                json_string(entry.change.name),
+                entry.change.offset,
+                hex_bytes(&entry.change.before),
+                hex_bytes(&entry.change.after),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats `bytes` as an unsigned little-endian integer if it's the size of
+/// one, or as hex bytes otherwise.
+fn display_bytes(bytes: &[u8]) -> String {
+    match bytes.len() {
+        1 | 2 | 4 | 8 => {
+            let mut buffer = [0; 8];
+            buffer[..bytes.len()].copy_from_slice(bytes);
+            u64::from_le_bytes(buffer).to_string()
+        }
+        _ => hex_bytes(bytes),
+    }
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut result, byte| {
+        let _ = write!(result, "{byte:02x}");
+        result
+    })
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut result = String::from("\"");
+    for ch in value.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            ch if ch.is_control() => {
+                let _ = write!(result, "\\u{:04x}", ch as u32);
+            }
+            ch => result.push(ch),
+        }
+    }
+    result.push('"');
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Researching;
+
+    #[repr(C)]
+    #[derive(Researching)]
+    struct Mount {
+        _state: u32,
+        unk4: u32,
+        unk8: [u8; 3],
+        _unkb: u8,
+    }
+
+    fn mount(unk4: u32, unk8: [u8; 3]) -> Mount {
+        Mount {
+            _state: 0,
+            unk4,
+            unk8,
+            _unkb: 0,
+        }
+    }
+
+    #[test]
+    fn capture() {
+        let snapshot = ResearchSnapshot::capture(&mount(0x0102, [1, 2, 3]));
+        assert!(snapshot.type_name.ends_with("Mount"));
+        assert_eq!(
+            vec![
+                FieldSnapshot {
+                    name: "unk4",
+                    offset: 4,
+                    bytes: vec![2, 1, 0, 0]
+                },
+                FieldSnapshot {
+                    name: "unk8",
+                    offset: 8,
+                    bytes: vec![1, 2, 3]
+                },
+                FieldSnapshot {
+                    name: "_unkb",
+                    offset: 0xb,
+                    bytes: vec![0]
+                },
+            ],
+            snapshot.fields
+        );
+    }
+
+    #[test]
+    fn diff() {
+        let before = ResearchSnapshot::capture(&mount(0, [1, 2, 3]));
+        let after = ResearchSnapshot::capture(&mount(1, [1, 2, 3]));
+        let changes = before.diff(&after);
+        assert_eq!(1, changes.len());
+        assert_eq!("unk4 changed 0 \u{2192} 1", changes[0].to_string());
+
+        let after = ResearchSnapshot::capture(&mount(0, [1, 0xff, 3]));
+        assert_eq!(
+            "unk8 changed 010203 \u{2192} 01ff03",
+            before.diff(&after)[0].to_string()
+        );
+        assert!(before.diff(&before).is_empty());
+    }
+
+    #[test]
+    fn log_records_changes() {
+        let mut log = ResearchLog::new();
+        assert!(log.record(0, &mount(0, [0; 3])).is_empty());
+        assert!(log.record(1, &mount(0, [0; 3])).is_empty());
+
+        let entries = log.record_event(2, "mounted Torrent", &mount(1, [0; 3]));
+        assert_eq!(1, entries.len());
+        assert_eq!(2, entries[0].frame);
+        assert_eq!(Some("mounted Torrent"), entries[0].event.as_deref());
+        assert_eq!("unk4", entries[0].change.name);
+
+        log.record(3, &mount(1, [0, 0, 1]));
+        assert_eq!(2, log.entries().len());
+        assert_eq!(None, log.entries()[1].event);
+
+        log.clear();
+        assert!(log.entries().is_empty());
+        assert!(log.record(4, &mount(1, [0, 0, 1])).is_empty());
+    }
+
+    #[test]
+    fn write_csv() {
+        let mut log = ResearchLog::new();
+        log.record(0, &mount(0, [0; 3]));
+        log.record_event(1, "said \"hi\", twice", &mount(1, [0; 3]));
+
+        let mut output = Vec::new();
+        log.write_csv(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let mut lines = output.lines();
+        assert_eq!(
+            Some("frame,event,type,field,offset,before,after"),
+            lines.next()
+        );
+        let row = lines.next().unwrap();
+        assert!(row.starts_with("1,\"said \"\"hi\"\", twice\","), "{row}");
+        assert!(row.ends_with("Mount,unk4,0x4,00000000,01000000"), "{row}");
+        assert_eq!(None, lines.next());
+    }
+
+    #[test]
+    fn write_jsonl() {
+        let mut log = ResearchLog::new();
+        log.record(0, &mount(0, [0; 3]));
+        log.record(1, &mount(0, [0, 0, 7]));
+        log.record_event(2, "line\nbreak", &mount(0, [0, 0, 8]));
+
+        let mut output = Vec::new();
+        log.write_jsonl(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(2, lines.len());
+        assert!(lines[0].starts_with(r#"{"frame":1,"event":null,"type":""#));
+        assert!(
+            lines[0].ends_with(
+                r#"Mount","field":"unk8","offset":8,"before":"000000","after":"000007"}"#
+            ),
+            "{}",
+            lines[0]
+        );
+        assert!(
+            lines[1].contains(r#""event":"line\nbreak""#),
+            "{}",
+            lines[1]
+        );
+    }
 }
@@ -0,0 +1,36 @@
+//! VMT RVAs for the classes declared in this crate's tests, which the
+//! `Superclass` and `Subclass` derives look up through `crate::rva::get()`.
+
+use std::sync::LazyLock;
+
+use pelite::pe64::Rva;
+
+use crate::MockImage;
+
+/// The image containing the test classes. Tests need to
+/// [install](MockImage::install) it before casting.
+pub static IMAGE: LazyLock<&'static MockImage> = LazyLock::new(|| {
+    MockImage::builder()
+        .class("CS::ChrIns", &[])
+        .class("CS::PlayerIns", &["CS::ChrIns"])
+        .class("CS::EnemyIns", &["CS::ChrIns"])
+        .class("CS::ReplayGhostIns", &["CS::PlayerIns"])
+        .class("CS::FieldIns", &[])
+        .build()
+});
+
+pub struct RvaBundle {
+    pub chr_ins_vmt: Rva,
+    pub player_ins_vmt: Rva,
+    pub enemy_ins_vmt: Rva,
+}
+
+pub fn get() -> &'static RvaBundle {
+    static RVAS: LazyLock<RvaBundle> = LazyLock::new(|| RvaBundle {
+        chr_ins_vmt: IMAGE.vmt_rva("CS::ChrIns"),
+        player_ins_vmt: IMAGE.vmt_rva("CS::PlayerIns"),
+        enemy_ins_vmt: IMAGE.vmt_rva("CS::EnemyIns"),
+    });
+
+    &RVAS
+}
@@ -1,4 +1,10 @@
-use std::{borrow::Cow, ptr::NonNull};
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::{LazyLock, Mutex, PoisonError};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use std::{borrow::Cow, fmt, ptr::NonNull, thread};
 
 use from_singleton::*;
 use pelite::pe64::{Pe, Rva};
@@ -25,6 +31,10 @@ pub enum InstanceError {
 /// A [Result] whose error type is [InstanceError].
 pub type InstanceResult<T> = Result<T, InstanceError>;
 
+/// The longest [FromStatic::wait_for_instance_ptr] sleeps between checks,
+/// which is about one frame at 60 FPS.
+const MAX_WAIT_INTERVAL: Duration = Duration::from_millis(16);
+
 /// A trait for all objects that are instantiated a single time at a fixed point
 /// in memory.
 ///
@@ -77,6 +87,193 @@ pub trait FromStatic {
         Self::instance_ptr()
             .and_then(|p| unsafe { p.as_ref() }.ok_or(InstanceError::Null(Self::name())))
     }
+
+    /// Blocks this thread until the single global instance of this object is
+    /// available or until `timeout` elapses, whichever comes first.
+    ///
+    /// Both [InstanceError::Null] and [InstanceError::NotFound] are treated as
+    /// "not available yet", since DLRF singletons aren't found at all until
+    /// the game registers them. If the timeout elapses, this returns the last
+    /// error.
+    ///
+    /// This checks for the object repeatedly, sleeping between checks for
+    /// longer each time up to about a frame, so it may return up to a frame
+    /// after the object becomes available.
+    ///
+    /// **Note:** This should never be called on the main thread, since the
+    /// main thread is usually responsible for initializing the object in the
+    /// first place. Use [FromStatic::ready] or [on_instance_ready] there
+    /// instead.
+    ///
+    /// **Note:** This only polls [FromStatic::instance_ptr], so it doesn't
+    /// wait for the game to start up. For [FromSingleton]s and other types
+    /// that are looked up through DLRF, wait for system initialization first
+    /// (each game crate has a `wait_for_system_init` function for this), since
+    /// looking up DLRF singletons before the game has set up its reflection
+    /// data isn't supported. The game crates' task managers have inherent
+    /// `wait_for_instance` methods that do this for them.
+    fn wait_for_instance_ptr(timeout: Duration) -> InstanceResult<NonNull<Self>> {
+        // A timeout too long to represent as an [Instant] never elapses.
+        let deadline = Instant::now().checked_add(timeout);
+        let mut interval = Duration::from_millis(1);
+        loop {
+            let err = match Self::instance_ptr().map(NonNull::new) {
+                Ok(Some(ptr)) => return Ok(ptr),
+                Ok(None) => InstanceError::Null(Self::name()),
+                Err(err) => err,
+            };
+
+            let now = Instant::now();
+            let sleep = match deadline {
+                Some(deadline) if now >= deadline => return Err(err),
+                Some(deadline) => interval.min(deadline - now),
+                None => interval,
+            };
+            thread::sleep(sleep);
+            interval = (interval * 2).min(MAX_WAIT_INTERVAL);
+        }
+    }
+
+    /// Blocks this thread until the single global instance of this object is
+    /// available and returns a reference to it. See
+    /// [FromStatic::wait_for_instance_ptr] for details.
+    ///
+    /// ## Safety
+    ///
+    /// This has the same safety requirements as [FromStatic::instance].
+    unsafe fn wait_for_instance(timeout: Duration) -> InstanceResult<&'static Self> {
+        Self::wait_for_instance_ptr(timeout).map(|ptr| unsafe { ptr.as_ref() })
+    }
+
+    /// Returns a future that resolves once the single global instance of this
+    /// object is available.
+    ///
+    /// The future doesn't get notified when the object is created. Instead, it
+    /// checks whether the object exists each time it's polled and immediately
+    /// asks to be polled again if it doesn't. It's intended to be driven once
+    /// per frame by an executor running on the game's task system.
+    fn ready() -> InstanceReady<Self>
+    where
+        Self: Sized,
+    {
+        InstanceReady(PhantomData)
+    }
+}
+
+/// A future returned by [FromStatic::ready].
+pub struct InstanceReady<T: FromStatic + ?Sized>(PhantomData<fn() -> *mut T>);
+
+impl<T: FromStatic> Future for InstanceReady<T> {
+    type Output = NonNull<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match T::instance_ptr().ok().and_then(NonNull::new) {
+            Some(ptr) => Poll::Ready(ptr),
+            None => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T: FromStatic> fmt::Debug for InstanceReady<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InstanceReady").field(&T::name()).finish()
+    }
+}
+
+/// A set of callbacks waiting for static objects to become available.
+///
+/// Most code should use the global registry through [on_instance_ready] and
+/// [poll_instance_ready] rather than creating its own.
+#[derive(Default)]
+pub struct ReadinessRegistry {
+    /// Each entry checks whether its object is available, and if so calls its
+    /// callback and returns `true`.
+    pending: Vec<Box<dyn FnMut() -> bool + Send>>,
+}
+
+impl ReadinessRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers `callback` to be called by [ReadinessRegistry::poll] the
+    /// first time it sees that `T`'s instance is available.
+    pub fn on_ready<T: FromStatic>(&mut self, callback: impl FnOnce(NonNull<T>) + Send + 'static) {
+        let mut callback = Some(callback);
+        self.pending.push(Box::new(move || {
+            let Some(ptr) = T::instance_ptr().ok().and_then(NonNull::new) else {
+                return false;
+            };
+            if let Some(callback) = callback.take() {
+                callback(ptr);
+            }
+            true
+        }));
+    }
+
+    /// Checks whether each object with a pending callback is available, and
+    /// calls and removes the callbacks for those that are.
+    ///
+    /// This should be called regularly from the thread that should run the
+    /// callbacks, typically once per frame from a task on the main thread.
+    pub fn poll(&mut self) {
+        self.pending.retain_mut(|check| !check());
+    }
+
+    /// Returns the number of callbacks that haven't been called yet.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns whether all registered callbacks have been called.
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+static READINESS: LazyLock<Mutex<ReadinessRegistry>> = LazyLock::new(Default::default);
+
+/// Registers `callback` with the global [ReadinessRegistry] to be called the
+/// first time [poll_instance_ready] sees that `T`'s instance is available.
+///
+/// For example, to set something up once the player has loaded into the world:
+///
+/// ```rs
+/// fromsoftware_shared::on_instance_ready::<WorldChrMan>(|world_chr_man| {
+///     // ...
+/// });
+///
+/// cs_task.run_recurring(
+///     |_: &FD4TaskData| fromsoftware_shared::poll_instance_ready(),
+///     CSTaskGroupIndex::FrameBegin,
+/// )
+/// .detach();
+/// ```
+pub fn on_instance_ready<T: FromStatic>(callback: impl FnOnce(NonNull<T>) + Send + 'static) {
+    READINESS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .on_ready(callback);
+}
+
+/// Calls any callbacks registered through [on_instance_ready] whose objects
+/// have become available. This should be called once per frame from a task
+/// on the main thread.
+pub fn poll_instance_ready() {
+    // Take the callbacks out of the registry while they run so that they can
+    // register new callbacks without deadlocking.
+    let mut registry =
+        std::mem::take(&mut *READINESS.lock().unwrap_or_else(PoisonError::into_inner));
+    registry.poll();
+    READINESS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .pending
+        .append(&mut registry.pending);
 }
 
 /// Looks up instances of singleton instances by their name. Some singletons
@@ -104,6 +301,21 @@ impl<T: FromSingleton> FromStatic for T {
     }
 }
 
+/// Looks up the DLRF singleton named by `S` and returns it as a `T`.
+///
+/// This is used by the [singleton](crate::singleton) attribute macro for types
+/// that have both a DLRF name and an RVA. Those types can't implement
+/// [FromSingleton] themselves, because they need their own [FromStatic]
+/// implementation to fall back to the RVA.
+///
+/// The caller must ensure that the singleton named by `S` is actually a `T`
+/// before dereferencing the result.
+pub fn load_static_singleton<S: FromSingleton, T: FromStatic>() -> InstanceResult<*mut T> {
+    address_of::<S>()
+        .map(|nn| nn.as_ptr().cast())
+        .ok_or(InstanceError::NotFound(T::name()))
+}
+
 /// Loads a static reference to `T` from an [Rva] that points directly to its
 /// memory. Because this always assumes that the underlying object is
 /// initialized, it can only return [InstanceError::Null] if `rva` itself is 0.
@@ -134,3 +346,130 @@ pub unsafe fn load_static_indirect<T: FromStatic>(rva: Rva) -> InstanceResult<*m
             .ok_or(InstanceError::Null(T::name()))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+    use std::task::Waker;
+
+    use super::*;
+
+    /// Declares a [FromStatic] type whose instance pointer is a static that
+    /// tests can set directly.
+    macro_rules! test_static {
+        ($name:ident, $slot:ident) => {
+            static $slot: AtomicPtr<$name> = AtomicPtr::new(std::ptr::null_mut());
+
+            #[allow(dead_code)]
+            struct $name(u32);
+
+            impl FromStatic for $name {
+                fn name() -> Cow<'static, str> {
+                    Cow::Borrowed(stringify!($name))
+                }
+
+                fn instance_ptr() -> InstanceResult<*mut Self> {
+                    Ok($slot.load(Ordering::SeqCst))
+                }
+            }
+        };
+    }
+
+    fn leak<T>(value: T) -> *mut T {
+        Box::into_raw(Box::new(value))
+    }
+
+    #[test]
+    fn wait_for_instance_times_out() {
+        test_static!(Missing, MISSING);
+        let result = Missing::wait_for_instance_ptr(Duration::from_millis(10));
+        assert!(matches!(result, Err(InstanceError::Null(name)) if name == "Missing"));
+    }
+
+    #[test]
+    fn wait_for_instance_sees_value_from_another_thread() {
+        test_static!(Late, LATE);
+        let setter = thread::spawn(|| {
+            thread::sleep(Duration::from_millis(20));
+            LATE.store(leak(Late(7)), Ordering::SeqCst);
+        });
+
+        let late = unsafe { Late::wait_for_instance(Duration::from_secs(10)) }.unwrap();
+        assert_eq!(late.0, 7);
+        setter.join().unwrap();
+    }
+
+    #[test]
+    fn wait_for_instance_without_deadline() {
+        test_static!(Unbounded, UNBOUNDED);
+        let setter = thread::spawn(|| {
+            thread::sleep(Duration::from_millis(20));
+            UNBOUNDED.store(leak(Unbounded(9)), Ordering::SeqCst);
+        });
+
+        let ptr = Unbounded::wait_for_instance_ptr(Duration::MAX).unwrap();
+        assert_eq!(unsafe { ptr.as_ref() }.0, 9);
+        setter.join().unwrap();
+    }
+
+    #[test]
+    fn ready_resolves_once_available() {
+        test_static!(Eventually, EVENTUALLY);
+        let mut future = std::pin::pin!(Eventually::ready());
+        let mut cx = Context::from_waker(Waker::noop());
+        assert!(future.as_mut().poll(&mut cx).is_pending());
+        assert!(future.as_mut().poll(&mut cx).is_pending());
+
+        let ptr = leak(Eventually(3));
+        EVENTUALLY.store(ptr, Ordering::SeqCst);
+        assert_eq!(
+            future.as_mut().poll(&mut cx),
+            Poll::Ready(NonNull::new(ptr).unwrap())
+        );
+    }
+
+    #[test]
+    fn registry_fires_callbacks_once() {
+        test_static!(Registered, REGISTERED);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut registry = ReadinessRegistry::new();
+        let counter = calls.clone();
+        registry.on_ready::<Registered>(move |ptr| {
+            assert_eq!(unsafe { ptr.as_ref() }.0, 5);
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        registry.poll();
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(registry.len(), 1);
+
+        REGISTERED.store(leak(Registered(5)), Ordering::SeqCst);
+        registry.poll();
+        registry.poll();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn global_callbacks_can_register_more_callbacks() {
+        test_static!(First, FIRST);
+        test_static!(Second, SECOND);
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        on_instance_ready::<First>(|_| {
+            CALLS.fetch_add(1, Ordering::SeqCst);
+            on_instance_ready::<Second>(|_| {
+                CALLS.fetch_add(1, Ordering::SeqCst);
+            });
+        });
+
+        FIRST.store(leak(First(0)), Ordering::SeqCst);
+        poll_instance_ready();
+        assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+
+        SECOND.store(leak(Second(0)), Ordering::SeqCst);
+        poll_instance_ready();
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+    }
+}
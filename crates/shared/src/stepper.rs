@@ -5,5 +5,18 @@
 /// the explicit -1 inactive state.
 pub unsafe trait StepperStates: Copy + std::fmt::Debug + 'static {
     // Generic associated type since we can't use the count itself on FD4StepTemplateBase.
-    type StepperFnArray<StepperFn>: AsRef<[StepperFn]>;
+    type StepperFnArray<StepperFn>: AsRef<[StepperFn]> + AsMut<[StepperFn]>;
+
+    /// Returns the state with the given discriminant, or `None` if there isn't
+    /// one.
+    fn from_raw(value: i32) -> Option<Self>;
+
+    /// Returns this state's discriminant.
+    fn raw(self) -> i32;
+
+    /// Returns the index of this state's entry in the stepper function array,
+    /// or `None` for the `NotExecuting` state.
+    fn index(self) -> Option<usize> {
+        usize::try_from(self.raw()).ok()
+    }
 }
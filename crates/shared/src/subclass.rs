@@ -143,3 +143,163 @@ where
         <T as Superclass>::vmt_rva()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use pelite::pe64::Va;
+
+    use crate::rva::IMAGE;
+    use crate::{Subclass, Superclass};
+
+    #[repr(C)]
+    #[derive(Superclass)]
+    #[superclass(children(PlayerIns, EnemyIns))]
+    struct ChrIns {
+        vftable: Va,
+        handle: u32,
+    }
+
+    #[repr(C)]
+    #[derive(Subclass)]
+    struct PlayerIns {
+        chr_ins: ChrIns,
+        player_id: u32,
+    }
+
+    #[repr(C)]
+    #[derive(Subclass)]
+    struct EnemyIns {
+        chr_ins: ChrIns,
+    }
+
+    fn player_ins(class: &str) -> PlayerIns {
+        PlayerIns {
+            chr_ins: ChrIns {
+                vftable: IMAGE.vmt_va(class),
+                handle: 1,
+            },
+            player_id: 2,
+        }
+    }
+
+    fn enemy_ins() -> EnemyIns {
+        EnemyIns {
+            chr_ins: ChrIns {
+                vftable: IMAGE.vmt_va("CS::EnemyIns"),
+                handle: 3,
+            },
+        }
+    }
+
+    #[test]
+    fn is_subclass() {
+        let _guard = IMAGE.install();
+        let player = player_ins("CS::PlayerIns");
+        let enemy = enemy_ins();
+
+        assert!(player.chr_ins.is_subclass::<ChrIns>());
+        assert!(player.chr_ins.is_subclass::<PlayerIns>());
+        assert!(!player.chr_ins.is_subclass::<EnemyIns>());
+        assert!(enemy.chr_ins.is_subclass::<EnemyIns>());
+        assert!(!enemy.chr_ins.is_subclass::<PlayerIns>());
+    }
+
+    #[test]
+    fn is_subclass_of_rtti_only_class() {
+        let _guard = IMAGE.install();
+        let ghost = player_ins("CS::ReplayGhostIns");
+        assert!(ghost.chr_ins.is_subclass::<PlayerIns>());
+        assert!(ghost.chr_ins.is_subclass::<ChrIns>());
+        assert!(!ghost.chr_ins.is_subclass::<EnemyIns>());
+
+        // A class that's unrelated to ChrIns in RTTI.
+        let field = player_ins("CS::FieldIns");
+        assert!(!field.chr_ins.is_subclass::<ChrIns>());
+    }
+
+    #[test]
+    fn as_subclass() {
+        let _guard = IMAGE.install();
+        let mut player = player_ins("CS::PlayerIns");
+
+        let chr: &ChrIns = player.superclass();
+        assert_eq!(1, chr.handle);
+        assert_eq!(2, chr.as_subclass::<PlayerIns>().unwrap().player_id);
+        assert!(chr.as_subclass::<EnemyIns>().is_none());
+
+        player
+            .chr_ins
+            .as_subclass_mut::<PlayerIns>()
+            .unwrap()
+            .player_id = 4;
+        assert_eq!(4, player.player_id);
+    }
+
+    #[test]
+    fn try_from() {
+        let _guard = IMAGE.install();
+        let enemy = enemy_ins();
+
+        let result: Result<&EnemyIns, _> = (&enemy.chr_ins).try_into();
+        assert_eq!(3, result.unwrap().handle);
+
+        let result: Result<&PlayerIns, _> = (&enemy.chr_ins).try_into();
+        assert_eq!(
+            "superclass is not an instance of PlayerIns",
+            result.err().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn classname() {
+        let _guard = IMAGE.install();
+        assert_eq!(
+            Some("CS::ReplayGhostIns".to_string()),
+            player_ins("CS::ReplayGhostIns").chr_ins.classname()
+        );
+        assert_eq!(Some("CS::EnemyIns".to_string()), enemy_ins().classname());
+    }
+
+    #[test]
+    fn subclass_enum() {
+        let _guard = IMAGE.install();
+        let player = player_ins("CS::PlayerIns");
+        let enemy = enemy_ins();
+        let ghost = player_ins("CS::ReplayGhostIns");
+
+        assert!(matches!(
+            ChrInsSubclass::from(&player.chr_ins),
+            ChrInsSubclass::PlayerIns(p) if p.player_id == 2
+        ));
+        assert!(matches!(
+            ChrInsSubclass::from(&enemy),
+            ChrInsSubclass::EnemyIns(e) if e.handle == 3
+        ));
+
+        // The enum only matches exact VMTs, so classes it doesn't know about
+        // are treated as the superclass.
+        let ghost = ChrInsSubclass::from(&ghost);
+        assert!(matches!(ghost, ChrInsSubclass::ChrIns(_)));
+        assert_eq!(1, ghost.superclass().handle);
+    }
+
+    #[test]
+    fn subclass_enum_mut() {
+        let _guard = IMAGE.install();
+        let mut player = player_ins("CS::PlayerIns");
+
+        match ChrInsSubclassMut::from(&mut player.chr_ins) {
+            ChrInsSubclassMut::PlayerIns(player) => player.player_id = 5,
+            _ => panic!("expected a PlayerIns"),
+        }
+        assert_eq!(5, player.player_id);
+
+        let mut subclass = ChrInsSubclassMut::from(&mut player);
+        subclass.superclass_mut().handle = 6;
+        assert!(matches!(
+            ChrInsSubclass::from(subclass),
+            ChrInsSubclass::PlayerIns(_)
+        ));
+        assert_eq!(6, player.handle);
+    }
+}
@@ -1,13 +1,23 @@
 use std::{
     cell::UnsafeCell,
     ffi::c_void,
+    num::NonZeroU32,
+    ops::ControlFlow,
     sync::{
-        Arc,
+        Arc, LazyLock, Mutex, PoisonError,
         atomic::{AtomicBool, Ordering},
     },
+    time::{Duration, Instant},
 };
 
 use vtable_rs::VPtr;
+use windows::Win32::System::Memory::{
+    MEM_COMMIT, MEM_RESERVE, PAGE_EXECUTE_READ, PAGE_PROTECTION_FLAGS, PAGE_READWRITE,
+    VirtualAlloc, VirtualProtect,
+};
+
+pub mod executor;
+pub use executor::TaskExecutor;
 
 /// The trait shared by task implementations across FSW games.
 pub trait SharedTaskImp<TIndex, TTaskData: Send + 'static> {
@@ -16,8 +26,27 @@ pub trait SharedTaskImp<TIndex, TTaskData: Send + 'static> {
     fn register_task_internal(&self, index: TIndex, task: &RecurringTask<TTaskData>);
 }
 
+/// The data each game passes to its tasks every time they run.
+pub trait TaskData: Send + 'static {
+    /// The time that passed since the previous frame, if the game provides it.
+    fn delta_time(&self) -> Option<Duration>;
+}
+
+/// DS3 and Sekiro pass a task data structure whose layout hasn't been worked
+/// out yet, so it's represented as an opaque `usize`.
+impl TaskData for usize {
+    fn delta_time(&self) -> Option<Duration> {
+        None
+    }
+}
+
 /// An extension on each game's task implementation to allow users to easily
 /// register custom tasks as Rust closures.
+///
+/// All the tasks registered through these methods are cancelled when their
+/// handles are dropped. Call [RecurringTaskHandle::detach] to let them run
+/// to completion without holding on to the handle. See [RecurringTask] for
+/// what happens to a task once it's cancelled.
 pub trait SharedTaskImpExt<TIndex, TTaskData: Send + 'static> {
     /// Registers the given closure as a task to the games task runtime.
     fn run_recurring<T: Into<RecurringTask<TTaskData>>>(
@@ -25,6 +54,45 @@ pub trait SharedTaskImpExt<TIndex, TTaskData: Send + 'static> {
         execute: T,
         group: TIndex,
     ) -> RecurringTaskHandle<TTaskData>;
+
+    /// Runs `execute` a single time, the next time `group` runs.
+    fn run_once<F: FnOnce(&TTaskData) + Send + 'static>(
+        &self,
+        execute: F,
+        group: TIndex,
+    ) -> RecurringTaskHandle<TTaskData>;
+
+    /// Runs `execute` a single time, the first time `group` runs after `delay`
+    /// has passed.
+    ///
+    /// Time is measured by summing the game's frame delta times when
+    /// [TaskData::delta_time] provides them, so it doesn't advance while the
+    /// game is paused. Otherwise, it falls back to wall-clock time.
+    fn run_after<F: FnOnce(&TTaskData) + Send + 'static>(
+        &self,
+        delay: Duration,
+        execute: F,
+        group: TIndex,
+    ) -> RecurringTaskHandle<TTaskData>
+    where
+        TTaskData: TaskData;
+
+    /// Runs `execute` each time `group` runs for the next `frames` frames.
+    fn run_for_frames<F: FnMut(&TTaskData) + Send + 'static>(
+        &self,
+        frames: u32,
+        execute: F,
+        group: TIndex,
+    ) -> RecurringTaskHandle<TTaskData>;
+
+    /// Runs `execute` once every `frames` frames, starting the next time
+    /// `group` runs.
+    fn run_every<F: FnMut(&TTaskData) + Send + 'static>(
+        &self,
+        frames: NonZeroU32,
+        execute: F,
+        group: TIndex,
+    ) -> RecurringTaskHandle<TTaskData>;
 }
 
 impl<TIndex, TTaskData: Send + 'static, S: SharedTaskImp<TIndex, TTaskData>>
@@ -44,50 +112,227 @@ impl<TIndex, TTaskData: Send + 'static, S: SharedTaskImp<TIndex, TTaskData>>
 
         self.register_task_internal(group, task.as_ref());
 
-        RecurringTaskHandle { _task: task }
+        RecurringTaskHandle { task }
+    }
+
+    fn run_once<F: FnOnce(&TTaskData) + Send + 'static>(
+        &self,
+        execute: F,
+        group: TIndex,
+    ) -> RecurringTaskHandle<TTaskData> {
+        let mut execute = Some(execute);
+        self.run_recurring(
+            RecurringTask::with_control_flow(move |data| {
+                if let Some(execute) = execute.take() {
+                    execute(data);
+                }
+                ControlFlow::Break(())
+            }),
+            group,
+        )
+    }
+
+    fn run_after<F: FnOnce(&TTaskData) + Send + 'static>(
+        &self,
+        delay: Duration,
+        execute: F,
+        group: TIndex,
+    ) -> RecurringTaskHandle<TTaskData>
+    where
+        TTaskData: TaskData,
+    {
+        let start = Instant::now();
+        let mut elapsed = Duration::ZERO;
+        let mut execute = Some(execute);
+        self.run_recurring(
+            RecurringTask::with_control_flow(move |data: &TTaskData| {
+                match data.delta_time() {
+                    Some(delta) => elapsed += delta,
+                    None => elapsed = start.elapsed(),
+                }
+                if elapsed < delay {
+                    return ControlFlow::Continue(());
+                }
+
+                if let Some(execute) = execute.take() {
+                    execute(data);
+                }
+                ControlFlow::Break(())
+            }),
+            group,
+        )
+    }
+
+    fn run_for_frames<F: FnMut(&TTaskData) + Send + 'static>(
+        &self,
+        frames: u32,
+        mut execute: F,
+        group: TIndex,
+    ) -> RecurringTaskHandle<TTaskData> {
+        let mut remaining = frames;
+        self.run_recurring(
+            RecurringTask::with_control_flow(move |data| {
+                if remaining == 0 {
+                    return ControlFlow::Break(());
+                }
+
+                execute(data);
+                remaining -= 1;
+                if remaining == 0 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            }),
+            group,
+        )
+    }
+
+    fn run_every<F: FnMut(&TTaskData) + Send + 'static>(
+        &self,
+        frames: NonZeroU32,
+        mut execute: F,
+        group: TIndex,
+    ) -> RecurringTaskHandle<TTaskData> {
+        let mut frame = 0;
+        self.run_recurring(
+            move |data: &TTaskData| {
+                if frame == 0 {
+                    execute(data);
+                }
+                frame = (frame + 1) % frames.get();
+            },
+            group,
+        )
     }
 }
 
 /// A handle for the a task registered through `SharedTaskImpExt.run_recurring`
 /// that allows users to cancel it later using `Drop.drop`.
+///
+/// Once the handle is dropped, the task's closure won't run again. It's freed
+/// the next time the game would have run the task, on the thread that runs it.
+/// Use [RecurringTaskHandle::detach] for tasks that should run for as long as
+/// the game does.
+#[must_use = "dropping the handle cancels the task; call detach() to keep it running"]
 pub struct RecurringTaskHandle<TTaskData: Send + 'static> {
-    _task: Arc<RecurringTask<TTaskData>>,
+    task: Arc<RecurringTask<TTaskData>>,
+}
+
+impl<TTaskData: Send + 'static> RecurringTaskHandle<TTaskData> {
+    /// Cancels the task. This is equivalent to dropping the handle.
+    pub fn cancel(self) {}
+
+    /// Lets the task run forever without holding on to its handle.
+    pub fn detach(self) {
+        std::mem::forget(self);
+    }
+
+    /// Returns whether the task has been cancelled or has finished running.
+    pub fn is_cancelled(&self) -> bool {
+        self.task.is_cancelled()
+    }
 }
 
 impl<TTaskData: Send + 'static> Drop for RecurringTaskHandle<TTaskData> {
     fn drop(&mut self) {
-        self._task.cancel();
+        self.task.cancel();
     }
 }
 
+/// The Rust closure run by a [RecurringTask].
+type TaskClosure<TTaskData> = Box<dyn FnMut(&TTaskData) -> ControlFlow<()> + Send>;
+
 /// A custom task created by `fromsoftware-rs` that can masquerade as one of the
 /// game's native tasks.
 ///
 /// We assume that the subset of this structure that the games care (especially
 /// the vftable) about is the same across games. So far, we know it works on both
 /// DS3 and ER.
+///
+/// ## Cancellation
+///
+/// The games' task removal routines haven't been mapped yet, so a task can't
+/// be taken back out of its task group once it's registered. Instead, the
+/// first time the game runs a task after it's been cancelled, the task drops
+/// its closure and retires: its vftable is replaced with one whose functions
+/// do nothing and that lives in memory allocated at runtime rather than in
+/// the module that registered the task. The game keeps calling retired tasks,
+/// but that stays sound after the module is unloaded, for example when it's
+/// hot-reloaded. Each retired task keeps its own small allocation alive until
+/// the game shuts down.
 #[repr(C)]
 pub struct RecurringTask<TTaskData: Send + 'static> {
     vftable: VPtr<dyn SharedTaskBaseVmt, Self>,
     unk8: usize,
-    closure: Box<dyn FnMut(&TTaskData)>,
-    unregister_requested: AtomicBool,
+
+    /// The task's closure, until it's finished or been cancelled.
+    closure: Mutex<Option<TaskClosure<TTaskData>>>,
+    cancelled: AtomicBool,
     self_ref: UnsafeCell<Option<Arc<Self>>>,
 }
 
 impl<TTaskData: Send + 'static> RecurringTask<TTaskData> {
-    pub fn new<F: FnMut(&TTaskData) + 'static + Send>(closure: F) -> Self {
+    pub fn new<F: FnMut(&TTaskData) + 'static + Send>(mut closure: F) -> Self {
+        Self::with_control_flow(move |data| {
+            closure(data);
+            ControlFlow::Continue(())
+        })
+    }
+
+    /// Creates a task that stops running once `closure` returns
+    /// [ControlFlow::Break], as though its handle were dropped.
+    pub fn with_control_flow<F: FnMut(&TTaskData) -> ControlFlow<()> + 'static + Send>(
+        closure: F,
+    ) -> Self {
         Self {
             vftable: Default::default(),
             unk8: 0,
-            closure: Box::new(closure),
-            unregister_requested: AtomicBool::new(false),
+            closure: Mutex::new(Some(Box::new(closure))),
+            cancelled: AtomicBool::new(false),
             self_ref: UnsafeCell::new(None),
         }
     }
 
+    /// Requests that the task stop running. The closure is dropped the next
+    /// time the game would have executed the task.
     pub fn cancel(&self) {
-        self.unregister_requested.store(true, Ordering::Relaxed);
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Returns whether the task has been cancelled or has finished.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Returns whether the task still has its closure. This is `false` once
+    /// the task has observed its cancellation and retired.
+    pub fn is_active(&self) -> bool {
+        self.closure
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_some()
+    }
+
+    /// Drops the closure and points the vftable at [retired_vftable], so the
+    /// game's later calls don't reach this module's code.
+    fn retire(&mut self) {
+        let closure = self
+            .closure
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        drop(closure);
+
+        // If the vftable couldn't be created, this task keeps its own. It
+        // still does nothing once retired, but only while this module is
+        // loaded.
+        if let Some(vftable) = retired_vftable() {
+            // SAFETY: The vftable pointer is the first field of this
+            // `repr(C)` struct, and the retired vftable's functions accept
+            // any `this`.
+            unsafe { (&raw mut self.vftable).cast::<usize>().write(vftable) };
+        }
     }
 }
 
@@ -97,26 +342,76 @@ impl<TTaskData: Send + 'static> SharedTaskBaseVmt for RecurringTask<TTaskData> {
     }
 
     extern "C" fn destructor(&mut self) {
-        unimplemented!();
+        self.cancel();
+        *self
+            .closure
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+
+        // Release the reference that kept this alive on the game's behalf.
+        // This may free `self`, so it has to come last.
+        let self_ref = self.self_ref.get_mut().take();
+        drop(self_ref);
     }
 
     extern "C" fn execute(&mut self, data: *const c_void) {
-        // Run the task if cancellation wasn't requested.
-        // if !self.unregister_requested.load(Ordering::Relaxed) {
+        if !self.is_cancelled() {
+            let mut closure = self.closure.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(closure) = closure.as_mut() {
+                // SAFETY: We're declaring the type of the data in the first place.
+                if closure(unsafe { &*(data as *const TTaskData) }).is_break() {
+                    self.cancel();
+                }
+            }
+        }
 
-        // SAFETY: We're declaring the type of the data in the first place.
-        (self.closure)(unsafe { &*(data as *const TTaskData) });
+        // Free the closure and everything it captured as soon as we find out
+        // we've been cancelled, including while it was running.
+        if self.is_cancelled() {
+            self.retire();
+        }
+    }
+}
 
-        // }
+/// Machine code for `xor eax, eax; ret`, which is used for every function of
+/// [retired_vftable]. None of them need to do anything, and the ones that
+/// return a pointer return null.
+const RETIRED_FN: [u8; 3] = [0x31, 0xc0, 0xc3];
 
-        // TODO: implement the games unregister fn to properly get the task removed from the task
-        // pool instead of just not running the closure.
+/// The number of entries in [retired_vftable]. This covers more than
+/// [SharedTaskBaseVmt] in case the game calls other virtual functions of its
+/// own task base class.
+const RETIRED_VFTABLE_LEN: usize = 8;
 
-        // Drop if we got cancelled in the meanwhile.
-        // if self.unregister_requested.load(Ordering::Relaxed) {
-        //     self.self_ref.get_mut().take();
-        // }
-    }
+/// Returns the address of a vftable whose functions all do nothing, or `None`
+/// if it couldn't be created.
+///
+/// Both the vftable and its function live in a page that's allocated once and
+/// never freed, so they outlive the module that created them.
+fn retired_vftable() -> Option<usize> {
+    static VFTABLE: LazyLock<Option<usize>> = LazyLock::new(|| {
+        const FN_SIZE: usize = 0x10;
+        const SIZE: usize = FN_SIZE + RETIRED_VFTABLE_LEN * size_of::<usize>();
+
+        let page = unsafe { VirtualAlloc(None, SIZE, MEM_COMMIT | MEM_RESERVE, PAGE_READWRITE) };
+        if page.is_null() {
+            return None;
+        }
+
+        let function = page.cast::<u8>();
+        unsafe {
+            function.copy_from_nonoverlapping(RETIRED_FN.as_ptr(), RETIRED_FN.len());
+            let vftable = function.add(FN_SIZE).cast::<usize>();
+            for i in 0..RETIRED_VFTABLE_LEN {
+                vftable.add(i).write(function as usize);
+            }
+
+            let mut old = PAGE_PROTECTION_FLAGS::default();
+            VirtualProtect(page, SIZE, PAGE_EXECUTE_READ, &mut old).ok()?;
+            Some(vftable as usize)
+        }
+    });
+    *VFTABLE
 }
 
 impl<TTaskData: Send + 'static, F: FnMut(&TTaskData) + 'static + Send> From<F>
@@ -136,3 +431,205 @@ trait SharedTaskBaseVmt {
     // TODO: Make data generic once vtable-rs supports this.
     fn execute(&mut self, data: *const c_void);
 }
+
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::ptr::NonNull;
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    /// Treat the test data as the number of milliseconds since the last frame.
+    impl TaskData for u32 {
+        fn delta_time(&self) -> Option<Duration> {
+            Some(Duration::from_millis(*self as u64))
+        }
+    }
+
+    /// A stand-in for a game's task pool that just remembers the tasks
+    /// registered with it.
+    #[derive(Default)]
+    struct TaskPool(RefCell<Vec<NonNull<RecurringTask<u32>>>>);
+
+    impl SharedTaskImp<(), u32> for TaskPool {
+        fn register_task_internal(&self, _: (), task: &RecurringTask<u32>) {
+            self.0.borrow_mut().push(NonNull::from(task));
+        }
+    }
+
+    impl TaskPool {
+        fn run_frame(&self, data: u32) {
+            // Tasks may register new tasks while they run.
+            let tasks = self.0.borrow().clone();
+            for task in tasks {
+                unsafe { (*task.as_ptr()).execute(&data as *const u32 as *const c_void) };
+            }
+        }
+
+        fn len(&self) -> usize {
+            self.0.borrow().len()
+        }
+    }
+
+    impl Drop for TaskPool {
+        /// Destroys the tasks like the game does when it shuts down, so that
+        /// pools created later at the same address don't see them.
+        fn drop(&mut self) {
+            for task in self.0.take() {
+                unsafe { (*task.as_ptr()).destructor() };
+            }
+        }
+    }
+
+    #[test]
+    fn runs_until_dropped() {
+        let pool = TaskPool::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let handle = pool.run_recurring(
+            move |data: &u32| {
+                counter.fetch_add(*data as usize, Ordering::SeqCst);
+            },
+            (),
+        );
+
+        pool.run_frame(1);
+        pool.run_frame(2);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(!handle.is_cancelled());
+
+        drop(handle);
+        // The closure is still alive until the next frame.
+        assert_eq!(Arc::strong_count(&calls), 2);
+
+        pool.run_frame(4);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(Arc::strong_count(&calls), 1);
+        assert!(!unsafe { pool.0.borrow()[0].as_ref() }.is_active());
+    }
+
+    #[test]
+    fn cancelled_from_inside_closure() {
+        thread_local! {
+            static HANDLE: RefCell<Option<RecurringTaskHandle<u32>>> = const { RefCell::new(None) };
+        }
+
+        let pool = TaskPool::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let handle = pool.run_recurring(
+            move |_: &u32| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                HANDLE.with_borrow_mut(|handle| handle.take());
+            },
+            (),
+        );
+        HANDLE.set(Some(handle));
+
+        pool.run_frame(0);
+        pool.run_frame(0);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(Arc::strong_count(&calls), 1);
+    }
+
+    #[test]
+    fn destructor_releases_task() {
+        let pool = TaskPool::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let handle = pool.run_recurring(
+            move |_: &u32| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            },
+            (),
+        );
+        let task = Arc::downgrade(&handle.task);
+        drop(handle);
+
+        let shell = pool.0.borrow_mut().remove(0);
+        unsafe { (*shell.as_ptr()).destructor() };
+        assert_eq!(Arc::strong_count(&calls), 1);
+        assert!(task.upgrade().is_none());
+    }
+
+    #[test]
+    fn retires_cancelled_tasks() {
+        let pool = TaskPool::default();
+        let task = Arc::downgrade(&pool.run_once(|_| {}, ()).task);
+        let vftable = |pool: &TaskPool| unsafe { *pool.0.borrow()[0].as_ptr().cast::<usize>() };
+        let original = vftable(&pool);
+
+        pool.run_frame(0);
+        let retired = retired_vftable().unwrap();
+        assert_ne!(original, retired);
+        assert_eq!(retired, vftable(&pool));
+
+        // The task stays allocated for the game to keep calling.
+        assert!(task.upgrade().is_some());
+        for i in 0..RETIRED_VFTABLE_LEN {
+            let function: extern "C" fn(*mut c_void) -> usize =
+                unsafe { std::mem::transmute(*(retired as *const usize).add(i)) };
+            assert_eq!(0, function(std::ptr::null_mut()));
+        }
+    }
+
+    /// Registers a task that logs the data it's called with and returns the
+    /// log.
+    fn log_calls(
+        register: impl FnOnce(Box<dyn FnMut(&u32) + Send>) -> RecurringTaskHandle<u32>,
+    ) -> (RecurringTaskHandle<u32>, Arc<std::sync::Mutex<Vec<u32>>>) {
+        let log = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let inner = log.clone();
+        let handle = register(Box::new(move |data: &u32| {
+            inner.lock().unwrap().push(*data)
+        }));
+        (handle, log)
+    }
+
+    #[test]
+    fn run_once() {
+        let pool = TaskPool::default();
+        let (handle, log) = log_calls(|mut f| pool.run_once(move |data| f(data), ()));
+        for frame in 1..=3 {
+            pool.run_frame(frame);
+        }
+        assert_eq!(*log.lock().unwrap(), [1]);
+        assert!(handle.is_cancelled());
+        assert_eq!(Arc::strong_count(&log), 1);
+    }
+
+    #[test]
+    fn run_after_uses_delta_time() {
+        let pool = TaskPool::default();
+        let (handle, log) =
+            log_calls(|mut f| pool.run_after(Duration::from_millis(50), move |data| f(data), ()));
+        for delta in [16, 16, 16, 16, 16, 16] {
+            pool.run_frame(delta);
+        }
+        assert_eq!(log.lock().unwrap().len(), 1);
+        assert!(handle.is_cancelled());
+    }
+
+    #[test]
+    fn run_for_frames() {
+        let pool = TaskPool::default();
+        let (handle, log) = log_calls(|f| pool.run_for_frames(3, f, ()));
+        for frame in 1..=5 {
+            pool.run_frame(frame);
+        }
+        assert_eq!(*log.lock().unwrap(), [1, 2, 3]);
+        assert!(handle.is_cancelled());
+    }
+
+    #[test]
+    fn run_every() {
+        let pool = TaskPool::default();
+        let (handle, log) = log_calls(|f| pool.run_every(NonZeroU32::new(3).unwrap(), f, ()));
+        for frame in 1..=7 {
+            pool.run_frame(frame);
+        }
+        assert_eq!(*log.lock().unwrap(), [1, 4, 7]);
+        assert!(!handle.is_cancelled());
+    }
+}
@@ -0,0 +1,397 @@
+use std::{
+    cell::Cell,
+    fmt,
+    future::Future,
+    pin::Pin,
+    ptr::NonNull,
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use crate::{FromStatic, TaskData};
+
+type BoxedFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+thread_local! {
+    /// The frame that's currently being run by a [TaskExecutor] on this
+    /// thread, if any.
+    static CURRENT_FRAME: Cell<Option<Frame>> = const { Cell::new(None) };
+}
+
+/// Information about the frame a [TaskExecutor] is currently running.
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    /// The number of frames this executor has run before this one.
+    index: u64,
+
+    /// The time since the previous frame, if the game provided it.
+    delta_time: Option<Duration>,
+}
+
+impl Frame {
+    /// Returns the frame that's currently being run.
+    ///
+    /// ## Panics
+    ///
+    /// If this isn't called from within a future being polled by a
+    /// [TaskExecutor].
+    fn current() -> Frame {
+        CURRENT_FRAME.get().expect(
+            "frame futures can only be awaited from within a future spawned on a TaskExecutor",
+        )
+    }
+}
+
+/// A single-threaded `async` executor that polls its futures once per frame
+/// from one of the game's task groups.
+///
+/// The executor itself is cheap to clone and can be shared across threads, so
+/// futures can be spawned from anywhere. They're only ever polled from the
+/// task group that drives the executor, though, which is usually on the main
+/// thread.
+///
+/// ```rs
+/// let executor = TaskExecutor::new();
+/// cs_task
+///     .run_recurring(executor.driver(), CSTaskGroupIndex::FrameBegin)
+///     .detach();
+///
+/// executor.spawn(async {
+///     let world_chr_man = wait_for_instance::<WorldChrMan>().await;
+///     sleep(Duration::from_secs(3)).await;
+///     // ...
+/// });
+/// ```
+///
+/// Because every future is polled every frame, there's no need for futures to
+/// wake the executor. Futures that aren't provided by this module work as long
+/// as they don't rely on a separate runtime.
+#[derive(Clone, Default)]
+pub struct TaskExecutor {
+    inner: Arc<ExecutorInner>,
+}
+
+#[derive(Default)]
+struct ExecutorInner {
+    /// Futures that are waiting to be polled on the next frame.
+    pending: Mutex<Vec<BoxedFuture>>,
+
+    /// The number of frames this executor has run.
+    frames: Mutex<u64>,
+}
+
+impl TaskExecutor {
+    /// Creates an executor with no futures. It won't do anything until
+    /// [TaskExecutor::driver] is registered as a task.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Spawns `future` on this executor. It's first polled on the next frame.
+    pub fn spawn(&self, future: impl Future<Output = ()> + Send + 'static) {
+        self.inner
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(Box::pin(future));
+    }
+
+    /// Returns the number of futures that haven't completed yet.
+    ///
+    /// Futures that are being polled when this is called aren't counted.
+    pub fn len(&self) -> usize {
+        self.inner
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Returns whether all spawned futures have completed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Polls each pending future once. This is normally called through
+    /// [TaskExecutor::driver], but can be called directly from an existing
+    /// task.
+    pub fn run_frame<TTaskData: TaskData>(&self, data: &TTaskData) {
+        let index = {
+            let mut frames = self
+                .inner
+                .frames
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            *frames += 1;
+            *frames - 1
+        };
+
+        // Take the futures out while they run so they can spawn new futures
+        // without deadlocking. Those are first polled on the next frame.
+        let mut futures = std::mem::take(
+            &mut *self
+                .inner
+                .pending
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+
+        let previous = CURRENT_FRAME.replace(Some(Frame {
+            index,
+            delta_time: data.delta_time(),
+        }));
+        let mut cx = Context::from_waker(Waker::noop());
+        futures.retain_mut(|future| future.as_mut().poll(&mut cx).is_pending());
+        CURRENT_FRAME.set(previous);
+
+        self.inner
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .splice(0..0, futures);
+    }
+
+    /// Returns a closure that runs a frame of this executor each time it's
+    /// called, suitable for passing to
+    /// [SharedTaskImpExt::run_recurring](crate::SharedTaskImpExt::run_recurring).
+    pub fn driver<TTaskData: TaskData>(&self) -> impl FnMut(&TTaskData) + Send + 'static {
+        let executor = self.clone();
+        move |data| executor.run_frame(data)
+    }
+}
+
+impl fmt::Debug for TaskExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskExecutor")
+            .field("pending", &self.len())
+            .finish()
+    }
+}
+
+/// Returns a future that completes on the next frame.
+pub fn next_frame() -> NextFrame {
+    NextFrame { started: None }
+}
+
+/// A future returned by [next_frame].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct NextFrame {
+    started: Option<u64>,
+}
+
+impl Future for NextFrame {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+        let frame = Frame::current().index;
+        match self.started {
+            Some(started) if started != frame => Poll::Ready(()),
+            Some(_) => Poll::Pending,
+            None => {
+                self.started = Some(frame);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Returns a future that completes on the first frame after `duration` has
+/// passed.
+///
+/// Like [SharedTaskImpExt::run_after](crate::SharedTaskImpExt::run_after),
+/// this uses the game's frame delta times when the task data provides them and
+/// wall-clock time otherwise.
+///
+/// The game crates convert their `FD4Time` timers into [Duration]s, so those
+/// can be passed here directly.
+pub fn sleep(duration: impl Into<Duration>) -> Sleep {
+    Sleep {
+        duration: duration.into(),
+        state: None,
+    }
+}
+
+/// A future returned by [sleep].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Sleep {
+    duration: Duration,
+
+    /// The last frame the sleep was polled on, when it started in wall-clock
+    /// time, and how much game time has elapsed since.
+    state: Option<(u64, Instant, Duration)>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+        let frame = Frame::current();
+        let duration = self.duration;
+        let Some((last_frame, start, elapsed)) = self.state.as_mut() else {
+            self.state = Some((frame.index, Instant::now(), Duration::ZERO));
+            return Poll::Pending;
+        };
+        if *last_frame == frame.index {
+            return Poll::Pending;
+        }
+        *last_frame = frame.index;
+
+        let elapsed = match frame.delta_time {
+            Some(delta) => {
+                *elapsed += delta;
+                *elapsed
+            }
+            None => start.elapsed(),
+        };
+        if elapsed >= duration {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Returns a future that completes the first time `predicate` returns `true`.
+/// The predicate is checked once per frame, starting with the frame the future
+/// is first polled on.
+pub fn wait_until<F: FnMut() -> bool>(predicate: F) -> WaitUntil<F> {
+    WaitUntil(predicate)
+}
+
+/// A future returned by [wait_until].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct WaitUntil<F>(F);
+
+impl<F: FnMut() -> bool + Unpin> Future for WaitUntil<F> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+        if (self.0)() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Returns a future that completes once the single global instance of `T` is
+/// available. This is the same as [FromStatic::ready].
+pub fn wait_for_instance<T: FromStatic>() -> impl Future<Output = NonNull<T>> {
+    T::ready()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// Runs `frames` frames of `executor`, each `delta` milliseconds long.
+    fn run_frames(executor: &TaskExecutor, frames: usize, delta: u32) {
+        for _ in 0..frames {
+            executor.run_frame(&delta);
+        }
+    }
+
+    #[test]
+    fn runs_futures_to_completion() {
+        let executor = TaskExecutor::new();
+        let steps = Arc::new(AtomicUsize::new(0));
+        let inner = steps.clone();
+        executor.spawn(async move {
+            inner.fetch_add(1, Ordering::SeqCst);
+            next_frame().await;
+            inner.fetch_add(1, Ordering::SeqCst);
+            next_frame().await;
+            inner.fetch_add(1, Ordering::SeqCst);
+        });
+
+        assert_eq!(steps.load(Ordering::SeqCst), 0);
+        run_frames(&executor, 1, 16);
+        assert_eq!(steps.load(Ordering::SeqCst), 1);
+        run_frames(&executor, 1, 16);
+        assert_eq!(steps.load(Ordering::SeqCst), 2);
+        run_frames(&executor, 1, 16);
+        assert_eq!(steps.load(Ordering::SeqCst), 3);
+        assert!(executor.is_empty());
+    }
+
+    #[test]
+    fn sleep_uses_delta_time() {
+        let executor = TaskExecutor::new();
+        let done = Arc::new(AtomicUsize::new(0));
+        let inner = done.clone();
+        executor.spawn(async move {
+            sleep(Duration::from_millis(100)).await;
+            inner.store(1, Ordering::SeqCst);
+        });
+
+        // The frame the sleep starts on doesn't count towards it.
+        run_frames(&executor, 4, 25);
+        assert_eq!(done.load(Ordering::SeqCst), 0);
+        run_frames(&executor, 1, 25);
+        assert_eq!(done.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn wait_until_and_spawn_from_future() {
+        static FLAG: AtomicUsize = AtomicUsize::new(0);
+
+        let executor = TaskExecutor::new();
+        let spawner = executor.clone();
+        executor.spawn(async move {
+            wait_until(|| FLAG.load(Ordering::SeqCst) == 1).await;
+            spawner.spawn(async {
+                FLAG.store(2, Ordering::SeqCst);
+            });
+        });
+
+        run_frames(&executor, 3, 16);
+        assert_eq!(executor.len(), 1);
+        FLAG.store(1, Ordering::SeqCst);
+        run_frames(&executor, 1, 16);
+        assert_eq!(FLAG.load(Ordering::SeqCst), 1);
+        run_frames(&executor, 1, 16);
+        assert_eq!(FLAG.load(Ordering::SeqCst), 2);
+        assert!(executor.is_empty());
+    }
+
+    #[test]
+    fn wait_for_instance_resolves() {
+        static SLOT: AtomicPtr<Instance> = AtomicPtr::new(std::ptr::null_mut());
+        struct Instance(u32);
+
+        impl FromStatic for Instance {
+            fn name() -> std::borrow::Cow<'static, str> {
+                "Instance".into()
+            }
+
+            fn instance_ptr() -> crate::InstanceResult<*mut Self> {
+                Ok(SLOT.load(Ordering::SeqCst))
+            }
+        }
+
+        let executor = TaskExecutor::new();
+        let value = Arc::new(AtomicUsize::new(0));
+        let inner = value.clone();
+        executor.spawn(async move {
+            let instance = wait_for_instance::<Instance>().await;
+            inner.store(unsafe { instance.as_ref() }.0 as usize, Ordering::SeqCst);
+        });
+
+        run_frames(&executor, 2, 16);
+        SLOT.store(Box::into_raw(Box::new(Instance(42))), Ordering::SeqCst);
+        run_frames(&executor, 1, 16);
+        assert_eq!(value.load(Ordering::SeqCst), 42);
+    }
+
+    #[test]
+    #[should_panic]
+    fn frame_futures_require_executor() {
+        let mut cx = Context::from_waker(Waker::noop());
+        let _ = std::pin::pin!(next_frame()).poll(&mut cx);
+    }
+}
@@ -0,0 +1,275 @@
+use std::alloc::Layout;
+use std::any::{TypeId, type_name};
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard, PoisonError};
+use std::{fmt, io, marker::PhantomData, ptr::NonNull};
+
+use crate::{AllocError, GameAllocator};
+
+/// A [`GameAllocator`] that forwards to `A` and keeps a record of every
+/// allocation that hasn't been freed yet.
+///
+/// This is meant for finding leaks in long-running mods: swap the allocator
+/// parameter of the types in question for `TrackingAllocator<A>`, let the game
+/// run for a while, and then look at [`TrackingAllocator::live_allocations`] or
+/// [`TrackingAllocator::dump`].
+///
+/// Memory is only *compatible with* `TrackingAllocator<A>` if it was allocated
+/// by it. Freeing memory that was allocated by `A` directly is still sound, but
+/// will show up as an untracked free in [`TrackingAllocator::untracked_frees`].
+///
+/// Backtraces are expensive to capture, so they're only recorded while
+/// [`TrackingAllocator::set_capture_backtraces`] is enabled.
+pub struct TrackingAllocator<A: GameAllocator>(PhantomData<A>);
+
+/// A single allocation that [`TrackingAllocator`] has handed out and that
+/// hasn't been freed yet.
+#[derive(Clone, Debug)]
+pub struct LiveAllocation {
+    /// The address of the allocation.
+    pub address: usize,
+
+    /// The layout that was requested for the allocation.
+    pub layout: Layout,
+
+    /// A number that increases with each allocation, shared between all
+    /// tracking allocators. Lower numbers are older allocations.
+    pub sequence: u64,
+
+    /// Where the allocation was made, if backtraces were being captured at the
+    /// time.
+    pub backtrace: Option<Arc<Backtrace>>,
+}
+
+struct Record {
+    allocator: TypeId,
+    allocation: LiveAllocation,
+}
+
+#[derive(Default)]
+struct Registry {
+    live: HashMap<usize, Record>,
+    untracked_frees: HashMap<TypeId, usize>,
+}
+
+// Statics can't be generic, so all tracking allocators share one registry and
+// each record notes which allocator it belongs to.
+static REGISTRY: LazyLock<Mutex<Registry>> = LazyLock::new(Default::default);
+static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+static CAPTURE_BACKTRACES: AtomicBool = AtomicBool::new(false);
+
+fn registry() -> MutexGuard<'static, Registry> {
+    REGISTRY.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl<A: GameAllocator + 'static> TrackingAllocator<A> {
+    /// Enables or disables capturing a backtrace for each new allocation. This
+    /// applies to all tracking allocators.
+    pub fn set_capture_backtraces(enabled: bool) {
+        CAPTURE_BACKTRACES.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Returns every allocation made by this allocator that hasn't been freed
+    /// yet, oldest first.
+    pub fn live_allocations() -> Vec<LiveAllocation> {
+        let mut result: Vec<_> = registry()
+            .live
+            .values()
+            .filter(|record| record.allocator == TypeId::of::<A>())
+            .map(|record| record.allocation.clone())
+            .collect();
+        result.sort_by_key(|allocation| allocation.sequence);
+        result
+    }
+
+    /// Returns the number of allocations made by this allocator that haven't
+    /// been freed yet.
+    pub fn live_count() -> usize {
+        registry()
+            .live
+            .values()
+            .filter(|record| record.allocator == TypeId::of::<A>())
+            .count()
+    }
+
+    /// Returns the total number of bytes requested by allocations made by this
+    /// allocator that haven't been freed yet.
+    pub fn live_bytes() -> usize {
+        registry()
+            .live
+            .values()
+            .filter(|record| record.allocator == TypeId::of::<A>())
+            .map(|record| record.allocation.layout.size())
+            .sum()
+    }
+
+    /// Returns the number of times this allocator was asked to free memory
+    /// that it has no record of allocating.
+    pub fn untracked_frees() -> usize {
+        registry()
+            .untracked_frees
+            .get(&TypeId::of::<A>())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Writes a human-readable report of [`Self::live_allocations`] to
+    /// `writer`.
+    pub fn dump(mut writer: impl io::Write) -> io::Result<()> {
+        let allocations = Self::live_allocations();
+        writeln!(
+            writer,
+            "{}: {} live allocations, {} bytes",
+            type_name::<Self>(),
+            allocations.len(),
+            allocations.iter().map(|a| a.layout.size()).sum::<usize>(),
+        )?;
+        for allocation in allocations {
+            writeln!(writer, "{allocation}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<A: GameAllocator + 'static> GameAllocator for TrackingAllocator<A> {
+    fn allocate(layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let ptr = A::allocate(layout)?;
+        let backtrace = CAPTURE_BACKTRACES
+            .load(Ordering::Relaxed)
+            .then(|| Arc::new(Backtrace::force_capture()));
+
+        let address = ptr.cast::<u8>().as_ptr() as usize;
+        let allocation = LiveAllocation {
+            address,
+            layout,
+            sequence: SEQUENCE.fetch_add(1, Ordering::Relaxed),
+            backtrace,
+        };
+        registry().live.insert(
+            address,
+            Record {
+                allocator: TypeId::of::<A>(),
+                allocation,
+            },
+        );
+        Ok(ptr)
+    }
+
+    unsafe fn deallocate(ptr: NonNull<u8>, layout: Layout) {
+        {
+            let mut registry = registry();
+            let address = ptr.as_ptr() as usize;
+            let tracked = registry
+                .live
+                .get(&address)
+                .is_some_and(|record| record.allocator == TypeId::of::<A>());
+            if tracked {
+                registry.live.remove(&address);
+            } else {
+                *registry
+                    .untracked_frees
+                    .entry(TypeId::of::<A>())
+                    .or_default() += 1;
+            }
+        }
+        unsafe { A::deallocate(ptr, layout) };
+    }
+}
+
+impl fmt::Display for LiveAllocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{} 0x{:x}: {} bytes (align {})",
+            self.sequence,
+            self.address,
+            self.layout.size(),
+            self.layout.align()
+        )?;
+        match &self.backtrace {
+            Some(backtrace) if backtrace.status() == BacktraceStatus::Captured => {
+                write!(f, "\n{backtrace}")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::alloc::{GlobalAlloc, System};
+
+    use super::*;
+    use crate::OwnedPtr;
+
+    // Each test uses its own inner allocator type so that tests running in
+    // parallel don't see each other's allocations.
+    macro_rules! system_allocator {
+        ($name:ident) => {
+            struct $name;
+
+            impl GameAllocator for $name {
+                fn allocate(layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                    let ptr = NonNull::new(unsafe { System.alloc(layout) }).ok_or(AllocError)?;
+                    Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+                }
+
+                unsafe fn deallocate(ptr: NonNull<u8>, layout: Layout) {
+                    unsafe { System.dealloc(ptr.as_ptr(), layout) };
+                }
+            }
+        };
+    }
+
+    #[test]
+    fn tracks_live_allocations() {
+        system_allocator!(Inner);
+        type Tracking = TrackingAllocator<Inner>;
+
+        let a = OwnedPtr::<u64, Tracking>::new(1);
+        let b = OwnedPtr::<[u8; 100], Tracking>::new([0; 100]);
+        assert_eq!(2, Tracking::live_count());
+        assert_eq!(108, Tracking::live_bytes());
+
+        let live = Tracking::live_allocations();
+        assert_eq!(a.as_ptr() as usize, live[0].address);
+        assert_eq!(b.as_ptr() as usize, live[1].address);
+        assert_eq!(Layout::new::<[u8; 100]>(), live[1].layout);
+
+        drop(a);
+        assert_eq!(1, Tracking::live_count());
+        drop(b);
+        assert_eq!(0, Tracking::live_count());
+        assert_eq!(0, Tracking::untracked_frees());
+    }
+
+    #[test]
+    fn counts_untracked_frees() {
+        system_allocator!(Inner);
+        type Tracking = TrackingAllocator<Inner>;
+
+        let layout = Layout::new::<u32>();
+        let ptr = Inner::allocate(layout).unwrap();
+        unsafe { Tracking::deallocate(ptr.cast(), layout) };
+        assert_eq!(1, Tracking::untracked_frees());
+    }
+
+    #[test]
+    fn dump_lists_allocations() {
+        system_allocator!(Inner);
+        type Tracking = TrackingAllocator<Inner>;
+
+        let ptr = OwnedPtr::<u32, Tracking>::new(1);
+        let mut output = Vec::new();
+        Tracking::dump(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("1 live allocations, 4 bytes"), "{output}");
+        assert!(
+            output.contains(&format!("0x{:x}: 4 bytes (align 4)", ptr.as_ptr() as usize)),
+            "{output}"
+        );
+    }
+}
@@ -0,0 +1,366 @@
+use std::{
+    ffi::c_void,
+    mem,
+    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use pelite::pe64::Va;
+use windows::Win32::System::Memory::{PAGE_PROTECTION_FLAGS, PAGE_READWRITE, VirtualProtect};
+
+use crate::Superclass;
+
+/// A hook that replaces a single entry of a virtual method table with a Rust
+/// function, either for every instance of a class or for a single instance.
+///
+/// `F` is the type of the entry, which must be a function pointer such as
+/// `extern "C" fn(&mut CSEzTask, &FD4TaskData)`. The replacement is declared
+/// with [vmt_hook_fn], which lets it call the entry it replaced:
+///
+/// ```rs
+/// vmt_hook_fn! {
+///     static EXECUTE = extern "C" fn(task: &mut FD4TaskBase, data: &FD4TaskData) {
+///         // ...
+///         EXECUTE.original()(task, data)
+///     };
+/// }
+///
+/// unsafe { VmtHook::class::<CSEzTask>(2, &EXECUTE) }?.detach();
+/// ```
+///
+/// Unlike inline detours, these hooks don't modify any code, so they aren't
+/// affected by Arxan's code restoration. The original entry is restored when
+/// the hook is dropped. If several hooks replace the same entry, they must be
+/// dropped in the reverse order they were created.
+pub struct VmtHook<F: Copy + 'static> {
+    /// The VMT entry that's been replaced.
+    slot: NonNull<F>,
+
+    /// The function that was in [Self::slot] before it was hooked.
+    original: F,
+
+    /// For instance hooks, the instance's vftable pointer and the VMT it
+    /// pointed to before it was hooked.
+    instance: Option<(NonNull<Va>, Va)>,
+}
+
+// Safety: The hook only refers to VMTs, which are never deallocated, and the
+// instance whose safety is vouched for by the caller of [VmtHook::instance].
+unsafe impl<F: Copy + Send + 'static> Send for VmtHook<F> {}
+unsafe impl<F: Copy + Sync + 'static> Sync for VmtHook<F> {}
+
+impl<F: Copy + 'static> VmtHook<F> {
+    /// Replaces entry `index` of `T`'s VMT with `hook`. This affects every
+    /// instance of `T`, including ones that already exist, but not instances
+    /// of `T`'s subclasses.
+    ///
+    /// ## Safety
+    ///
+    /// The caller must ensure that `T`'s VMT has an entry at `index` whose
+    /// type is `F`, and that no other thread calls that entry while it's being
+    /// replaced.
+    pub unsafe fn class<T: Superclass>(
+        index: usize,
+        hook: &'static VmtHookFn<F>,
+    ) -> windows::core::Result<Self> {
+        unsafe { Self::at(T::vmt_va(), index, hook) }
+    }
+
+    /// Replaces entry `index` of the VMT at `vmt` with `hook`. This is
+    /// [VmtHook::class] for classes that aren't represented by a
+    /// [Superclass].
+    ///
+    /// ## Safety
+    ///
+    /// The caller must ensure that `vmt` is a VMT with an entry at `index`
+    /// whose type is `F`, and that no other thread calls that entry while it's
+    /// being replaced.
+    pub unsafe fn at(
+        vmt: Va,
+        index: usize,
+        hook: &'static VmtHookFn<F>,
+    ) -> windows::core::Result<Self> {
+        let slot = NonNull::new((vmt as *mut F).wrapping_add(index)).expect("VMT is null");
+        let original = unsafe { slot.read() };
+        hook.set_original(original);
+        unsafe { write_protected(slot, hook.replacement) }?;
+        Ok(Self {
+            slot,
+            original,
+            instance: None,
+        })
+    }
+
+    /// Replaces entry `index` of `instance`'s VMT with `hook`, without
+    /// affecting any other instances.
+    ///
+    /// This copies the first `len` entries of the instance's current VMT,
+    /// along with the RTTI locator that precedes them, and points `instance`
+    /// at the copy. The copy is leaked, since the game may still be calling
+    /// through it when the hook is dropped.
+    ///
+    /// Since the copy isn't part of the executable, [Superclass::classname]
+    /// returns `None` for hooked instances. [Superclass::is_subclass] and
+    /// casts still work, since they only need the RTTI locator.
+    ///
+    /// ## Safety
+    ///
+    /// The caller must ensure that:
+    ///
+    /// * `instance`'s VMT has at least `len` entries.
+    /// * `index` is less than `len` and the entry's type is `F`.
+    /// * `instance` outlives the hook. When the hook is dropped, it restores
+    ///   the instance's original vftable pointer.
+    pub unsafe fn instance<T: Superclass>(
+        instance: &mut T,
+        len: usize,
+        index: usize,
+        hook: &'static VmtHookFn<F>,
+    ) -> Self {
+        assert!(
+            index < len,
+            "VMT index {index} out of bounds for length {len}"
+        );
+
+        let vftable = NonNull::from(&mut *instance).cast::<Va>();
+        let vmt = instance.vmt();
+
+        // Include the complete object locator in the copy so
+        // Superclass::is_subclass still works for the hooked instance.
+        let source = unsafe { (vmt as *const Va).sub(1) };
+        let copy = unsafe { std::slice::from_raw_parts(source, len + 1) }
+            .to_vec()
+            .leak();
+        let copy_vmt = NonNull::from(&mut copy[1]).cast::<F>();
+        let slot = unsafe { copy_vmt.add(index) };
+        let original = unsafe { slot.read() };
+        hook.set_original(original);
+        unsafe {
+            slot.write(hook.replacement);
+            vftable.write(copy_vmt.as_ptr() as Va);
+        }
+
+        Self {
+            slot,
+            original,
+            instance: Some((vftable, vmt)),
+        }
+    }
+
+    /// Returns the function that was in the VMT before it was hooked.
+    pub fn original(&self) -> F {
+        self.original
+    }
+
+    /// Leaves the hook in place for as long as the game runs.
+    pub fn detach(self) {
+        mem::forget(self);
+    }
+}
+
+impl<F: Copy + 'static> Drop for VmtHook<F> {
+    fn drop(&mut self) {
+        match self.instance {
+            // The copied VMT is leaked, so it's enough to point the instance
+            // back at the original.
+            Some((vftable, vmt)) => unsafe { vftable.write(vmt) },
+            None => {
+                // There's no reasonable way to recover if this fails, and
+                // leaving the hook in place is safe.
+                if let Err(e) = unsafe { write_protected(self.slot, self.original) } {
+                    tracing::warn!(
+                        "Couldn't restore VMT entry at {:p}, leaving it hooked: {e}",
+                        self.slot
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// A function that replaces a VMT entry, along with the entry it replaced.
+///
+/// This is usually declared with [vmt_hook_fn] and passed to [VmtHook]'s
+/// constructors, which record the entry they replace so the replacement can
+/// call it through [VmtHookFn::original]. Since there's only one original per
+/// `VmtHookFn`, each VMT entry that's hooked needs its own.
+pub struct VmtHookFn<F: Copy + 'static> {
+    replacement: F,
+    original: AtomicUsize,
+}
+
+impl<F: Copy + 'static> VmtHookFn<F> {
+    /// Creates a hook function that runs `replacement`.
+    pub const fn new(replacement: F) -> Self {
+        const { assert!(mem::size_of::<F>() == mem::size_of::<usize>()) };
+        Self {
+            replacement,
+            original: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the function that replaces the VMT entry.
+    pub fn replacement(&self) -> F {
+        self.replacement
+    }
+
+    /// Returns the VMT entry that was replaced by the last [VmtHook] created
+    /// with this.
+    ///
+    /// ## Panics
+    ///
+    /// If no [VmtHook] has been created with this yet.
+    pub fn original(&self) -> F {
+        let original = self.original.load(Ordering::Acquire);
+        assert!(original != 0, "VMT hook function was never installed");
+        unsafe { mem::transmute_copy(&original) }
+    }
+
+    fn set_original(&self, original: F) {
+        let original: usize = unsafe { mem::transmute_copy(&original) };
+        self.original.store(original, Ordering::Release);
+    }
+}
+
+// Safety: `F` is a function pointer, which can be shared between threads.
+unsafe impl<F: Copy + 'static> Sync for VmtHookFn<F> {}
+
+/// Declares a `static` [VmtHookFn] whose replacement is the given function
+/// body. The body can refer to the static by name to call the original entry.
+///
+/// ```rs
+/// vmt_hook_fn! {
+///     static GET_HANDLE = extern "C" fn(chr: &ChrIns) -> u32 {
+///         GET_HANDLE.original()(chr) + 1
+///     };
+/// }
+/// ```
+#[macro_export]
+macro_rules! vmt_hook_fn {
+    ($(
+        $(#[$meta:meta])*
+        $vis:vis static $name:ident = extern "C" fn($($arg:ident: $ty:ty),* $(,)?)
+            $(-> $ret:ty)? $body:block;
+    )*) => {$(
+        $(#[$meta])*
+        $vis static $name: $crate::VmtHookFn<extern "C" fn($($ty),*) $(-> $ret)?> =
+            $crate::VmtHookFn::new({
+                extern "C" fn replacement($($arg: $ty),*) $(-> $ret)? $body
+                replacement
+            });
+    )*};
+}
+
+/// Writes `value` to `slot`, temporarily making it writable if necessary.
+unsafe fn write_protected<F>(slot: NonNull<F>, value: F) -> windows::core::Result<()> {
+    let address = slot.as_ptr() as *const c_void;
+    let mut old = PAGE_PROTECTION_FLAGS::default();
+    unsafe {
+        VirtualProtect(address, mem::size_of::<F>(), PAGE_READWRITE, &mut old)?;
+        slot.write(value);
+        VirtualProtect(address, mem::size_of::<F>(), old, &mut old)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pelite::pe64::Va;
+
+    use super::*;
+    use crate::rva::IMAGE;
+
+    #[repr(C)]
+    #[derive(Superclass)]
+    struct ChrIns {
+        vftable: Va,
+        handle: u32,
+    }
+
+    type Entry = extern "C" fn(&ChrIns) -> u32;
+
+    vmt_hook_fn! {
+        static CLASS_HOOK = extern "C" fn(chr: &ChrIns) -> u32 {
+            chr.handle + 100
+        };
+
+        static INSTANCE_HOOK = extern "C" fn(chr: &ChrIns) -> u32 {
+            chr.handle + 100
+        };
+
+        static CALLS_ORIGINAL = extern "C" fn(chr: &ChrIns) -> u32 {
+            CALLS_ORIGINAL.original()(chr) + 1
+        };
+    }
+
+    extern "C" fn original(chr: &ChrIns) -> u32 {
+        chr.handle * 10
+    }
+
+    fn entry(vmt: Va, index: usize) -> Va {
+        unsafe { *(vmt as *const Va).add(index) }
+    }
+
+    #[test]
+    fn class_hook() {
+        // Use a class that isn't hooked by other tests, since they may run in
+        // parallel.
+        let _guard = IMAGE.install();
+        let vmt = IMAGE.vmt_va("CS::FieldIns");
+        let before = entry(vmt, 1);
+
+        let hook = unsafe { VmtHook::at(vmt, 1, &CLASS_HOOK) }.unwrap();
+        assert_eq!(entry(vmt, 1), CLASS_HOOK.replacement() as usize as Va);
+        assert_eq!(hook.original() as usize as Va, before);
+        assert_eq!(CLASS_HOOK.original() as usize as Va, before);
+
+        drop(hook);
+        assert_eq!(entry(vmt, 1), before);
+    }
+
+    #[test]
+    fn instance_hook() {
+        let _guard = IMAGE.install();
+        let vmt = IMAGE.vmt_va("CS::ChrIns");
+        let mut chr = ChrIns {
+            vftable: vmt,
+            handle: 1,
+        };
+        let other = ChrIns {
+            vftable: vmt,
+            handle: 2,
+        };
+        let before = entry(vmt, 2);
+
+        let hook = unsafe { VmtHook::instance(&mut chr, 4, 2, &INSTANCE_HOOK) };
+        assert_ne!(chr.vmt(), vmt);
+        assert_eq!(hook.original() as usize as Va, before);
+        assert_eq!(entry(vmt, 2), before);
+        assert_eq!(entry(chr.vmt(), 0), entry(vmt, 0));
+        assert!(chr.is_subclass::<ChrIns>());
+
+        let call: Entry = unsafe { mem::transmute(entry(chr.vmt(), 2)) };
+        assert_eq!(call(&chr), 101);
+        assert_eq!(other.vmt(), vmt);
+
+        drop(hook);
+        assert_eq!(chr.vmt(), vmt);
+    }
+
+    #[test]
+    fn call_original() {
+        // A VMT outside the image, preceded by a null RTTI locator, whose
+        // entries can actually be called.
+        let table = [0, original as Entry as usize as Va];
+        let mut chr = ChrIns {
+            vftable: &table[1] as *const Va as Va,
+            handle: 2,
+        };
+
+        let hook = unsafe { VmtHook::instance(&mut chr, 1, 0, &CALLS_ORIGINAL) };
+        let call: Entry = unsafe { mem::transmute(entry(chr.vmt(), 0)) };
+        assert_eq!(call(&chr), 21);
+
+        drop(hook);
+        assert_eq!(chr.vmt(), &table[1] as *const Va as Va);
+    }
+}
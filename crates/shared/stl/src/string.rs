@@ -1,6 +1,7 @@
 use crate::allocator::Allocator;
 use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
 use std::mem::ManuallyDrop;
 use std::ptr::NonNull;
 
@@ -23,6 +24,13 @@ use std::ptr::NonNull;
 /// └──────────────┴───────┴─────────┴───────────────────┘
 ///```
 ///
+/// # Layout
+///
+/// MSVC 2012 stores the allocator after the string's data, while MSVC 2015
+/// and later store it in front. `L` picks between them with [Msvc2015] (the
+/// default) or [Msvc2012], so that crates targeting different compilers can
+/// be built together.
+///
 /// # References
 ///
 /// - [cppreference - `std::basic_string`]
@@ -33,18 +41,84 @@ use std::ptr::NonNull;
 /// [MSVC STL source - `xstring`]: https://github.com/microsoft/STL/blob/main/stl/inc/xstring
 /// [Raymond Chen's breakdown of `std::basic_string`]: https://devblogs.microsoft.com/oldnewthing/20230803-00/?p=108532
 #[repr(C)]
-pub struct BasicString<C, A>
+pub struct BasicString<C, A, L = Msvc2015>
 where
     C: CodeUnit,
     A: Allocator,
+    L: StringLayout,
 {
-    #[cfg(any(not(feature = "msvc2012"), feature = "msvc2015"))]
-    pub allocator: A,
+    leading_allocator: L::Leading<A>,
     buffer: StringBuffer<C>,
     size: usize,
     capacity: usize,
-    #[cfg(all(feature = "msvc2012", not(feature = "msvc2015")))]
-    pub allocator: A,
+    trailing_allocator: L::Trailing<A>,
+}
+
+/// Where a [BasicString] stores its allocator. This is implemented by
+/// [Msvc2015] and [Msvc2012].
+///
+/// Exactly one of [Self::Leading] and [Self::Trailing] is the allocator, and
+/// the other is zero-sized.
+pub trait StringLayout: 'static {
+    /// The field in front of the string's data.
+    type Leading<A>;
+
+    /// The field after the string's data.
+    type Trailing<A>;
+
+    /// Splits `allocator` into the string's two allocator fields.
+    fn place<A>(allocator: A) -> (Self::Leading<A>, Self::Trailing<A>);
+
+    /// Returns the allocator stored in one of the two fields.
+    fn allocator<'a, A>(leading: &'a Self::Leading<A>, trailing: &'a Self::Trailing<A>) -> &'a A;
+
+    /// Returns the allocator stored in one of the two fields.
+    fn allocator_mut<'a, A>(
+        leading: &'a mut Self::Leading<A>,
+        trailing: &'a mut Self::Trailing<A>,
+    ) -> &'a mut A;
+}
+
+/// The [StringLayout] used by MSVC 2015 and later, where the allocator comes
+/// before the string's data.
+pub enum Msvc2015 {}
+
+impl StringLayout for Msvc2015 {
+    type Leading<A> = A;
+    type Trailing<A> = PhantomData<A>;
+
+    fn place<A>(allocator: A) -> (A, PhantomData<A>) {
+        (allocator, PhantomData)
+    }
+
+    fn allocator<'a, A>(leading: &'a A, _: &'a PhantomData<A>) -> &'a A {
+        leading
+    }
+
+    fn allocator_mut<'a, A>(leading: &'a mut A, _: &'a mut PhantomData<A>) -> &'a mut A {
+        leading
+    }
+}
+
+/// The [StringLayout] used by MSVC 2012, where the allocator comes after the
+/// string's data.
+pub enum Msvc2012 {}
+
+impl StringLayout for Msvc2012 {
+    type Leading<A> = PhantomData<A>;
+    type Trailing<A> = A;
+
+    fn place<A>(allocator: A) -> (PhantomData<A>, A) {
+        (PhantomData, allocator)
+    }
+
+    fn allocator<'a, A>(_: &'a PhantomData<A>, trailing: &'a A) -> &'a A {
+        trailing
+    }
+
+    fn allocator_mut<'a, A>(_: &'a mut PhantomData<A>, trailing: &'a mut A) -> &'a mut A {
+        trailing
+    }
 }
 
 pub trait CodeUnit: Copy + Default + 'static {
@@ -81,26 +155,27 @@ union StringBuffer<C: CodeUnit> {
     pointer: NonNull<C>,
 }
 
-impl<C, A> BasicString<C, A>
+impl<C, A, L> BasicString<C, A, L>
 where
     C: CodeUnit,
     A: Allocator,
+    L: StringLayout,
 {
     /// Creates an empty string backed by `allocator`.
     ///
     /// Equivalent to `std::basic_string<C>()` with a custom allocator.
     /// Starts in SSO mode with `capacity = SSO_CAP - 1`
     pub fn new_in(allocator: A) -> Self {
-        Self {
-            // SAFETY: A zero-initialized SSO buffer is valid, it represents
-            // an empty string. `capacity = SSO_CAP - 1` keeps us in SSO mode
-            buffer: StringBuffer {
+        // SAFETY: A zero-initialized SSO buffer is valid, it represents
+        // an empty string. `capacity = SSO_CAP - 1` keeps us in SSO mode
+        Self::from_parts(
+            StringBuffer {
                 inline: ManuallyDrop::new(C::InlineBuffer::default()),
             },
-            size: 0,
-            capacity: C::SSO_CAP - 1,
+            0,
+            C::SSO_CAP - 1,
             allocator,
-        }
+        )
     }
 
     /// Creates a string from a slice of code units, allocating if the
@@ -114,29 +189,47 @@ where
         if len < C::SSO_CAP {
             let mut sso = C::InlineBuffer::default();
             sso.as_mut()[..len].copy_from_slice(chars);
-            Self {
-                buffer: StringBuffer {
+            Self::from_parts(
+                StringBuffer {
                     inline: ManuallyDrop::new(sso),
                 },
-                size: len,
-                capacity: C::SSO_CAP - 1,
+                len,
+                C::SSO_CAP - 1,
                 allocator,
-            }
+            )
         } else {
             let ptr = allocator.allocate_n::<C>(len + 1).cast::<C>();
             unsafe {
                 std::ptr::copy_nonoverlapping(chars.as_ptr(), ptr.as_ptr(), len);
                 ptr.as_ptr().add(len).write(C::default());
             }
-            Self {
-                buffer: StringBuffer { pointer: ptr },
-                size: len,
-                capacity: len,
-                allocator,
-            }
+            Self::from_parts(StringBuffer { pointer: ptr }, len, len, allocator)
         }
     }
 
+    fn from_parts(buffer: StringBuffer<C>, size: usize, capacity: usize, allocator: A) -> Self {
+        let (leading_allocator, trailing_allocator) = L::place(allocator);
+        Self {
+            leading_allocator,
+            buffer,
+            size,
+            capacity,
+            trailing_allocator,
+        }
+    }
+
+    /// Returns the allocator this string allocates its buffer through.
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        L::allocator(&self.leading_allocator, &self.trailing_allocator)
+    }
+
+    /// Returns the allocator this string allocates its buffer through.
+    #[inline]
+    pub fn allocator_mut(&mut self) -> &mut A {
+        L::allocator_mut(&mut self.leading_allocator, &mut self.trailing_allocator)
+    }
+
     pub fn assign(&mut self, units: impl AsRef<[C]>) {
         self.clear();
         self.push_slice(units);
@@ -227,15 +320,18 @@ where
     }
 
     fn reallocate(&mut self, new_cap: usize) {
-        let new_ptr = self.allocator.allocate_n::<C>(new_cap + 1).cast::<C>();
+        let new_ptr = self
+            .allocator_mut()
+            .allocate_n::<C>(new_cap + 1)
+            .cast::<C>();
         // Copy existing data + NUL terminator in one shot
         unsafe {
             std::ptr::copy_nonoverlapping(self.as_ptr(), new_ptr.as_ptr(), self.size + 1);
         }
         if !self.is_sso() {
             unsafe {
-                self.allocator
-                    .deallocate_raw(self.buffer.pointer.as_ptr() as _)
+                let pointer = self.buffer.pointer.as_ptr();
+                self.allocator_mut().deallocate_raw(pointer as _)
             };
         }
         self.buffer.pointer = new_ptr;
@@ -301,10 +397,11 @@ where
     }
 }
 
-impl<C, A> BasicString<C, A>
+impl<C, A, L> BasicString<C, A, L>
 where
     C: CodeUnit + PartialEq,
     A: Allocator,
+    L: StringLayout,
 {
     /// Returns the index of the first occurrence of `needle`, or `None`.
     ///
@@ -373,7 +470,7 @@ where
     pub fn replacen<T: AsRef<[C]>>(&self, from: T, to: T, n: usize) -> Self {
         let from = from.as_ref();
         let to = to.as_ref();
-        let mut out = Self::new_in(self.allocator.clone());
+        let mut out = Self::new_in(self.allocator().clone());
         let src = self.as_code_units();
         let flen = from.len();
         if flen == 0 || flen > src.len() || n == 0 {
@@ -407,8 +504,8 @@ where
         let src = self.as_code_units();
         assert!(index <= src.len(), "split index out of bounds");
         (
-            Self::from_units_in(&src[..index], self.allocator.clone()),
-            Self::from_units_in(&src[index..], self.allocator.clone()),
+            Self::from_units_in(&src[..index], self.allocator().clone()),
+            Self::from_units_in(&src[index..], self.allocator().clone()),
         )
     }
 
@@ -421,21 +518,24 @@ where
         let dlen = delimiter.len();
         let mut parts = Vec::new();
         if dlen == 0 {
-            parts.push(Self::from_units_in(src, self.allocator.clone()));
+            parts.push(Self::from_units_in(src, self.allocator().clone()));
             return parts;
         }
         let mut start = 0;
         let mut i = 0;
         while i + dlen <= src.len() {
             if &src[i..i + dlen] == delimiter {
-                parts.push(Self::from_units_in(&src[start..i], self.allocator.clone()));
+                parts.push(Self::from_units_in(
+                    &src[start..i],
+                    self.allocator().clone(),
+                ));
                 i += dlen;
                 start = i;
             } else {
                 i += 1;
             }
         }
-        parts.push(Self::from_units_in(&src[start..], self.allocator.clone()));
+        parts.push(Self::from_units_in(&src[start..], self.allocator().clone()));
         parts
     }
 
@@ -444,7 +544,7 @@ where
     /// Same as [`str::repeat`]
     pub fn repeat(&self, n: usize) -> Self {
         let src = self.as_code_units();
-        let mut out = Self::new_in(self.allocator.clone());
+        let mut out = Self::new_in(self.allocator().clone());
         out.reserve(src.len() * n);
         for _ in 0..n {
             out.push_slice(src);
@@ -453,7 +553,7 @@ where
     }
 }
 
-impl<A: Allocator> BasicString<u8, A> {
+impl<A: Allocator, L: StringLayout> BasicString<u8, A, L> {
     /// Returns a new string with leading and trailing ASCII whitespace removed.
     ///
     /// Same as [`str::trim`]
@@ -470,7 +570,7 @@ impl<A: Allocator> BasicString<u8, A> {
             .unwrap_or(0);
         Self::from_units_in(
             if start <= end { &src[start..end] } else { &[] },
-            self.allocator.clone(),
+            self.allocator().clone(),
         )
     }
 
@@ -483,7 +583,7 @@ impl<A: Allocator> BasicString<u8, A> {
             .iter()
             .position(|&c| !c.is_ascii_whitespace())
             .unwrap_or(src.len());
-        Self::from_units_in(&src[start..], self.allocator.clone())
+        Self::from_units_in(&src[start..], self.allocator().clone())
     }
 
     /// Returns a new string with trailing ASCII whitespace removed.
@@ -496,7 +596,7 @@ impl<A: Allocator> BasicString<u8, A> {
             .rposition(|&c| !c.is_ascii_whitespace())
             .map(|i| i + 1)
             .unwrap_or(0);
-        Self::from_units_in(&src[..end], self.allocator.clone())
+        Self::from_units_in(&src[..end], self.allocator().clone())
     }
 
     /// Converts all ASCII letters to uppercase in place.
@@ -517,7 +617,7 @@ impl<A: Allocator> BasicString<u8, A> {
     ///
     /// Same as [`str::to_ascii_uppercase`]
     pub fn to_ascii_uppercase(&self) -> Self {
-        let mut s = Self::from_units_in(self.as_code_units(), self.allocator.clone());
+        let mut s = Self::from_units_in(self.as_code_units(), self.allocator().clone());
         s.make_ascii_uppercase();
         s
     }
@@ -526,7 +626,7 @@ impl<A: Allocator> BasicString<u8, A> {
     ///
     /// Same as [`str::to_ascii_lowercase`]
     pub fn to_ascii_lowercase(&self) -> Self {
-        let mut s = Self::from_units_in(self.as_code_units(), self.allocator.clone());
+        let mut s = Self::from_units_in(self.as_code_units(), self.allocator().clone());
         s.make_ascii_lowercase();
         s
     }
@@ -538,16 +638,17 @@ impl<A: Allocator> BasicString<u8, A> {
     }
 }
 
-impl<C, A> Drop for BasicString<C, A>
+impl<C, A, L> Drop for BasicString<C, A, L>
 where
     C: CodeUnit,
     A: Allocator,
+    L: StringLayout,
 {
     fn drop(&mut self) {
         if !self.is_sso() {
             unsafe {
-                self.allocator
-                    .deallocate_raw(self.buffer.pointer.as_ptr() as _)
+                let pointer = self.buffer.pointer.as_ptr();
+                self.allocator_mut().deallocate_raw(pointer as _)
             };
         }
     }
@@ -556,11 +657,12 @@ where
 /// Single generic impl covers: `&[C]`, `[C; N]`, `&[C; N]`, `Vec<C>`,
 /// other `BasicString` instances (cross-allocator), and for `C = u8`:
 /// `str`, `&str`, `String` all via their `AsRef<[u8]>` impls
-impl<C, A, S> PartialEq<S> for BasicString<C, A>
+impl<C, A, L, S> PartialEq<S> for BasicString<C, A, L>
 where
     C: CodeUnit + PartialEq,
     A: Allocator,
     S: AsRef<[C]>,
+    L: StringLayout,
 {
     #[inline]
     fn eq(&self, other: &S) -> bool {
@@ -568,18 +670,20 @@ where
     }
 }
 
-impl<C, A> Eq for BasicString<C, A>
+impl<C, A, L> Eq for BasicString<C, A, L>
 where
     C: CodeUnit + PartialEq,
     A: Allocator,
+    L: StringLayout,
 {
 }
 
-impl<C, A, S> PartialOrd<S> for BasicString<C, A>
+impl<C, A, L, S> PartialOrd<S> for BasicString<C, A, L>
 where
     C: CodeUnit + PartialEq + Ord,
     A: Allocator,
     S: AsRef<[C]>,
+    L: StringLayout,
 {
     #[inline]
     fn partial_cmp(&self, other: &S) -> Option<std::cmp::Ordering> {
@@ -587,10 +691,11 @@ where
     }
 }
 
-impl<C, A> Ord for BasicString<C, A>
+impl<C, A, L> Ord for BasicString<C, A, L>
 where
     C: CodeUnit + PartialEq + Ord,
     A: Allocator,
+    L: StringLayout,
 {
     #[inline]
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
@@ -600,17 +705,18 @@ where
 
 /// Hashes only the code units, so strings with identical content hash equally
 /// regardless of allocator, capacity, or SSO vs heap storage.
-impl<C, A> Hash for BasicString<C, A>
+impl<C, A, L> Hash for BasicString<C, A, L>
 where
     C: CodeUnit + Hash,
     A: Allocator,
+    L: StringLayout,
 {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.as_code_units().hash(state);
     }
 }
 
-impl<A: Allocator> fmt::Debug for BasicString<u8, A> {
+impl<A: Allocator, L: StringLayout> fmt::Debug for BasicString<u8, A, L> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match std::str::from_utf8(self.as_code_units()) {
             Ok(s) => write!(f, "{s:?}"),
@@ -619,7 +725,7 @@ impl<A: Allocator> fmt::Debug for BasicString<u8, A> {
     }
 }
 
-impl<A: Allocator> fmt::Display for BasicString<u8, A> {
+impl<A: Allocator, L: StringLayout> fmt::Display for BasicString<u8, A, L> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match std::str::from_utf8(self.as_code_units()) {
             Ok(s) => f.write_str(s),
@@ -628,7 +734,7 @@ impl<A: Allocator> fmt::Display for BasicString<u8, A> {
     }
 }
 
-impl<A: Allocator> fmt::Debug for BasicString<u16, A> {
+impl<A: Allocator, L: StringLayout> fmt::Debug for BasicString<u16, A, L> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "L\"")?;
         for r in char::decode_utf16(self.as_code_units().iter().cloned()) {
@@ -645,10 +751,11 @@ impl<A: Allocator> fmt::Debug for BasicString<u16, A> {
     }
 }
 
-impl<C, A> AsRef<[C]> for BasicString<C, A>
+impl<C, A, L> AsRef<[C]> for BasicString<C, A, L>
 where
     C: CodeUnit,
     A: Allocator,
+    L: StringLayout,
 {
     #[inline]
     fn as_ref(&self) -> &[C] {
@@ -656,10 +763,11 @@ where
     }
 }
 
-impl<C, A> std::borrow::Borrow<[C]> for BasicString<C, A>
+impl<C, A, L> std::borrow::Borrow<[C]> for BasicString<C, A, L>
 where
     C: CodeUnit,
     A: Allocator,
+    L: StringLayout,
 {
     #[inline]
     fn borrow(&self) -> &[C] {
@@ -1,7 +1,7 @@
 mod common;
 
 use common::StdAlloc;
-use fromsoftware_shared_stl::BasicString;
+use fromsoftware_shared_stl::{BasicString, Msvc2012, Msvc2015, StringLayout};
 use std::sync::atomic::AtomicUsize;
 
 fn std_alloc() -> StdAlloc {
@@ -282,3 +282,16 @@ fn string_drop_in_middle_of_scope() {
     drop(s2);
     assert_eq!(a.live_count(), 0);
 }
+
+#[test]
+fn string_allocator_position() {
+    fn allocator_offset<L: StringLayout>() -> usize {
+        let s = BasicString::<u8, StdAlloc, L>::new_in(std_alloc());
+        std::ptr::from_ref(s.allocator()).addr() - std::ptr::from_ref(&s).addr()
+    }
+
+    assert_eq!(0x28, size_of::<BasicString<u8, StdAlloc, Msvc2015>>());
+    assert_eq!(0x28, size_of::<BasicString<u8, StdAlloc, Msvc2012>>());
+    assert_eq!(0, allocator_offset::<Msvc2015>());
+    assert_eq!(0x20, allocator_offset::<Msvc2012>());
+}
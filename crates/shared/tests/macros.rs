@@ -0,0 +1,13 @@
+#[test]
+fn game_layout() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/game_layout/pass.rs");
+    t.compile_fail("tests/ui/game_layout/fail_*.rs");
+}
+
+#[test]
+fn singleton() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/singleton/pass.rs");
+    t.compile_fail("tests/ui/singleton/fail_*.rs");
+}
@@ -0,0 +1,10 @@
+use fromsoftware_shared::GameLayout;
+
+#[repr(C)]
+#[derive(GameLayout)]
+pub struct BadAttribute {
+    #[layout(align = 0x8)]
+    vftable: usize,
+}
+
+fn main() {}
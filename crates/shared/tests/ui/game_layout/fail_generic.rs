@@ -0,0 +1,9 @@
+use fromsoftware_shared::GameLayout;
+
+#[repr(C)]
+#[derive(GameLayout)]
+pub struct Generic<T> {
+    unk0: T,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use fromsoftware_shared::GameLayout;
+
+#[repr(C)]
+#[derive(GameLayout)]
+pub struct Misplaced {
+    vftable: usize,
+    unk10: u32,
+}
+
+fn main() {}
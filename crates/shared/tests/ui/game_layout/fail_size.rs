@@ -0,0 +1,10 @@
+use fromsoftware_shared::GameLayout;
+
+#[repr(C)]
+#[derive(GameLayout)]
+#[layout(size = 0x10)]
+pub struct TooSmall {
+    vftable: usize,
+}
+
+fn main() {}
@@ -0,0 +1,32 @@
+use fromsoftware_shared::GameLayout;
+
+#[repr(C)]
+#[derive(GameLayout)]
+#[layout(size = 0x18)]
+pub struct Named {
+    vftable: usize,
+    unk8: u32,
+    _unkc: u32,
+    #[layout(offset = 0x10)]
+    pub count: u64,
+}
+
+// Names that only look like offsets aren't checked.
+#[repr(C)]
+#[derive(GameLayout)]
+pub struct NotOffsets {
+    unk: u32,
+    unknown: u32,
+    unk_flags: u32,
+}
+
+// Lifetime parameters don't affect the layout.
+#[repr(C)]
+#[derive(GameLayout)]
+#[layout(size = 0x10)]
+pub struct WithLifetime<'a> {
+    unk0: u64,
+    unk8: &'a u64,
+}
+
+fn main() {}
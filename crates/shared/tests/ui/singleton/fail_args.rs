@@ -0,0 +1,25 @@
+use fromsoftware_shared::singleton;
+
+mod rva {
+    pub struct RvaBundle {
+        pub thing: u32,
+    }
+
+    pub fn get() -> RvaBundle {
+        RvaBundle { thing: 0 }
+    }
+}
+
+#[singleton]
+pub struct NoArgs;
+
+#[singleton(indirect)]
+pub struct IndirectWithoutRva;
+
+#[singleton("CSNamed", rva = thing, name = "Other")]
+pub struct TwoNames;
+
+#[singleton(rva = thing, offset = 8)]
+pub struct UnknownArg;
+
+fn main() {}
@@ -0,0 +1,6 @@
+use fromsoftware_shared::singleton;
+
+#[singleton("CSGeneric")]
+pub struct Generic<T>(T);
+
+fn main() {}
@@ -0,0 +1,34 @@
+use fromsoftware_shared::{FromSingleton, FromStatic, singleton};
+
+mod rva {
+    pub struct RvaBundle {
+        pub direct: u32,
+        pub indirect: u32,
+    }
+
+    pub fn get() -> RvaBundle {
+        RvaBundle {
+            direct: 0x1000,
+            indirect: 0x2000,
+        }
+    }
+}
+
+#[singleton("CSDlrfOnly")]
+pub struct DlrfOnly;
+
+#[singleton(rva = direct)]
+pub struct Direct;
+
+#[singleton(rva = indirect, indirect, name = "CSIndirect")]
+pub struct Indirect;
+
+#[singleton("CSFallback", rva = indirect, indirect)]
+pub struct Fallback;
+
+fn main() {
+    assert_eq!("CSDlrfOnly", <DlrfOnly as FromSingleton>::name());
+    assert_eq!("Direct", <Direct as FromStatic>::name());
+    assert_eq!("CSIndirect", <Indirect as FromStatic>::name());
+    assert_eq!("CSFallback", <Fallback as FromStatic>::name());
+}
@@ -72,7 +72,8 @@ pub unsafe extern "C" fn DllMain(_hmodule: usize, reason: u32) -> bool {
             },
             // Specify the task group in which physics calculations are already done.
             CSTaskGroupIndex::ChrIns_PostPhysics,
-        );
+        )
+        .detach();
     });
 
     // Signal that DllMain executed successfully
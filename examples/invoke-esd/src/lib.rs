@@ -241,7 +241,8 @@ pub unsafe extern "C" fn DllMain(_hmodule: u64, reason: u32) -> bool {
                 }
             },
             CSTaskGroupIndex::FrameBegin,
-        );
+        )
+        .detach();
     });
 
     true
@@ -68,7 +68,8 @@ pub unsafe extern "C" fn DllMain(_hmodule: usize, reason: u32) -> bool {
                 );
             },
             CSTaskGroupIndex::ChrIns_PostPhysics,
-        );
+        )
+        .detach();
     });
 
     true
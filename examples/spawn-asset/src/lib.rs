@@ -56,7 +56,8 @@ pub unsafe extern "C" fn DllMain(_hmodule: usize, reason: u32) -> bool {
                 );
             },
             CSTaskGroupIndex::ChrIns_PostPhysics,
-        );
+        )
+        .detach();
     });
 
     true
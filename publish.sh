@@ -1,7 +1,7 @@
 #!/bin/bash
 cargo publish --allow-dirty --no-verify -p fromsoftware-shared-macros
-cargo publish --allow-dirty --no-verify -p fromsoftware-shared
 cargo publish --allow-dirty --no-verify -p fromsoftware-shared-stl
+cargo publish --allow-dirty --no-verify -p fromsoftware-shared
 cargo publish --allow-dirty --no-verify -p eldenring
 cargo publish --allow-dirty --no-verify -p nightreign
 cargo publish --allow-dirty --no-verify -p darksouls3